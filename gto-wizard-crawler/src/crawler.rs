@@ -1,65 +1,131 @@
-use std::{
-    fmt::Write,
-    io::ErrorKind,
-    path::{Path, PathBuf},
-    str::FromStr,
-    sync::Arc,
-    time::Duration,
-};
+use std::{collections::VecDeque, path::PathBuf, str::FromStr, sync::Arc};
 
-use rand::Rng;
-use reqwest::Client;
-use reqwest::{
-    header::{
-        HeaderMap, HeaderValue, ACCEPT, ACCEPT_ENCODING, ACCEPT_LANGUAGE, CONNECTION, CONTENT_TYPE,
-        ORIGIN, REFERER, USER_AGENT,
-    },
-    StatusCode,
-};
-use serde::Deserialize;
-use serde_json::json;
+use futures_util::{stream::FuturesUnordered, StreamExt};
+use serde::{Deserialize, Serialize};
 
 use poker_core::{
+    card::Card,
     game::{milli_big_blind_from_f64, Game, MilliBigBlind},
+    hand::Hand,
     range::{
-        PreFlopAction, PreFlopRangeAction, PreFlopRangeConfig, PreFlopRangeConfigData,
-        PreFlopRangeConfigEntry, PreFlopRangeConfigEntryData, PreFlopRangeTable,
-        PreFlopRangeTableWith, RangeEntry,
+        PostFlopRangeConfig, PostFlopRangeConfigData, PostFlopRangeConfigEntry,
+        PostFlopRangeConfigEntryData, PreFlopAction, PreFlopRangeAction, PreFlopRangeConfig,
+        PreFlopRangeConfigData, PreFlopRangeConfigEntry, PreFlopRangeConfigEntryData,
+        PreFlopRangeTable, PreFlopRangeTableWith, RangeAction, RangeActionKind, RangeConfigEntry,
+        RangeEntry, RangeTableWith, MAX_FREQUENCY,
     },
     result::Result,
 };
-use tokio::{fs, time::sleep};
-use url::Url;
+use tokio::fs;
+
+use crate::{
+    queue::Queue,
+    source::{
+        url_encode_pre_flop_actions, HttpRangeSource, PlayerInfo, RangeSource, RawRangeData, Spot,
+    },
+};
 
 #[derive(Debug, Deserialize)]
 pub struct Config {
-    gw_client_id: String,
-    bearer_token: String,
-    refresh_token: String,
-    game_type: String,
-    max_players: usize,
-    depth: MilliBigBlind,
-    min_frequency: f64,
-    max_calls: usize,
-    out_dir: String,
+    pub(crate) gw_client_id: String,
+    pub(crate) bearer_token: String,
+    pub(crate) refresh_token: String,
+    #[serde(default = "Config::default_base_url")]
+    pub(crate) base_url: String,
+    pub(crate) game_type: String,
+    pub(crate) max_players: usize,
+    pub(crate) depth: MilliBigBlind,
+    pub(crate) min_frequency: f64,
+    pub(crate) max_calls: usize,
+    pub(crate) out_dir: String,
+    /// The number of spots requested at the same time.
+    #[serde(default = "Config::default_concurrency")]
+    pub(crate) concurrency: usize,
+    /// The average request rate, bursts of `burst` requests are allowed.
+    #[serde(default = "Config::default_requests_per_second")]
+    pub(crate) requests_per_second: f64,
+    #[serde(default = "Config::default_burst")]
+    pub(crate) burst: u32,
+    /// Retries of throttled or failed requests, the backoff doubles every time.
+    #[serde(default = "Config::default_max_retries")]
+    pub(crate) max_retries: u32,
+    #[serde(default = "Config::default_initial_backoff_ms")]
+    pub(crate) initial_backoff_ms: u64,
+    /// Crawls flop spots after a pre flop line instead of the pre flop tree.
+    #[serde(default)]
+    pub(crate) post_flop: Option<PostFlopConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PostFlopConfig {
+    pub(crate) pre_flop_actions: Vec<PreFlopAction>,
+    /// E.g. `AhKd7c`.
+    pub(crate) flops: Vec<String>,
 }
 
-pub struct Crawler {
-    gw_client_id: String,
-    bearer_token: String,
-    refresh_token: String,
+impl Config {
+    fn default_base_url() -> String {
+        "https://api.gtowizard.com".to_string()
+    }
+
+    fn default_concurrency() -> usize {
+        4
+    }
+
+    fn default_requests_per_second() -> f64 {
+        0.5
+    }
+
+    fn default_burst() -> u32 {
+        1
+    }
+
+    fn default_max_retries() -> u32 {
+        5
+    }
+
+    fn default_initial_backoff_ms() -> u64 {
+        2_000
+    }
+}
+
+pub struct Crawler<S = HttpRangeSource> {
+    source: S,
     game_type: String,
     max_players: usize,
     depth: MilliBigBlind,
     min_frequency: f64,
     max_calls: usize,
     out_dir: String,
+    concurrency: usize,
+    post_flop: Option<PostFlopTree>,
+}
+
+struct PostFlopTree {
+    pre_flop_actions: Vec<PreFlopAction>,
+    flops: Vec<Vec<Card>>,
+}
+
+/// A flop spot in the post flop queue.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct PostFlopNode {
+    board: Vec<Card>,
+    actions: Vec<RangeActionKind>,
 }
 
 impl Crawler {
+    pub async fn new(config: Config) -> Result<Self> {
+        let source = HttpRangeSource::new(&config)?;
+        Self::with_source(config, source).await
+    }
+}
+
+impl<S: RangeSource> Crawler<S> {
     const RANGE_FILE_PREFIX: &str = "range_";
+    const POST_FLOP_RANGE_FILE_PREFIX: &str = "post_flop_range_";
+    const POST_FLOP_JOURNAL_FILE: &str = "queue_post_flop.jsonl";
 
-    pub async fn new(config: Config) -> Result<Self> {
+    pub async fn with_source(config: Config, source: S) -> Result<Self> {
         if config.depth < 1_000 || config.depth % 1000 != 0 {
             return Err("crawler: invalid depth".into());
         }
@@ -72,258 +138,148 @@ impl Crawler {
         {
             return Err("crawler: invalid min frequency".into());
         }
+        if config.concurrency == 0 {
+            return Err("crawler: invalid concurrency".into());
+        }
+
+        let post_flop = match config.post_flop {
+            Some(post_flop) => {
+                let flops = post_flop
+                    .flops
+                    .iter()
+                    .map(|flop| Self::parse_flop(flop))
+                    .collect::<Result<Vec<_>>>()?;
+                for flop in &flops {
+                    PostFlopRangeConfigEntry::build_game(
+                        config.max_players,
+                        config.depth,
+                        Self::SMALL_BLIND,
+                        &post_flop.pre_flop_actions,
+                        flop,
+                        &[],
+                    )
+                    .map_err(|err| format!("crawler: invalid post flop line: {err}"))?;
+                }
+
+                Some(PostFlopTree {
+                    pre_flop_actions: post_flop.pre_flop_actions,
+                    flops,
+                })
+            }
+            None => None,
+        };
 
         let crawler = Self {
-            gw_client_id: config.gw_client_id,
-            bearer_token: config.bearer_token,
-            refresh_token: config.refresh_token,
+            source,
             game_type: config.game_type,
             max_players: config.max_players,
             depth: config.depth,
             min_frequency: config.min_frequency,
             max_calls: config.max_calls,
             out_dir: config.out_dir,
+            concurrency: config.concurrency,
+            post_flop,
         };
         Ok(crawler)
     }
 
-    async fn read_queue(&self) -> Result<Vec<Vec<PreFlopAction>>> {
-        match fs::read_to_string(self.queue_path()).await {
-            Ok(queue_raw) => {
-                let queue: Vec<Vec<PreFlopAction>> = serde_json::from_str(&queue_raw)?;
-                Ok(queue)
-            }
-            Err(err) if err.kind() == ErrorKind::NotFound => {
-                eprintln!("Creating new queue file...");
-
-                let queue: Vec<Vec<PreFlopAction>> = vec![vec![]];
-                self.write_queue(&queue).await?;
-                Ok(queue)
-            }
-            Err(err) => Err(err.into()),
+    fn parse_flop(flop: &str) -> Result<Vec<Card>> {
+        let cards = flop
+            .as_bytes()
+            .chunks(2)
+            .map(Card::from_bytes)
+            .collect::<Result<Vec<_>>>()?;
+        if cards.len() != 3 {
+            return Err(format!("crawler: invalid flop '{flop}'").into());
         }
+        Ok(cards)
     }
 
-    async fn write_queue(&self, queue: &[Vec<PreFlopAction>]) -> Result<()> {
-        fs::write(self.queue_path(), serde_json::to_string_pretty(&queue)?).await?;
-        Ok(())
-    }
-
-    fn queue_path(&self) -> Box<Path> {
-        PathBuf::from(&self.out_dir)
-            .join("queue.json")
-            .into_boxed_path()
-    }
-
-    async fn queue_pop_and_push_next(
-        &self,
-        pop: &[PreFlopAction],
-        mut next: Vec<Vec<PreFlopAction>>,
-    ) -> Result<()> {
-        let queue = self.read_queue().await?;
-        if queue.first().is_none() || queue.first().is_some_and(|popped| popped != pop) {
-            return Err("queue: unexpected item at front of the queue".into());
+    /// Crawls all queued spots, then writes the full config.
+    /// Spots are requested concurrently and stored in the order they arrive,
+    /// an interrupted crawl continues with the spots left in the queue.
+    pub async fn crawl(self) -> Result<()> {
+        if let Some(post_flop) = &self.post_flop {
+            return self.crawl_post_flop(post_flop).await;
         }
 
-        next.extend(queue.into_iter().skip(1));
-        self.write_queue(&next).await?;
+        let mut queue = Queue::open(&PathBuf::from(&self.out_dir)).await?;
+        let mut waiting: VecDeque<_> = queue.pending().iter().cloned().collect();
+        let mut in_flight = FuturesUnordered::new();
 
-        Ok(())
-    }
-
-    pub async fn crawl(mut self) -> Result<()> {
         loop {
-            let queue = self.read_queue().await?;
-            let Some(next_pre_flop_actions) = queue.first() else {
-                eprintln!("Queue is empty, nothing to do. Writing full config...");
-                self.write_full_config().await?;
-                return Ok(());
+            while in_flight.len() < self.concurrency {
+                let Some(pre_flop_actions) = waiting.pop_front() else {
+                    break;
+                };
+                let spot = Spot::pre_flop(pre_flop_actions.clone());
+                in_flight.push(self.get_raw_range(pre_flop_actions, spot));
+            }
+
+            let Some((pre_flop_actions, raw_range)) = in_flight.next().await else {
+                break;
             };
+            let entry = self.parse_raw_range(pre_flop_actions.clone(), raw_range?)?;
+            let next_actions = self.store_range(&entry).await?;
 
-            self.get_and_parse_range(next_pre_flop_actions.clone())
+            queue
+                .complete(&pre_flop_actions, next_actions.clone())
                 .await?;
-
-            sleep(Duration::from_secs(rand::thread_rng().gen_range(1..=3))).await;
-        }
-    }
-
-    async fn get_and_parse_range(&mut self, pre_flop_actions: Vec<PreFlopAction>) -> Result<()> {
-        eprintln!("Processing {pre_flop_actions:?}...");
-
-        let raw_range = self.get_raw_range(&pre_flop_actions).await?;
-        let entry = self.parse_raw_range(pre_flop_actions, raw_range)?;
-
-        self.store_range(entry).await?;
-        Ok(())
-    }
-
-    async fn get_raw_range(&mut self, pre_flop_actions: &[PreFlopAction]) -> Result<RawRangeData> {
-        match self.get_raw_range_inner(pre_flop_actions).await {
-            Ok(range) => Ok(range),
-            Err(err) => {
-                let err: Box<reqwest::Error> = err.downcast()?;
-                if err
-                    .status()
-                    .is_some_and(|status| status == StatusCode::UNAUTHORIZED)
-                {
-                    self.renew_bearer_token().await?;
-                    self.get_raw_range_inner(pre_flop_actions).await
-                } else {
-                    Err(err.into())
-                }
-            }
+            waiting.extend(next_actions);
+            eprintln!("{} spots queued.", queue.pending().len());
         }
-    }
 
-    async fn get_raw_range_inner(
-        &self,
-        pre_flop_actions: &[PreFlopAction],
-    ) -> Result<RawRangeData> {
-        let url = self.build_range_url(pre_flop_actions)?;
-        let headers = self.build_headers()?;
-        let client = Client::builder().default_headers(headers).build()?;
-        let response = client.get(url).send().await?.error_for_status()?;
-        let body: RawRangeData = response.json().await?;
-        Ok(body)
+        eprintln!("Queue is empty, nothing to do. Writing full config...");
+        self.write_full_config().await
     }
 
-    fn build_range_url(&self, pre_flop_actions: &[PreFlopAction]) -> Result<Url> {
-        let pre_flop_actions_formatted = self.url_encode_pre_flop_actions(pre_flop_actions)?;
-        let depth = self.depth / 1000;
-        let params: &[(&str, &str)] = &[
-            ("gametype", &self.game_type),
-            ("depth", &depth.to_string()),
-            ("stacks", ""),
-            ("preflop_actions", &pre_flop_actions_formatted),
-            ("flop_actions", ""),
-            ("turn_actions", ""),
-            ("river_actions", ""),
-            ("board", ""),
-        ];
-        let url = Url::parse_with_params(
-            "https://api.gtowizard.com/v4/solutions/spot-solution/",
-            params,
-        )?;
-        Ok(url)
-    }
-
-    fn build_headers(&self) -> Result<HeaderMap<HeaderValue>> {
-        let mut headers = HeaderMap::new();
-
-        headers.insert(
-            USER_AGENT,
-            HeaderValue::from_static(
-                "Mozilla/5.0 (X11; Linux x86_64; rv:139.0) Gecko/20100101 Firefox/139.0",
-            ),
-        );
-        headers.insert(
-            ACCEPT,
-            HeaderValue::from_static("application/json, text/plain, */*"),
-        );
-        headers.insert(ACCEPT_LANGUAGE, HeaderValue::from_static("en-US,en;q=0.5"));
-        headers.insert(
-            ACCEPT_ENCODING,
-            HeaderValue::from_static("gzip, deflate, br, zstd"),
-        );
-        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
-        headers.insert(
-            REFERER,
-            HeaderValue::from_static("https://app.gtowizard.com/"),
-        );
-        headers.insert("GWCLIENTID", self.gw_client_id.parse()?);
-        headers.insert(
-            ORIGIN,
-            HeaderValue::from_static("https://app.gtowizard.com"),
-        );
-        headers.insert("DNT", HeaderValue::from_static("1"));
-        headers.insert("Sec-Fetch-Dest", HeaderValue::from_static("empty"));
-        headers.insert("Sec-Fetch-Mode", HeaderValue::from_static("cors"));
-        headers.insert("Sec-Fetch-Site", HeaderValue::from_static("same-site"));
-        headers.insert(
-            "Authorization",
-            format!("Bearer {}", self.bearer_token).parse()?,
-        );
-        headers.insert(CONNECTION, HeaderValue::from_static("keep-alive"));
-        headers.insert("Priority", HeaderValue::from_static("u=0"));
-
-        Ok(headers)
-    }
-
-    fn url_encode_pre_flop_actions(&self, pre_flop_actions: &[PreFlopAction]) -> Result<String> {
-        let mut pre_flop_actions_formatted = String::new();
-
-        for (index, action) in pre_flop_actions.iter().copied().enumerate() {
-            match action {
-                PreFlopAction::Post { .. } => {
-                    return Err("pre flop actions: additional post unsupported".into());
-                }
-                PreFlopAction::Straddle { .. } => {
-                    return Err("pre flop actions: straddle unsupported".into());
-                }
-                PreFlopAction::Fold => pre_flop_actions_formatted.write_char('F')?,
-                PreFlopAction::Check => pre_flop_actions_formatted.write_char('X')?,
-                PreFlopAction::Call => pre_flop_actions_formatted.write_char('C')?,
-                PreFlopAction::Raise(to) => {
-                    let raise_formatted = &self.format_raise_in_url(to)?;
-                    pre_flop_actions_formatted.write_str(&raise_formatted)?
-                }
-            }
+    /// Crawls the flop tree of every flop, in its own queue.
+    async fn crawl_post_flop(&self, tree: &PostFlopTree) -> Result<()> {
+        let initial = tree
+            .flops
+            .iter()
+            .map(|flop| PostFlopNode {
+                board: flop.clone(),
+                actions: Vec::new(),
+            })
+            .collect();
+        let journal_path = PathBuf::from(&self.out_dir).join(Self::POST_FLOP_JOURNAL_FILE);
+        let mut queue = Queue::open_journal(&journal_path, async { Ok(initial) }).await?;
+        let mut waiting: VecDeque<_> = queue.pending().iter().cloned().collect();
+        let mut in_flight = FuturesUnordered::new();
 
-            if index < pre_flop_actions.len() - 1 {
-                pre_flop_actions_formatted.write_char('-')?;
+        loop {
+            while in_flight.len() < self.concurrency {
+                let Some(node) = waiting.pop_front() else {
+                    break;
+                };
+                let spot = Spot {
+                    pre_flop_actions: tree.pre_flop_actions.clone(),
+                    board: node.board.clone(),
+                    post_flop_actions: node.actions.clone(),
+                };
+                in_flight.push(self.get_raw_range(node, spot));
             }
-        }
 
-        Ok(pre_flop_actions_formatted)
-    }
-
-    fn format_raise_in_url(&self, to: MilliBigBlind) -> Result<String> {
-        if to < 2_000 {
-            return Err("pre flop actions: raise too small".into());
-        }
+            let Some((node, raw_range)) = in_flight.next().await else {
+                break;
+            };
+            let entry = self.parse_raw_post_flop_range(tree, node.clone(), raw_range?)?;
+            let next_nodes = self.store_post_flop_range(tree, &entry).await?;
 
-        if to == self.depth {
-            return Ok("RAI".to_string());
+            queue.complete(&node, next_nodes.clone()).await?;
+            waiting.extend(next_nodes);
+            eprintln!("{} spots queued.", queue.pending().len());
         }
 
-        let full_blinds = to / 1_000;
-        let blind_fraction = to % 1_000;
-        let blind_fraction = blind_fraction.to_string();
-        let blind_fraction = blind_fraction.trim_end_matches('0');
-
-        if blind_fraction.is_empty() {
-            return Ok(format!("R{full_blinds}"));
-        } else {
-            return Ok(format!("R{full_blinds}.{blind_fraction}"));
-        }
+        eprintln!("Queue is empty, nothing to do. Writing full post flop config...");
+        self.write_full_post_flop_config(tree).await
     }
 
-    async fn renew_bearer_token(&mut self) -> Result<()> {
-        eprintln!("Renewing bearer token...");
-
-        let url = "https://api.gtowizard.com/v1/token/refresh/";
-        let headers = self.build_headers()?;
-        let client = reqwest::Client::builder()
-            .default_headers(headers)
-            .build()?;
-
-        let body = json!({
-            "refresh": self.refresh_token,
-        });
-
-        let response = client
-            .post(url)
-            .json(&body)
-            .send()
-            .await?
-            .error_for_status()?;
-
-        let body = response.text().await?;
-        let new_token: TokenRefresh = serde_json::from_str(&body)?;
-        self.bearer_token = new_token.access;
-
-        eprintln!("Updated bearer token to: {}", self.bearer_token);
-        Ok(())
+    async fn get_raw_range<T>(&self, node: T, spot: Spot) -> (T, Result<RawRangeData>) {
+        eprintln!("Processing {spot:?}...");
+        let raw_range = self.source.get_raw_range(&spot).await;
+        (node, raw_range)
     }
 
     fn parse_raw_range(
@@ -407,46 +363,142 @@ impl Crawler {
             self.max_players,
             self.depth,
             self.small_blind(),
-            &pre_flop_actions,
+            pre_flop_actions,
         )?;
 
-        let Some(current_player) = current_game.current_player() else {
+        let current_player = Self::current_player_info(&current_game, raw_range)?;
+        let total_range = convert_frequency_array_to_range(&current_player.range)?;
+        Ok(total_range)
+    }
+
+    fn current_player_info<'a>(game: &Game, raw_range: &'a RawRangeData) -> Result<&'a PlayerInfo> {
+        let Some(current_player) = game.current_player() else {
             return Err("parse raw range: internal error while computing current player".into());
         };
-        let player_position = Game::position_name(
-            current_game.player_count(),
-            current_game.button_index(),
-            current_player,
-        )
-        .unwrap()
-        .0;
+        let player_position =
+            Game::position_name(game.player_count(), game.button_index(), current_player)
+                .unwrap()
+                .0;
 
         let current_player = raw_range
             .players_info
             .iter()
-            .filter(|player_info| player_info.player.position == player_position)
-            .next();
+            .find(|player_info| player_info.player.position == player_position);
         let Some(current_player) = current_player else {
             return Err("parse raw range: current player not found".into());
         };
 
-        let total_range = convert_frequency_array_to_range(&current_player.range)?;
-        Ok(total_range)
+        Ok(current_player)
+    }
+
+    fn parse_raw_post_flop_range(
+        &self,
+        tree: &PostFlopTree,
+        node: PostFlopNode,
+        raw_range: RawRangeData,
+    ) -> Result<PostFlopRangeConfigEntry> {
+        let current_game = PostFlopRangeConfigEntry::build_game(
+            self.max_players,
+            self.depth,
+            self.small_blind(),
+            &tree.pre_flop_actions,
+            &node.board,
+            &node.actions,
+        )?;
+
+        let current_player = Self::current_player_info(&current_game, &raw_range)?;
+        let total_range = convert_combo_frequency_array(&current_player.range)?;
+
+        let mut kinds = Vec::new();
+        let mut ranges = Vec::new();
+        let mut evs = Vec::new();
+        for action_solution in raw_range.action_solutions {
+            let bet_size = || -> Result<MilliBigBlind> {
+                let Some(bet_size) = &action_solution.action.bet_size else {
+                    return Err("parse raw range: bet_size missing with type bet or raise".into());
+                };
+                milli_big_blind_from_f64(bet_size.parse()?)
+            };
+            let kind = match action_solution.action.action_type.as_str() {
+                "FOLD" => RangeActionKind::Fold,
+                "CHECK" => RangeActionKind::Check,
+                "CALL" => RangeActionKind::Call,
+                "BET" => RangeActionKind::Bet(bet_size()?),
+                "RAISE" => RangeActionKind::Raise(bet_size()?),
+                _ => return Err("parse raw range: unknown strategy type".into()),
+            };
+
+            if action_solution.evs.len() != Hand::COUNT {
+                return Err("parse raw range: ev array has bad length".into());
+            }
+            let mut ev = RangeTableWith::default();
+            for (current_ev, hand) in action_solution
+                .evs
+                .iter()
+                .copied()
+                .zip(combos_ordered_by_gto_wizard_api())
+            {
+                ev[hand] = milli_big_blind_from_f64(current_ev)?;
+            }
+
+            kinds.push(kind);
+            ranges.push(convert_combo_frequency_array(&action_solution.strategy)?);
+            evs.push(ev);
+        }
+
+        round_up_combo_frequencies(&kinds, &mut ranges);
+
+        let actions = kinds
+            .into_iter()
+            .zip(ranges)
+            .zip(evs)
+            .map(|((kind, range), ev)| RangeAction::with_ev(kind, &total_range, range, Some(ev)))
+            .collect();
+
+        eprintln!("Parsed range, validating...");
+
+        let entry = PostFlopRangeConfigEntry::new(
+            node.board,
+            node.actions,
+            RangeConfigEntry::new(total_range, actions)?,
+            self.max_players,
+            self.depth,
+            self.small_blind(),
+            &tree.pre_flop_actions,
+        )?;
+
+        eprintln!(
+            "Validated range: {:?}",
+            entry
+                .entry()
+                .action_kinds()
+                .map(|action| (action, entry.entry().frequency(action) * 100.0))
+                .collect::<Vec<_>>()
+        );
+
+        Ok(entry)
     }
 
-    async fn store_range(&self, entry: PreFlopRangeConfigEntry) -> Result<()> {
+    /// Writes the range and returns the spots after it.
+    async fn store_range(
+        &self,
+        entry: &PreFlopRangeConfigEntry,
+    ) -> Result<Vec<Vec<PreFlopAction>>> {
         eprintln!("Storing progress...");
 
-        let range_path = PathBuf::from(&self.out_dir).join(format!(
+        let file_name = format!(
             "{}{}.json",
             Self::RANGE_FILE_PREFIX,
-            self.url_encode_pre_flop_actions(entry.previous_actions())?
-        ));
+            url_encode_pre_flop_actions(entry.previous_actions(), self.depth)?
+        );
+        // Renaming is atomic, a crash never leaves a partial range.
+        let temp_path = PathBuf::from(&self.out_dir).join(format!("tmp_{file_name}"));
         fs::write(
-            range_path,
+            &temp_path,
             serde_json::to_string_pretty(&entry.clone().to_data())?,
         )
         .await?;
+        fs::rename(temp_path, PathBuf::from(&self.out_dir).join(file_name)).await?;
 
         let possible_next_actions = entry.possible_next_actions(
             self.max_players,
@@ -474,14 +526,64 @@ impl Crawler {
             })
             .collect();
 
-        self.queue_pop_and_push_next(entry.previous_actions(), next_actions)
-            .await?;
-        Ok(())
+        Ok(next_actions)
+    }
+
+    /// Writes the range and returns the spots after it on the same street.
+    async fn store_post_flop_range(
+        &self,
+        tree: &PostFlopTree,
+        entry: &PostFlopRangeConfigEntry,
+    ) -> Result<Vec<PostFlopNode>> {
+        eprintln!("Storing progress...");
+
+        let spot = Spot {
+            pre_flop_actions: tree.pre_flop_actions.clone(),
+            board: entry.board().to_vec(),
+            post_flop_actions: entry.previous_actions().to_vec(),
+        };
+        let file_name = format!(
+            "{}{}.json",
+            Self::POST_FLOP_RANGE_FILE_PREFIX,
+            spot.encode(self.depth)?
+        );
+        // Renaming is atomic, a crash never leaves a partial range.
+        let temp_path = PathBuf::from(&self.out_dir).join(format!("tmp_{file_name}"));
+        fs::write(
+            &temp_path,
+            serde_json::to_string_pretty(&entry.clone().to_data())?,
+        )
+        .await?;
+        fs::rename(temp_path, PathBuf::from(&self.out_dir).join(file_name)).await?;
+
+        let possible_next_actions = entry.possible_next_actions(
+            self.max_players,
+            self.depth,
+            self.small_blind(),
+            &tree.pre_flop_actions,
+            self.min_frequency,
+        )?;
+
+        let next_nodes = possible_next_actions
+            .into_iter()
+            .map(|action| {
+                let mut actions = entry.previous_actions().to_vec();
+                actions.push(action);
+                PostFlopNode {
+                    board: entry.board().to_vec(),
+                    actions,
+                }
+            })
+            .collect();
+
+        Ok(next_nodes)
     }
 
+    const SMALL_BLIND: MilliBigBlind = 500;
+
     fn small_blind(&self) -> MilliBigBlind {
         // Assume small blind is always half the big blind.
-        500
+        Self::SMALL_BLIND
     }
 
     async fn write_full_config(&self) -> Result<()> {
@@ -528,43 +630,58 @@ impl Crawler {
 
         Ok(config)
     }
-}
 
-#[derive(Debug, Deserialize)]
-struct RawRangeData {
-    action_solutions: Vec<ActionSolution>,
-    players_info: Vec<PlayerInfo>,
-}
+    async fn write_full_post_flop_config(&self, tree: &PostFlopTree) -> Result<()> {
+        let mut out_dir = fs::read_dir(&self.out_dir).await?;
+        let mut spots = Vec::new();
 
-#[derive(Debug, Deserialize)]
-struct ActionSolution {
-    action: Action,
-    strategy: Vec<f64>,
-    evs: Vec<f64>,
-}
+        while let Some(entry) = out_dir.next_entry().await? {
+            let is_range = entry.path().file_name().is_some_and(|file_name| {
+                file_name
+                    .as_encoded_bytes()
+                    .starts_with(Self::POST_FLOP_RANGE_FILE_PREFIX.as_bytes())
+            });
+            if !is_range {
+                continue;
+            }
 
-#[derive(Debug, Deserialize)]
-struct Action {
-    #[serde(rename = "type")]
-    action_type: String,
-    #[serde(rename = "betsize")]
-    bet_size: Option<String>,
-}
+            let range_content = fs::read_to_string(entry.path()).await?;
+            let spot: PostFlopRangeConfigEntryData = serde_json::from_str(&range_content)?;
 
-#[derive(Debug, Deserialize)]
-struct PlayerInfo {
-    player: Player,
-    range: Vec<f64>,
-}
+            spots.push(spot);
+        }
 
-#[derive(Debug, Deserialize)]
-struct Player {
-    position: String,
-}
+        spots.sort_by(|a, b| {
+            let board = |spot: &PostFlopRangeConfigEntryData| {
+                spot.board
+                    .iter()
+                    .map(|card| card.to_index())
+                    .collect::<Vec<_>>()
+            };
+            board(a)
+                .cmp(&board(b))
+                .then_with(|| a.previous_actions.cmp(&b.previous_actions))
+        });
 
-#[derive(Debug, Deserialize)]
-struct TokenRefresh {
-    access: String,
+        let config = PostFlopRangeConfigData {
+            description: Some(Arc::new(self.game_type.clone())),
+            max_players: self.max_players,
+            depth: self.depth,
+            small_blind: self.small_blind(),
+            pre_flop_actions: tree.pre_flop_actions.clone(),
+            spots,
+        };
+        let config = PostFlopRangeConfig::from_data(config)?;
+        let config_serialized = serde_json::to_string_pretty(&config.to_data())?;
+
+        let config_path = PathBuf::from(&self.out_dir).join(format!(
+            "config_post_flop_{}_{}.json",
+            self.game_type, self.depth
+        ));
+        fs::write(config_path, config_serialized).await?;
+
+        Ok(())
+    }
 }
 
 const HANDS_ORDERED_BY_GTO_WIZARD_API: &[&str] = &[
@@ -615,3 +732,64 @@ fn convert_frequency(frequency: f64) -> Result<u16> {
     let frequency = frequency * 10_000.0;
     Ok(frequency.trunc() as u16)
 }
+
+/// Post flop arrays have one value per combo. Cards are ordered by rank from deuce
+/// to ace and then clubs, diamonds, hearts and spades, combos by their higher and
+/// then their lower card, e.g. `2d2c`, `2h2c`, `2h2d`, .., `AsAh`.
+fn combos_ordered_by_gto_wizard_api() -> impl Iterator<Item = Hand> {
+    let cards: Vec<Card> = "23456789TJQKA"
+        .bytes()
+        .flat_map(|rank| "cdhs".bytes().map(move |suit| [rank, suit]))
+        .map(|card| Card::from_bytes(&card).unwrap())
+        .collect();
+
+    (0..cards.len()).flat_map(move |high| {
+        let cards = cards.clone();
+        (0..high).map(move |low| Hand::of_two_cards(cards[high], cards[low]).unwrap())
+    })
+}
+
+fn convert_combo_frequency_array(frequencies: &[f64]) -> Result<RangeTableWith<u16>> {
+    if frequencies.len() != Hand::COUNT {
+        return Err("parse raw range: bad frequency array length".into());
+    }
+
+    let mut range = RangeTableWith::default();
+
+    for (mut strategy, hand) in frequencies
+        .iter()
+        .copied()
+        .zip(combos_ordered_by_gto_wizard_api())
+    {
+        // Sometimes the GTO Wizard frequency is a litter bigger than 1.0.
+        if strategy > 1.0 && strategy <= 1.01 {
+            strategy = 1.0;
+        }
+
+        range[hand] = convert_frequency(strategy)?;
+    }
+
+    Ok(range)
+}
+
+/// Adds the frequency lost by truncation to the most frequent action,
+/// combos without any action (e.g. blocked by the board) fold if possible.
+fn round_up_combo_frequencies(kinds: &[RangeActionKind], ranges: &mut [RangeTableWith<u16>]) {
+    let fold = kinds.iter().position(|kind| *kind == RangeActionKind::Fold);
+
+    for hand in Hand::all() {
+        let total: u16 = ranges.iter().map(|range| range[hand]).sum();
+        if total >= MAX_FREQUENCY {
+            continue;
+        }
+
+        let index = if total == 0 {
+            fold
+        } else {
+            (0..ranges.len()).max_by_key(|index| ranges[*index][hand])
+        };
+        if let Some(index) = index {
+            ranges[index][hand] += MAX_FREQUENCY - total;
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub mod crawler;
+pub mod queue;
+pub mod rate_limit;
+pub mod source;
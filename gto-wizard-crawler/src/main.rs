@@ -1,11 +1,7 @@
-mod crawler;
-
-use crawler::Config;
+use gto_wizard_crawler::crawler::{Config, Crawler};
 use poker_core::result::Result;
 use tokio::fs;
 
-use crate::crawler::Crawler;
-
 #[tokio::main]
 async fn main() -> Result<()> {
     unsafe { poker_core::init::init() };
@@ -0,0 +1,142 @@
+use std::{future::Future, io::ErrorKind, path::Path};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio::{
+    fs::{self, File, OpenOptions},
+    io::AsyncWriteExt,
+};
+
+use poker_core::{range::PreFlopAction, result::Result};
+
+/// The spots left to crawl as an append-only journal, one JSON record per line.
+/// Every record is appended with a single synced write, so a crash loses at most
+/// the spots in progress, and a torn last line is dropped on the next start.
+pub struct Queue<T = Vec<PreFlopAction>> {
+    file: File,
+    pending: Vec<T>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Record<T> {
+    Queued(Vec<T>),
+    Completed { actions: T, next: Vec<T> },
+}
+
+impl Queue {
+    pub const JOURNAL_FILE: &str = "queue.jsonl";
+    /// The queue of older versions, rewritten after every spot.
+    pub const LEGACY_FILE: &str = "queue.json";
+
+    /// Replays the pre flop journal in the directory, or starts a new one with the root spot.
+    pub async fn open(dir: &Path) -> Result<Self> {
+        Self::open_journal(&dir.join(Self::JOURNAL_FILE), Self::initial_pending(dir)).await
+    }
+
+    async fn initial_pending(dir: &Path) -> Result<Vec<Vec<PreFlopAction>>> {
+        match fs::read_to_string(dir.join(Self::LEGACY_FILE)).await {
+            Ok(content) => {
+                eprintln!("Migrating queue file...");
+                Ok(serde_json::from_str(&content)?)
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                eprintln!("Creating new queue file...");
+                Ok(vec![vec![]])
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+impl<T: Serialize + DeserializeOwned + Clone + PartialEq> Queue<T> {
+    /// Replays the journal, or starts a new one with the initial spots.
+    pub async fn open_journal(
+        path: &Path,
+        initial: impl Future<Output = Result<Vec<T>>>,
+    ) -> Result<Self> {
+        let content = match fs::read(&path).await {
+            Ok(content) => Some(content),
+            Err(err) if err.kind() == ErrorKind::NotFound => None,
+            Err(err) => return Err(err.into()),
+        };
+
+        let Some(content) = content else {
+            let pending = initial.await?;
+            let file = OpenOptions::new()
+                .create_new(true)
+                .append(true)
+                .open(path)
+                .await?;
+            let mut queue = Self {
+                file,
+                pending: Vec::new(),
+            };
+            queue.append(Record::Queued(pending)).await?;
+            return Ok(queue);
+        };
+
+        // Everything after the last newline is a torn write.
+        let valid_len = content
+            .iter()
+            .rposition(|byte| *byte == b'\n')
+            .map_or(0, |index| index + 1);
+
+        let mut pending = Vec::new();
+        for line in content[..valid_len].split(|byte| *byte == b'\n') {
+            if line.is_empty() {
+                continue;
+            }
+            let record: Record<T> = serde_json::from_slice(line)
+                .map_err(|err| format!("queue: invalid journal record: {err}"))?;
+            Self::apply(&mut pending, record);
+        }
+
+        let file = OpenOptions::new().append(true).open(path).await?;
+        if valid_len < content.len() {
+            eprintln!("Dropping incomplete last queue record...");
+            file.set_len(u64::try_from(valid_len)?).await?;
+        }
+
+        Ok(Self { file, pending })
+    }
+
+    /// In the order they were queued.
+    pub fn pending(&self) -> &[T] {
+        &self.pending
+    }
+
+    /// Removes a crawled spot and queues the spots after it.
+    pub async fn complete(&mut self, spot: &T, next: Vec<T>) -> Result<()> {
+        if !self.pending.contains(spot) {
+            return Err("queue: completed spot was not queued".into());
+        }
+
+        self.append(Record::Completed {
+            actions: spot.clone(),
+            next,
+        })
+        .await
+    }
+
+    async fn append(&mut self, record: Record<T>) -> Result<()> {
+        let mut line = serde_json::to_string(&record)?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes()).await?;
+        self.file.sync_data().await?;
+
+        Self::apply(&mut self.pending, record);
+        Ok(())
+    }
+
+    fn apply(pending: &mut Vec<T>, record: Record<T>) {
+        match record {
+            Record::Queued(actions) => pending.extend(actions),
+            Record::Completed { actions, next } => {
+                if let Some(index) = pending.iter().position(|pending| *pending == actions) {
+                    pending.remove(index);
+                }
+                pending.extend(next);
+            }
+        }
+    }
+}
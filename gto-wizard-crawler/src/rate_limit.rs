@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+use tokio::{
+    sync::Mutex,
+    time::{sleep, Instant},
+};
+
+/// Allows bursts of up to `burst` requests and `rate` requests per second on average.
+pub struct TokenBucket {
+    rate: f64,
+    burst: f64,
+    state: Mutex<BucketState>,
+}
+
+struct BucketState {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(rate: f64, burst: u32) -> Self {
+        let burst = f64::from(burst.max(1));
+        Self {
+            rate,
+            burst,
+            state: Mutex::new(BucketState {
+                tokens: burst,
+                refilled_at: Instant::now(),
+            }),
+        }
+    }
+
+    /// Waits for a token. Waiters are served in order, the lock is fair.
+    pub async fn acquire(&self) {
+        let mut state = self.state.lock().await;
+        self.refill(&mut state);
+
+        if state.tokens < 1.0 {
+            let wait = Duration::from_secs_f64((1.0 - state.tokens) / self.rate);
+            sleep(wait).await;
+            self.refill(&mut state);
+        }
+
+        state.tokens = (state.tokens - 1.0).max(0.0);
+    }
+
+    fn refill(&self, state: &mut BucketState) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(state.refilled_at).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * self.rate).min(self.burst);
+        state.refilled_at = now;
+    }
+}
@@ -0,0 +1,442 @@
+use std::{
+    fmt::Write,
+    future::Future,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use rand::Rng;
+use reqwest::{
+    header::{
+        HeaderMap, HeaderValue, ACCEPT, ACCEPT_ENCODING, ACCEPT_LANGUAGE, AUTHORIZATION,
+        CONNECTION, CONTENT_TYPE, ORIGIN, REFERER, RETRY_AFTER, USER_AGENT,
+    },
+    Client, RequestBuilder, Response, StatusCode,
+};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::{fs, sync::RwLock, time::sleep};
+use url::Url;
+
+use poker_core::{
+    card::Card,
+    game::MilliBigBlind,
+    range::{PreFlopAction, RangeActionKind},
+    result::Result,
+};
+
+use crate::{crawler::Config, rate_limit::TokenBucket};
+
+/// Where the crawler gets the solutions of spots from.
+/// Requests may run concurrently.
+pub trait RangeSource {
+    fn get_raw_range(&self, spot: &Spot) -> impl Future<Output = Result<RawRangeData>>;
+}
+
+/// A spot of the game tree, pre flop if there is no board.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Spot {
+    pub pre_flop_actions: Vec<PreFlopAction>,
+    pub board: Vec<Card>,
+    /// Only flop actions are supported by the API parameters.
+    pub post_flop_actions: Vec<RangeActionKind>,
+}
+
+impl Spot {
+    pub fn pre_flop(pre_flop_actions: Vec<PreFlopAction>) -> Self {
+        Self {
+            pre_flop_actions,
+            ..Self::default()
+        }
+    }
+
+    /// The actions encoded like in the API URL, the board and
+    /// the post flop actions follow separated by underscores, e.g. `R2.5-C_AhKd7c_X-B1.8`.
+    pub fn encode(&self, depth: MilliBigBlind) -> Result<String> {
+        let mut encoded = url_encode_pre_flop_actions(&self.pre_flop_actions, depth)?;
+        if !self.board.is_empty() {
+            write!(
+                encoded,
+                "_{}_{}",
+                encode_board(&self.board),
+                url_encode_post_flop_actions(&self.post_flop_actions)?
+            )?;
+        }
+        Ok(encoded)
+    }
+}
+
+/// The GTO Wizard API, or a stand-in server at the configured base URL.
+pub struct HttpRangeSource {
+    base_url: Url,
+    client: Client,
+    bearer_token: RwLock<String>,
+    refresh_token: String,
+    game_type: String,
+    depth: MilliBigBlind,
+    rate_limiter: TokenBucket,
+    max_retries: u32,
+    initial_backoff: Duration,
+}
+
+impl HttpRangeSource {
+    const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+    pub fn new(config: &Config) -> Result<Self> {
+        if !config.requests_per_second.is_finite() || config.requests_per_second <= 0.0 {
+            return Err("http source: invalid requests per second".into());
+        }
+
+        let mut base_url = Url::parse(&config.base_url)?;
+        // Join relative paths below the base path.
+        if !base_url.path().ends_with('/') {
+            base_url.set_path(&format!("{}/", base_url.path()));
+        }
+
+        let headers = Self::build_headers(&config.gw_client_id)?;
+        let client = Client::builder().default_headers(headers).build()?;
+
+        Ok(Self {
+            base_url,
+            client,
+            bearer_token: RwLock::new(config.bearer_token.clone()),
+            refresh_token: config.refresh_token.clone(),
+            game_type: config.game_type.clone(),
+            depth: config.depth,
+            rate_limiter: TokenBucket::new(config.requests_per_second, config.burst),
+            max_retries: config.max_retries,
+            initial_backoff: Duration::from_millis(config.initial_backoff_ms),
+        })
+    }
+
+    async fn get_raw_range_inner(&self, spot: &Spot, bearer_token: &str) -> Result<RawRangeData> {
+        let url = self.build_range_url(spot)?;
+        let request = self
+            .client
+            .get(url)
+            .header(AUTHORIZATION, format!("Bearer {bearer_token}"));
+        let response = self.send(request).await?;
+        let body: RawRangeData = response.json().await?;
+        Ok(body)
+    }
+
+    /// Sends a request with rate limiting, retrying on throttling,
+    /// server errors and connection problems with exponential backoff.
+    async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        let mut attempt = 0;
+        loop {
+            let Some(current) = request.try_clone() else {
+                return Err("http source: request can't be retried".into());
+            };
+
+            self.rate_limiter.acquire().await;
+            let result = current.send().await;
+
+            let transient = match &result {
+                Ok(response) => Self::is_transient(response.status()),
+                Err(err) => err.is_timeout() || err.is_connect(),
+            };
+            if !transient || attempt >= self.max_retries {
+                return Ok(result?.error_for_status()?);
+            }
+
+            let delay = result
+                .ok()
+                .and_then(|response| Self::retry_after(&response))
+                .unwrap_or_else(|| self.backoff(attempt));
+            eprintln!("Request failed, retrying in {delay:?}...");
+            sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    fn is_transient(status: StatusCode) -> bool {
+        status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+    }
+
+    fn retry_after(response: &Response) -> Option<Duration> {
+        let seconds: u64 = response
+            .headers()
+            .get(RETRY_AFTER)?
+            .to_str()
+            .ok()?
+            .parse()
+            .ok()?;
+        Some(Duration::from_secs(seconds).min(Self::MAX_BACKOFF))
+    }
+
+    /// Doubles with every attempt, randomized so workers don't retry in lockstep.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(1 << attempt.min(16))
+            .min(Self::MAX_BACKOFF);
+        backoff.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+    }
+
+    fn build_range_url(&self, spot: &Spot) -> Result<Url> {
+        let pre_flop_actions_formatted =
+            url_encode_pre_flop_actions(&spot.pre_flop_actions, self.depth)?;
+        let flop_actions_formatted = url_encode_post_flop_actions(&spot.post_flop_actions)?;
+        let depth = self.depth / 1000;
+        let params: &[(&str, &str)] = &[
+            ("gametype", &self.game_type),
+            ("depth", &depth.to_string()),
+            ("stacks", ""),
+            ("preflop_actions", &pre_flop_actions_formatted),
+            ("flop_actions", &flop_actions_formatted),
+            ("turn_actions", ""),
+            ("river_actions", ""),
+            ("board", &encode_board(&spot.board)),
+        ];
+        let mut url = self.base_url.join("v4/solutions/spot-solution/")?;
+        url.query_pairs_mut().extend_pairs(params);
+        Ok(url)
+    }
+
+    fn build_headers(gw_client_id: &str) -> Result<HeaderMap<HeaderValue>> {
+        let mut headers = HeaderMap::new();
+
+        headers.insert(
+            USER_AGENT,
+            HeaderValue::from_static(
+                "Mozilla/5.0 (X11; Linux x86_64; rv:139.0) Gecko/20100101 Firefox/139.0",
+            ),
+        );
+        headers.insert(
+            ACCEPT,
+            HeaderValue::from_static("application/json, text/plain, */*"),
+        );
+        headers.insert(ACCEPT_LANGUAGE, HeaderValue::from_static("en-US,en;q=0.5"));
+        headers.insert(
+            ACCEPT_ENCODING,
+            HeaderValue::from_static("gzip, deflate, br, zstd"),
+        );
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        headers.insert(
+            REFERER,
+            HeaderValue::from_static("https://app.gtowizard.com/"),
+        );
+        headers.insert("GWCLIENTID", gw_client_id.parse()?);
+        headers.insert(
+            ORIGIN,
+            HeaderValue::from_static("https://app.gtowizard.com"),
+        );
+        headers.insert("DNT", HeaderValue::from_static("1"));
+        headers.insert("Sec-Fetch-Dest", HeaderValue::from_static("empty"));
+        headers.insert("Sec-Fetch-Mode", HeaderValue::from_static("cors"));
+        headers.insert("Sec-Fetch-Site", HeaderValue::from_static("same-site"));
+        headers.insert(CONNECTION, HeaderValue::from_static("keep-alive"));
+        headers.insert("Priority", HeaderValue::from_static("u=0"));
+
+        Ok(headers)
+    }
+
+    /// Renews the expired token unless another request already did,
+    /// requests wait for the new token in the meantime.
+    async fn renew_bearer_token(&self, expired_token: &str) -> Result<()> {
+        let mut bearer_token = self.bearer_token.write().await;
+        if *bearer_token != expired_token {
+            return Ok(());
+        }
+
+        eprintln!("Renewing bearer token...");
+
+        let url = self.base_url.join("v1/token/refresh/")?;
+        let body = json!({
+            "refresh": self.refresh_token,
+        });
+        let request = self
+            .client
+            .post(url)
+            .header(AUTHORIZATION, format!("Bearer {bearer_token}"))
+            .json(&body);
+        let response = self.send(request).await?;
+
+        let body = response.text().await?;
+        let new_token: TokenRefresh = serde_json::from_str(&body)?;
+        *bearer_token = new_token.access;
+
+        eprintln!("Updated bearer token to: {bearer_token}");
+        Ok(())
+    }
+}
+
+impl RangeSource for HttpRangeSource {
+    async fn get_raw_range(&self, spot: &Spot) -> Result<RawRangeData> {
+        let bearer_token = self.bearer_token.read().await.clone();
+        let err = match self.get_raw_range_inner(spot, &bearer_token).await {
+            Ok(range) => return Ok(range),
+            Err(err) => err,
+        };
+
+        match err.downcast::<reqwest::Error>() {
+            Ok(err) if err.status() == Some(StatusCode::UNAUTHORIZED) => {
+                self.renew_bearer_token(&bearer_token).await?;
+                let bearer_token = self.bearer_token.read().await.clone();
+                self.get_raw_range_inner(spot, &bearer_token).await
+            }
+            Ok(err) => Err(err.into()),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Recorded solutions in a directory, one `spot_<encoded spot>.json` file per spot
+/// encoded like `Spot::encode`, e.g. `spot_R2.5-C.json`. The root spot is `spot_.json`.
+pub struct FixtureRangeSource {
+    dir: PathBuf,
+    depth: MilliBigBlind,
+}
+
+impl FixtureRangeSource {
+    pub const FILE_PREFIX: &str = "spot_";
+
+    pub fn new(dir: impl AsRef<Path>, depth: MilliBigBlind) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            depth,
+        }
+    }
+
+    pub fn path(&self, spot: &Spot) -> Result<PathBuf> {
+        Ok(self.dir.join(format!(
+            "{}{}.json",
+            Self::FILE_PREFIX,
+            spot.encode(self.depth)?
+        )))
+    }
+}
+
+impl RangeSource for FixtureRangeSource {
+    async fn get_raw_range(&self, spot: &Spot) -> Result<RawRangeData> {
+        let path = self.path(spot)?;
+        let content = fs::read_to_string(&path)
+            .await
+            .map_err(|err| format!("fixture source: can't read {}: {err}", path.display()))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+}
+
+/// Encodes pre flop actions like the API, e.g. `R2.5-R8-C`, all-ins are `RAI`.
+pub fn url_encode_pre_flop_actions(
+    pre_flop_actions: &[PreFlopAction],
+    depth: MilliBigBlind,
+) -> Result<String> {
+    let mut pre_flop_actions_formatted = String::new();
+
+    for (index, action) in pre_flop_actions.iter().copied().enumerate() {
+        match action {
+            PreFlopAction::Post { .. } => {
+                return Err("pre flop actions: additional post unsupported".into());
+            }
+            PreFlopAction::Straddle { .. } => {
+                return Err("pre flop actions: straddle unsupported".into());
+            }
+            PreFlopAction::Fold => pre_flop_actions_formatted.write_char('F')?,
+            PreFlopAction::Check => pre_flop_actions_formatted.write_char('X')?,
+            PreFlopAction::Call => pre_flop_actions_formatted.write_char('C')?,
+            PreFlopAction::Raise(to) => {
+                let raise_formatted = &format_raise_in_url(to, depth)?;
+                pre_flop_actions_formatted.write_str(raise_formatted)?
+            }
+        }
+
+        if index < pre_flop_actions.len() - 1 {
+            pre_flop_actions_formatted.write_char('-')?;
+        }
+    }
+
+    Ok(pre_flop_actions_formatted)
+}
+
+/// Encodes post flop actions like pre flop actions, bets are `B` and
+/// raises `R` with the total amount in the street, e.g. `X-B1.8-R6`.
+pub fn url_encode_post_flop_actions(post_flop_actions: &[RangeActionKind]) -> Result<String> {
+    let encoded = post_flop_actions
+        .iter()
+        .map(|action| match *action {
+            RangeActionKind::Post { .. } | RangeActionKind::Straddle { .. } => {
+                Err("post flop actions: posts unsupported".into())
+            }
+            RangeActionKind::Fold => Ok("F".to_string()),
+            RangeActionKind::Check => Ok("X".to_string()),
+            RangeActionKind::Call => Ok("C".to_string()),
+            RangeActionKind::Bet(amount) => Ok(format!("B{}", format_big_blinds(amount)?)),
+            RangeActionKind::Raise(to) => Ok(format!("R{}", format_big_blinds(to)?)),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(encoded.join("-"))
+}
+
+/// The cards without separators, e.g. `AhKd7c`.
+pub fn encode_board(board: &[Card]) -> String {
+    board.iter().map(Card::to_string).collect()
+}
+
+fn format_raise_in_url(to: MilliBigBlind, depth: MilliBigBlind) -> Result<String> {
+    if to < 2_000 {
+        return Err("pre flop actions: raise too small".into());
+    }
+
+    if to == depth {
+        return Ok("RAI".to_string());
+    }
+
+    Ok(format!("R{}", format_big_blinds(to)?))
+}
+
+fn format_big_blinds(amount: MilliBigBlind) -> Result<String> {
+    if amount <= 0 {
+        return Err("actions: amount must be positive".into());
+    }
+
+    let full_blinds = amount / 1_000;
+    let blind_fraction = amount % 1_000;
+    let blind_fraction = format!("{blind_fraction:03}");
+    let blind_fraction = blind_fraction.trim_end_matches('0');
+
+    if blind_fraction.is_empty() {
+        Ok(full_blinds.to_string())
+    } else {
+        Ok(format!("{full_blinds}.{blind_fraction}"))
+    }
+}
+
+/// The solution of one spot as returned by the API.
+#[derive(Debug, Deserialize)]
+pub struct RawRangeData {
+    pub(crate) action_solutions: Vec<ActionSolution>,
+    pub(crate) players_info: Vec<PlayerInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ActionSolution {
+    pub(crate) action: Action,
+    pub(crate) strategy: Vec<f64>,
+    pub(crate) evs: Vec<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct Action {
+    #[serde(rename = "type")]
+    pub(crate) action_type: String,
+    #[serde(rename = "betsize")]
+    pub(crate) bet_size: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct PlayerInfo {
+    pub(crate) player: Player,
+    pub(crate) range: Vec<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct Player {
+    pub(crate) position: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenRefresh {
+    access: String,
+}
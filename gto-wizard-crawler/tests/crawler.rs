@@ -0,0 +1,352 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use gto_wizard_crawler::{
+    crawler::{Config, Crawler},
+    queue::Queue,
+    source::FixtureRangeSource,
+};
+use poker_core::range::{
+    PostFlopRangeConfig, PostFlopRangeConfigData, PreFlopAction, PreFlopRangeConfig,
+    PreFlopRangeConfigData, RangeActionKind,
+};
+use serde_json::json;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+use url::Url;
+
+const FRESH_TOKEN: &str = "fresh-token";
+const REFRESH_TOKEN: &str = "refresh-token";
+
+fn fixtures_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/heads_up")
+}
+
+fn out_dir(name: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("gto-wizard-crawler-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn config(base_url: &str, bearer_token: &str, out_dir: &Path) -> Config {
+    serde_json::from_value(config_json(base_url, bearer_token, out_dir)).unwrap()
+}
+
+fn config_json(base_url: &str, bearer_token: &str, out_dir: &Path) -> serde_json::Value {
+    json!({
+        "gw_client_id": "client",
+        "bearer_token": bearer_token,
+        "refresh_token": REFRESH_TOKEN,
+        "base_url": base_url,
+        "game_type": "HeadsUp",
+        "max_players": 2,
+        "depth": 100_000,
+        "min_frequency": 0.01,
+        "max_calls": 5,
+        "out_dir": out_dir.to_str().unwrap(),
+        "concurrency": 2,
+        "requests_per_second": 1_000.0,
+        "burst": 10,
+        "initial_backoff_ms": 1,
+    })
+}
+
+/// Checks the files written by a crawl of the heads up fixture tree:
+/// the open, the 3-bet and the call or fold against the 3-bet.
+async fn assert_crawled(out_dir: &Path) {
+    for name in ["range_.json", "range_R2.5.json", "range_R2.5-R8.json"] {
+        assert!(out_dir.join(name).exists(), "{name} missing");
+    }
+
+    let queue = Queue::open(out_dir).await.unwrap();
+    assert!(queue.pending().is_empty());
+
+    let config = std::fs::read_to_string(out_dir.join("config_HeadsUp_100000.json")).unwrap();
+    let config: PreFlopRangeConfigData = serde_json::from_str(&config).unwrap();
+    let config = PreFlopRangeConfig::from_data(config).unwrap();
+
+    let previous_actions: Vec<_> = config
+        .ranges()
+        .iter()
+        .map(|entry| entry.previous_actions().to_vec())
+        .collect();
+    assert_eq!(
+        previous_actions,
+        vec![
+            vec![],
+            vec![PreFlopAction::Raise(2_500)],
+            vec![PreFlopAction::Raise(2_500), PreFlopAction::Raise(8_000)],
+        ]
+    );
+
+    let open = &config.ranges()[0];
+    assert!(open.frequency(PreFlopAction::Raise(2_500)) > 0.0);
+    assert!(open.frequency(PreFlopAction::Fold) > 0.0);
+}
+
+#[tokio::test]
+async fn test_crawl_fixtures() {
+    unsafe { poker_core::init::init() };
+
+    let out_dir = out_dir("fixtures");
+    let config = config("http://127.0.0.1:1", "unused", &out_dir);
+    let source = FixtureRangeSource::new(fixtures_dir(), 100_000);
+    let crawler = Crawler::with_source(config, source).await.unwrap();
+    crawler.crawl().await.unwrap();
+
+    assert_crawled(&out_dir).await;
+    std::fs::remove_dir_all(out_dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_crawl_stand_in_server() {
+    unsafe { poker_core::init::init() };
+
+    let server = StandInServer::start(fixtures_dir(), 0).await;
+    let out_dir = out_dir("server");
+    // The expired token is renewed after the first unauthorized request.
+    let config = config(&server.base_url, "expired-token", &out_dir);
+    let crawler = Crawler::new(config).await.unwrap();
+    crawler.crawl().await.unwrap();
+
+    assert_crawled(&out_dir).await;
+    assert_eq!(server.counters.token_refreshes.load(Ordering::SeqCst), 1);
+    assert_eq!(server.counters.spot_requests.load(Ordering::SeqCst), 4);
+    std::fs::remove_dir_all(out_dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_crawl_throttled() {
+    unsafe { poker_core::init::init() };
+
+    let server = StandInServer::start(fixtures_dir(), 2).await;
+    let out_dir = out_dir("throttled");
+    let config = config(&server.base_url, FRESH_TOKEN, &out_dir);
+    let crawler = Crawler::new(config).await.unwrap();
+    crawler.crawl().await.unwrap();
+
+    assert_crawled(&out_dir).await;
+    assert_eq!(server.counters.spot_requests.load(Ordering::SeqCst), 5);
+    std::fs::remove_dir_all(out_dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_crawl_missing_spot() {
+    unsafe { poker_core::init::init() };
+
+    let out_dir = out_dir("missing");
+    let source = FixtureRangeSource::new(out_dir.join("nothing"), 100_000);
+    let crawler = Crawler::with_source(config("http://127.0.0.1:1", "unused", &out_dir), source)
+        .await
+        .unwrap();
+    let err = crawler.crawl().await.unwrap_err();
+    assert!(err.to_string().starts_with("fixture source: can't read"));
+
+    // A record torn by a crash is dropped.
+    let journal = out_dir.join(Queue::JOURNAL_FILE);
+    let mut content = std::fs::read_to_string(&journal).unwrap();
+    content.push_str(r#"{"completed":{"actions":[]"#);
+    std::fs::write(&journal, content).unwrap();
+
+    // The spot stays queued and the next run continues with it.
+    let queue = Queue::open(&out_dir).await.unwrap();
+    assert_eq!(queue.pending(), [vec![]]);
+    drop(queue);
+
+    let source = FixtureRangeSource::new(fixtures_dir(), 100_000);
+    let crawler = Crawler::with_source(config("http://127.0.0.1:1", "unused", &out_dir), source)
+        .await
+        .unwrap();
+    crawler.crawl().await.unwrap();
+
+    assert_crawled(&out_dir).await;
+    std::fs::remove_dir_all(out_dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_crawl_post_flop() {
+    unsafe { poker_core::init::init() };
+
+    let server = StandInServer::start(fixtures_dir(), 0).await;
+    let out_dir = out_dir("post-flop");
+    let mut config = config_json(&server.base_url, FRESH_TOKEN, &out_dir);
+    config["post_flop"] = json!({
+        "pre_flop_actions": [{ "raise": 2_500 }, "call"],
+        "flops": ["AhKd7c"],
+    });
+    let config = serde_json::from_value(config).unwrap();
+    let crawler = Crawler::new(config).await.unwrap();
+    crawler.crawl().await.unwrap();
+
+    // Both players check or the bet is called or folded, the flop ends.
+    assert_eq!(server.counters.spot_requests.load(Ordering::SeqCst), 4);
+
+    let config =
+        std::fs::read_to_string(out_dir.join("config_post_flop_HeadsUp_100000.json")).unwrap();
+    let config: PostFlopRangeConfigData = serde_json::from_str(&config).unwrap();
+    let config = PostFlopRangeConfig::from_data(config).unwrap();
+
+    let previous_actions: Vec<_> = config
+        .spots()
+        .iter()
+        .map(|spot| spot.previous_actions().to_vec())
+        .collect();
+    assert_eq!(
+        previous_actions,
+        vec![
+            vec![],
+            vec![RangeActionKind::Check],
+            vec![RangeActionKind::Check, RangeActionKind::Bet(3_300)],
+            vec![RangeActionKind::Bet(1_800)],
+        ]
+    );
+
+    let c_bet = config.spots()[1].entry();
+    let bet = RangeActionKind::Bet(3_300);
+    assert!(c_bet.frequency(bet) > 0.0);
+    assert!(c_bet.entry_ev(bet, "QJs".parse().unwrap()).is_some());
+    std::fs::remove_dir_all(out_dir).unwrap();
+}
+
+/// Serves the fixtures like the API below a path prefix,
+/// one request per connection.
+struct StandInServer {
+    base_url: String,
+    counters: Arc<Counters>,
+}
+
+#[derive(Default)]
+struct Counters {
+    token_refreshes: AtomicUsize,
+    spot_requests: AtomicUsize,
+    /// The number of spot requests answered with too many requests.
+    throttled: AtomicUsize,
+}
+
+impl StandInServer {
+    const PREFIX: &str = "/api";
+
+    async fn start(fixtures_dir: PathBuf, throttled: usize) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}{}", listener.local_addr().unwrap(), Self::PREFIX);
+        let counters = Arc::new(Counters {
+            throttled: AtomicUsize::new(throttled),
+            ..Counters::default()
+        });
+
+        let server = Self {
+            base_url,
+            counters: counters.clone(),
+        };
+
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let fixtures_dir = fixtures_dir.clone();
+                let counters = counters.clone();
+                tokio::spawn(async move {
+                    Self::handle(stream, &fixtures_dir, &counters).await;
+                });
+            }
+        });
+
+        server
+    }
+
+    async fn handle(stream: TcpStream, fixtures_dir: &Path, counters: &Counters) {
+        let mut stream = BufReader::new(stream);
+
+        let mut request_line = String::new();
+        stream.read_line(&mut request_line).await.unwrap();
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next().unwrap().to_string();
+        let target = parts.next().unwrap().to_string();
+
+        let mut authorization = None;
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            stream.read_line(&mut line).await.unwrap();
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            let (name, value) = line.split_once(':').unwrap();
+            match name.to_ascii_lowercase().as_str() {
+                "authorization" => authorization = Some(value.trim().to_string()),
+                "content-length" => content_length = value.trim().parse().unwrap(),
+                _ => {}
+            }
+        }
+        let mut body = vec![0; content_length];
+        stream.read_exact(&mut body).await.unwrap();
+
+        let url = Url::parse(&format!("http://localhost{target}")).unwrap();
+        let path = url.path().strip_prefix(Self::PREFIX).unwrap_or_default();
+        let (status, response) = match (method.as_str(), path) {
+            ("POST", "/v1/token/refresh/") => {
+                let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                if body["refresh"] == REFRESH_TOKEN {
+                    counters.token_refreshes.fetch_add(1, Ordering::SeqCst);
+                    ("200 OK", json!({ "access": FRESH_TOKEN }).to_string())
+                } else {
+                    ("401 Unauthorized", String::new())
+                }
+            }
+            ("GET", "/v4/solutions/spot-solution/") => {
+                counters.spot_requests.fetch_add(1, Ordering::SeqCst);
+                let query = |key: &str| {
+                    url.query_pairs()
+                        .find(|(name, _)| name == key)
+                        .map(|(_, value)| value.into_owned())
+                };
+
+                let throttled = counters
+                    .throttled
+                    .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                    .is_ok();
+
+                if throttled {
+                    ("429 Too Many Requests", String::new())
+                } else if authorization.as_deref() != Some(&format!("Bearer {FRESH_TOKEN}")) {
+                    ("401 Unauthorized", String::new())
+                } else if query("gametype").as_deref() != Some("HeadsUp")
+                    || query("depth").as_deref() != Some("100")
+                {
+                    ("400 Bad Request", String::new())
+                } else {
+                    let mut spot = query("preflop_actions").unwrap_or_default();
+                    let board = query("board").unwrap_or_default();
+                    if !board.is_empty() {
+                        let actions = query("flop_actions").unwrap_or_default();
+                        spot = format!("{spot}_{board}_{actions}");
+                    }
+                    let path = fixtures_dir
+                        .join(format!("{}{spot}.json", FixtureRangeSource::FILE_PREFIX));
+                    match tokio::fs::read_to_string(path).await {
+                        Ok(content) => ("200 OK", content),
+                        Err(_) => ("404 Not Found", String::new()),
+                    }
+                }
+            }
+            _ => ("404 Not Found", String::new()),
+        };
+
+        let response = format!(
+            "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{response}",
+            response.len()
+        );
+        let mut stream = stream.into_inner();
+        stream.write_all(response.as_bytes()).await.unwrap();
+        stream.shutdown().await.unwrap();
+    }
+}
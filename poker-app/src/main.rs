@@ -1,20 +1,33 @@
 use std::cmp::Ordering;
 use std::fmt::Write;
-use std::fs::read_to_string;
+use std::fs::{read_to_string, File};
 use std::io::{self, BufWriter};
+use std::path::Path;
+use std::sync::Arc;
 use std::time::Instant;
 
 use eframe::egui::{CentralPanel, Context, Rect, Style, UiBuilder, Vec2, ViewportBuilder, Visuals};
 use eframe::Frame;
+use poker_core::ai::{AlwaysAllIn, AlwaysCheckCall, AlwaysFold, SimpleStrategy};
 use poker_core::cards::Cards;
 use poker_core::db::{self, DB};
 use poker_core::equity::{Equity, EquityTable};
-use poker_core::game::Game;
+use poker_core::external::ExternalEngine;
+use poker_core::game::{milli_big_blind_from_f64, milli_big_blind_to_f64_approximate, Game};
+use poker_core::opponent::OpponentModel;
 use poker_core::parser::GGHandHistoryParser;
-use poker_core::range::RangeTable;
+use poker_core::range::{
+    PostFlopRangeConfig, PostFlopRangeConfigData, PreFlopAction, PreFlopRangeConfig,
+    PreFlopRangeConfigSet, RangeTable, MAX_FREQUENCY,
+};
+use poker_core::range_binary;
+use poker_core::range_diff::{self, DiffThreshold, PreFlopRangeConfigDiff};
 use poker_core::result::Result;
+use poker_core::simulation::{self, Bot, SimulationConfig};
+use poker_core::writer;
 use poker_gui::game_view::GameView;
 use poker_gui::history_viewer::HistoryView;
+use poker_gui::range_viewer::{RangeValue, RangeViewer};
 use rusqlite::types::Value;
 
 const INVALID_COMMAND_ERROR: &'static str = "Invalid command. See README for usage.";
@@ -30,8 +43,19 @@ fn main() -> Result<()> {
         Some("simulate-table") => simulate_table(&args[2..]),
         Some("parse-gg") => parse_gg(&args[2..]),
         Some("query") => query(&args[2..]),
+        Some("export-json") => export_json(&args[2..]),
+        Some("export-csv") => export_csv(&args[2..]),
+        Some("import-json") => import_json(&args[2..]),
+        Some("export-text") => export_text(&args[2..]),
+        Some("verify") => verify(&args[2..]),
         Some("gui") => gui(&args[2..]),
         Some("history-gui") => history_gui(&args[2..]),
+        Some("range-editor") => range_editor(&args[2..]),
+        Some("post-flop-viewer") => post_flop_viewer(&args[2..]),
+        Some("range-diff") => range_diff(&args[2..]),
+        Some("range-merge") => range_merge(&args[2..]),
+        Some("convert-ranges") => convert_ranges(&args[2..]),
+        Some("self-play") => self_play(&args[2..]),
         _ => Err(INVALID_COMMAND_ERROR.into()),
     }
 }
@@ -152,7 +176,7 @@ fn parse_gg(args: &[String]) -> Result<()> {
     };
 
     // TODO
-    let mut db = DB::open("hands.db")?;
+    let mut db = DB::create("hands.db")?;
 
     let read_time = Instant::now();
     let content = read_to_string(path)?;
@@ -217,7 +241,7 @@ fn query(args: &[String]) -> Result<()> {
     let [db_path, query] = args else {
         return Err(INVALID_COMMAND_ERROR.into());
     };
-    let db = DB::open(db_path)?;
+    let db = DB::open_read_only(db_path)?;
 
     let mut formatted = String::new();
     db.query_for_each(query, (), |row| {
@@ -243,27 +267,130 @@ fn query(args: &[String]) -> Result<()> {
     Ok(())
 }
 
-fn history_gui(args: &[String]) -> Result<()> {
-    // TODO
+fn export_json(args: &[String]) -> Result<()> {
+    let (db_path, hand_ids_query) = match args {
+        [db_path] => (db_path.as_str(), DB::ALL_HAND_IDS_QUERY),
+        [db_path, hand_ids_query] => (db_path.as_str(), hand_ids_query.as_str()),
+        _ => return Err(INVALID_COMMAND_ERROR.into()),
+    };
+    let db = DB::open_read_only(db_path)?;
 
-    const DEFAULT_QUERY: &str =
-        "SELECT * FROM hands LEFT JOIN hands_players ON id = hand_id AND hero_index = player";
+    let count =
+        db.export_game_data_json_lines(hand_ids_query, (), BufWriter::new(io::stdout().lock()))?;
+    eprintln!("--- exported {count} hand(s) ---");
+    Ok(())
+}
 
-    let (db_path, query) = match args {
-        [db_path] => (db_path.as_str(), DEFAULT_QUERY),
-        [db_path, query] => (db_path.as_str(), query.as_str()),
+fn export_csv(args: &[String]) -> Result<()> {
+    let (db_path, hands_path, players_path, hand_ids_query) = match args {
+        [db_path, hands_path, players_path] => {
+            (db_path, hands_path, players_path, DB::ALL_HAND_IDS_QUERY)
+        }
+        [db_path, hands_path, players_path, hand_ids_query] => {
+            (db_path, hands_path, players_path, hand_ids_query.as_str())
+        }
         _ => return Err(INVALID_COMMAND_ERROR.into()),
     };
+    let db = DB::open_read_only(db_path)?;
+
+    let hands_count = db.export_hands_csv(
+        hand_ids_query,
+        (),
+        BufWriter::new(File::create(hands_path)?),
+    )?;
+    let players_count = db.export_hands_players_csv(
+        hand_ids_query,
+        (),
+        BufWriter::new(File::create(players_path)?),
+    )?;
+    eprintln!("--- exported {hands_count} hand(s) with {players_count} player row(s) ---");
+    Ok(())
+}
 
-    let db = DB::open(db_path)?;
-    let hands = db.load_hands_from_query(query, ())?;
+fn export_text(args: &[String]) -> Result<()> {
+    let (db_path, hand_ids_query) = match args {
+        [db_path] => (db_path.as_str(), DB::ALL_HAND_IDS_QUERY),
+        [db_path, hand_ids_query] => (db_path.as_str(), hand_ids_query.as_str()),
+        _ => return Err(INVALID_COMMAND_ERROR.into()),
+    };
+    let db = DB::open_read_only(db_path)?;
 
-    let game_getter = move |hand_id| {
-        db.get_game_data(hand_id)
-            .and_then(|data| Game::from_game_data(&data))
+    let mut history = String::new();
+    db.query_for_each(hand_ids_query, (), |row| {
+        let [Value::Integer(hand_id)] = row else {
+            return Err("export-text: hand ids query has to return a single integer column".into());
+        };
+
+        let game = Game::from_game_data(&db.get_game_data(u64::try_from(*hand_id)?)?)?;
+        writer::write_game(&game, &mut history)?;
+        history.push_str("\n\n");
+        Ok(true)
+    })?;
+
+    print!("{history}");
+    Ok(())
+}
+
+fn import_json(args: &[String]) -> Result<()> {
+    let [db_path, path] = args else {
+        return Err(INVALID_COMMAND_ERROR.into());
+    };
+    let mut db = DB::create(db_path)?;
+
+    let game_data = db::parse_game_data_json(&read_to_string(path)?)?;
+    eprintln!("--- found {} hands ---", game_data.len());
+
+    let write_db_time = Instant::now();
+    let new_hands_count = db.add_game_data(&game_data)?;
+    eprintln!(
+        "--- took {:?} to write {new_hands_count} new hand(s) to the database ---",
+        write_db_time.elapsed(),
+    );
+    Ok(())
+}
+
+fn verify(args: &[String]) -> Result<()> {
+    let (db_path, repair) = match args {
+        [db_path] => (db_path, false),
+        [db_path, flag] if flag == "--repair" => (db_path, true),
+        _ => return Err(INVALID_COMMAND_ERROR.into()),
+    };
+    let mut db = if repair {
+        DB::open_existing(db_path)?
+    } else {
+        DB::open_read_only(db_path)?
     };
 
-    // TODO: Deduplicate.
+    let verify_time = Instant::now();
+    let report = db.verify(repair)?;
+    for issue in &report.issues {
+        let repaired = if issue.repaired { " (repaired)" } else { "" };
+        println!(
+            "hand {}: {}{repaired}: {}",
+            issue.hand_id,
+            issue.kind.to_str(),
+            issue.message,
+        );
+    }
+    eprintln!(
+        "--- took {:?} to verify {} hand(s) ---",
+        verify_time.elapsed(),
+        report.hands_checked,
+    );
+
+    let unrepaired_count = report.issues.iter().filter(|issue| !issue.repaired).count();
+    if unrepaired_count > 0 {
+        Err(format!("verify: {unrepaired_count} unrepaired issue(s) found").into())
+    } else {
+        Ok(())
+    }
+}
+
+/// Runs a maximized window with the dark style.
+fn run_app<'a>(
+    title: &str,
+    creator: impl FnOnce() -> Result<Box<dyn eframe::App + 'a>> + 'a,
+) -> Result<()> {
     env_logger::init();
     let options = eframe::NativeOptions {
         viewport: ViewportBuilder::default().with_maximized(true),
@@ -271,7 +398,7 @@ fn history_gui(args: &[String]) -> Result<()> {
     };
 
     eframe::run_native(
-        "Poker Toolkit",
+        title,
         options,
         Box::new(|cc| {
             let style = Style {
@@ -280,7 +407,7 @@ fn history_gui(args: &[String]) -> Result<()> {
             };
             cc.egui_ctx.set_style(style);
             egui_extras::install_image_loaders(&cc.egui_ctx);
-            Ok(Box::new(HandHistory::new(hands, game_getter)?))
+            creator()
         }),
     )
     .map_err(|err| err.to_string())?;
@@ -288,48 +415,333 @@ fn history_gui(args: &[String]) -> Result<()> {
     Ok(())
 }
 
-fn gui(args: &[String]) -> Result<()> {
-    let pre_flop_ranges_config_path = match args {
-        [] => None,
-        [pre_flop_ranges_config_path] => Some(pre_flop_ranges_config_path.as_str()),
+fn history_gui(args: &[String]) -> Result<()> {
+    // TODO
+
+    const DEFAULT_QUERY: &str =
+        "SELECT * FROM hands LEFT JOIN hands_players ON id = hand_id AND hero_index = player";
+
+    let (db_path, query) = match args {
+        [db_path] => (db_path.as_str(), DEFAULT_QUERY),
+        [db_path, query] => (db_path.as_str(), query.as_str()),
         _ => return Err(INVALID_COMMAND_ERROR.into()),
     };
 
-    env_logger::init();
-    let options = eframe::NativeOptions {
-        viewport: ViewportBuilder::default().with_maximized(true),
+    let db = DB::open_read_only(db_path)?;
+    let hands = db.load_hands_from_query(query, ())?;
+
+    // Tagging needs write access, browsing still works without it.
+    let tags_db = DB::open_existing(db_path)
+        .inspect_err(|err| eprintln!("can't open database for tagging: {err}"))
+        .ok();
+
+    let game_getter = move |hand_id| {
+        db.get_game_data(hand_id)
+            .and_then(|data| Game::from_game_data(&data))
+    };
+
+    run_app("Poker Toolkit", move || {
+        Ok(Box::new(HandHistory::new(hands, game_getter, tags_db)?))
+    })
+}
+
+fn self_play(args: &[String]) -> Result<()> {
+    let [hands_raw, ..] = args else {
+        return Err(INVALID_COMMAND_ERROR.into());
+    };
+
+    let mut config = SimulationConfig {
+        hands: hands_raw.parse()?,
         ..Default::default()
     };
+    let mut pre_flop_ranges = None;
+    let mut bot_names = Vec::new();
+    for arg in &args[1..] {
+        if arg == "--duplicate" {
+            config.duplicate = true;
+        } else if let Some(tables) = arg.strip_prefix("--tables=") {
+            config.tables = tables.parse()?;
+        } else if let Some(paths) = arg.strip_prefix("--ranges=") {
+            // One config per depth, separated by commas.
+            let configs = paths
+                .split(',')
+                .map(read_pre_flop_range_config)
+                .collect::<Result<Vec<_>>>()?;
+            pre_flop_ranges = Some(Arc::new(PreFlopRangeConfigSet::new(configs)?));
+        } else {
+            bot_names.push(arg.as_str());
+        }
+    }
 
-    eframe::run_native(
-        "Poker Toolkit",
-        options,
-        Box::new(|cc| {
-            let style = Style {
-                visuals: Visuals::dark(),
-                ..Style::default()
+    let bots = bot_names
+        .iter()
+        .enumerate()
+        .map(|(index, bot_name)| {
+            let name = format!("{}. {bot_name}", index + 1);
+            let bot = match *bot_name {
+                "fold" => Bot::new(name, || Box::new(AlwaysFold)),
+                "check-call" => Bot::new(name, || Box::new(AlwaysCheckCall)),
+                "all-in" => Bot::new(name, || Box::new(AlwaysAllIn)),
+                "simple" => {
+                    let Some(pre_flop_ranges) = pre_flop_ranges.clone() else {
+                        return Err("self-play: simple bot requires --ranges=<path>".into());
+                    };
+                    Bot::new(name, move || {
+                        Box::new(SimpleStrategy::new(pre_flop_ranges.clone()))
+                    })
+                }
+                _ => {
+                    let Some(command_line) = bot_name.strip_prefix("external=") else {
+                        return Err(format!("self-play: unknown bot {bot_name}").into());
+                    };
+                    let engine = ExternalEngine::from_command_line(command_line)?;
+                    Bot::new(name, move || Box::new(engine.generator()))
+                }
             };
-            cc.egui_ctx.set_style(style);
-            egui_extras::install_image_loaders(&cc.egui_ctx);
-            Ok(Box::new(App::new(pre_flop_ranges_config_path)?))
-        }),
-    )
-    .map_err(|err| err.to_string())?;
+            Ok(bot)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let simulation_time = Instant::now();
+    let results = simulation::simulate(&bots, &config)?;
+    for result in &results {
+        println!(
+            "{}: {:.2} ± {:.2} bb/100 ({} hands, {} invalid actions)",
+            result.name, result.win_rate, result.confidence, result.hands, result.invalid_actions,
+        );
+    }
+    eprintln!(
+        "--- took {:?} to play {} hand(s) ---",
+        simulation_time.elapsed(),
+        config.hands,
+    );
 
     Ok(())
 }
 
+fn gui(args: &[String]) -> Result<()> {
+    let mut external_bots = Vec::new();
+    let mut positional_args = Vec::new();
+    for arg in args {
+        if let Some(command_line) = arg.strip_prefix("--bot=") {
+            let engine = ExternalEngine::from_command_line(command_line)?;
+            external_bots.push((format!("External ({command_line})"), engine));
+        } else {
+            positional_args.push(arg.as_str());
+        }
+    }
+
+    let (pre_flop_ranges_config_path, db_path) = match positional_args[..] {
+        [] => (None, None),
+        [pre_flop_ranges_config_path] => (Some(pre_flop_ranges_config_path), None),
+        [pre_flop_ranges_config_path, db_path] => {
+            (Some(pre_flop_ranges_config_path), Some(db_path))
+        }
+        _ => return Err(INVALID_COMMAND_ERROR.into()),
+    };
+
+    let mut opponent_model = OpponentModel::default();
+    if let Some(db_path) = db_path {
+        let db = DB::open_read_only(db_path)?;
+        opponent_model.set_tendencies(db.get_players_tendencies()?);
+    }
+
+    run_app("Poker Toolkit", move || {
+        Ok(Box::new(App::new(
+            pre_flop_ranges_config_path,
+            opponent_model,
+            external_bots,
+        )?))
+    })
+}
+
+fn range_editor(args: &[String]) -> Result<()> {
+    let range = match args {
+        [] => None,
+        [range] => Some(RangeTable::parse(range)?),
+        _ => return Err(INVALID_COMMAND_ERROR.into()),
+    };
+
+    let mut range_viewer = RangeViewer::new();
+    range_viewer.set_editable(true);
+    if let Some(range) = range {
+        range_viewer.replace_ranges(vec![RangeValue::Simple(
+            range.to_frequencies(MAX_FREQUENCY),
+        )]);
+    }
+
+    run_app("Poker Toolkit", move || {
+        Ok(Box::new(RangeEditor {
+            range_viewer,
+            titles: Vec::new(),
+            boards: Vec::new(),
+            board_shown: None,
+        }))
+    })
+}
+
+fn post_flop_viewer(args: &[String]) -> Result<()> {
+    let [config_path] = args else {
+        return Err(INVALID_COMMAND_ERROR.into());
+    };
+
+    let config: PostFlopRangeConfigData = serde_json::from_str(&read_to_string(config_path)?)?;
+    let config = PostFlopRangeConfig::from_data(config)?;
+
+    let pre_flop_actions = config
+        .pre_flop_actions()
+        .iter()
+        .map(|action| match action {
+            PreFlopAction::Raise(to) => {
+                format!("Raise {:.2}", milli_big_blind_to_f64_approximate(*to))
+            }
+            action => format!("{action:?}"),
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut ranges = Vec::new();
+    let mut titles = Vec::new();
+    let mut boards = Vec::new();
+    for spot in config.spots() {
+        let board: String = spot.board().iter().map(|card| card.to_string()).collect();
+        let actions = spot
+            .previous_actions()
+            .iter()
+            .map(|action| action.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        ranges.push(RangeValue::Full(spot.entry().clone()));
+        boards.push(Cards::from_slice(spot.board()).ok_or("post-flop-viewer: invalid board")?);
+        titles.push(format!(
+            "{}\nPre flop: {pre_flop_actions}\nBoard: {board}\nActions: {actions}",
+            config.description().unwrap_or_default()
+        ));
+    }
+
+    let mut range_viewer = RangeViewer::new();
+    if range_viewer.replace_ranges(ranges).is_none() {
+        return Err("post-flop-viewer: config without spots".into());
+    }
+
+    run_app("Poker Toolkit", move || {
+        Ok(Box::new(RangeEditor {
+            range_viewer,
+            titles,
+            boards,
+            board_shown: None,
+        }))
+    })
+}
+
+fn read_pre_flop_range_config(path: &str) -> Result<PreFlopRangeConfig> {
+    range_binary::load_pre_flop_range_config(Path::new(path))
+}
+
+/// Writes JSON if the output ends with `.json`, the binary format otherwise.
+fn convert_ranges(args: &[String]) -> Result<()> {
+    let [input_path, output_path] = args else {
+        return Err(INVALID_COMMAND_ERROR.into());
+    };
+
+    let config = read_pre_flop_range_config(input_path)?;
+    let writer = BufWriter::new(File::create(output_path)?);
+    if output_path.ends_with(".json") {
+        serde_json::to_writer_pretty(writer, &config.to_data())?;
+    } else {
+        range_binary::write_config(writer, &config)?;
+    }
+    Ok(())
+}
+
+fn range_diff(args: &[String]) -> Result<()> {
+    let [old_path, new_path, ..] = args else {
+        return Err(INVALID_COMMAND_ERROR.into());
+    };
+
+    let mut threshold = DiffThreshold::default();
+    let mut show_gui = false;
+    for arg in &args[2..] {
+        if arg == "--gui" {
+            show_gui = true;
+        } else if let Some(frequency) = arg.strip_prefix("--frequency=") {
+            threshold.frequency = frequency.parse::<f64>()? / 100.0;
+        } else if let Some(ev) = arg.strip_prefix("--ev=") {
+            threshold.ev = milli_big_blind_from_f64(ev.parse()?)?;
+        } else {
+            return Err(INVALID_COMMAND_ERROR.into());
+        }
+    }
+
+    let old = read_pre_flop_range_config(old_path)?;
+    let new = read_pre_flop_range_config(new_path)?;
+    let diff = PreFlopRangeConfigDiff::new(&old, &new, threshold);
+    print!("{diff}");
+
+    if !show_gui || diff.changed.is_empty() {
+        return Ok(());
+    }
+
+    let ranges = diff
+        .changed
+        .iter()
+        .map(|node| RangeValue::Heatmap(node.heatmap.clone()))
+        .collect();
+    let titles = diff
+        .changed
+        .iter()
+        .map(|node| {
+            format!(
+                "{:?}\n{} changes",
+                node.previous_actions,
+                node.entries.len()
+            )
+        })
+        .collect();
+
+    let mut range_viewer = RangeViewer::new();
+    range_viewer.replace_ranges(ranges);
+
+    run_app("Poker Toolkit", move || {
+        Ok(Box::new(RangeEditor {
+            range_viewer,
+            titles,
+            boards: Vec::new(),
+            board_shown: None,
+        }))
+    })
+}
+
+fn range_merge(args: &[String]) -> Result<()> {
+    let [base_path, overlay_path] = args else {
+        return Err(INVALID_COMMAND_ERROR.into());
+    };
+
+    let base = read_pre_flop_range_config(base_path)?;
+    let overlay = read_pre_flop_range_config(overlay_path)?;
+    let merged = range_diff::merge(&base, &overlay)?;
+
+    serde_json::to_writer_pretty(BufWriter::new(io::stdout().lock()), &merged.to_data())?;
+    Ok(())
+}
+
 struct App {
     game: GameView,
 }
 
 impl App {
-    fn new(pre_flop_ranges_config_path: Option<&str>) -> Result<Self> {
-        let game = if let Some(path) = pre_flop_ranges_config_path {
-            GameView::new_with_simple_strategy(path)?
+    fn new(
+        pre_flop_ranges_config_path: Option<&str>,
+        opponent_model: OpponentModel,
+        external_bots: Vec<(String, ExternalEngine)>,
+    ) -> Result<Self> {
+        let mut game = if let Some(path) = pre_flop_ranges_config_path {
+            GameView::new_with_simple_strategy(path, opponent_model)?
         } else {
             GameView::new()
         };
+        for (name, engine) in external_bots {
+            game.add_player_action_generator(name, move || Box::new(engine.generator()));
+        }
         Ok(Self { game })
     }
 }
@@ -352,6 +764,46 @@ impl eframe::App for App {
     }
 }
 
+struct RangeEditor {
+    range_viewer: RangeViewer,
+    /// Shown below the range with the same index.
+    titles: Vec<String>,
+    /// The blocker board of the range with the same index.
+    boards: Vec<Cards>,
+    board_shown: Option<usize>,
+}
+
+impl eframe::App for RangeEditor {
+    fn update(&mut self, ctx: &Context, _frame: &mut Frame) {
+        if let Some(title) = self.titles.get(self.range_viewer.selected()) {
+            if self.range_viewer.details() != title {
+                self.range_viewer.set_details(title.clone());
+            }
+        }
+
+        // Only on a new selection to keep edits of the board.
+        let selected = self.range_viewer.selected();
+        if let Some(board) = self.boards.get(selected) {
+            if self.board_shown != Some(selected) {
+                self.range_viewer.set_blocker_board(*board);
+                self.board_shown = Some(selected);
+            }
+        }
+
+        CentralPanel::default().show(ctx, |ui| {
+            // Leave room below the grid for the editor controls.
+            let width = ui.available_width().min(ui.available_height() * 0.6);
+            let bounding_rect = Rect::from_min_size(
+                ui.max_rect().center_top() - Vec2::new(width / 2.0, 0.0),
+                Vec2::new(width, ui.available_height()),
+            );
+            ui.allocate_new_ui(UiBuilder::new().max_rect(bounding_rect), |ui| {
+                self.range_viewer.view(ui)
+            });
+        });
+    }
+}
+
 // TODO
 struct HandHistory {
     history: HistoryView,
@@ -361,10 +813,13 @@ impl HandHistory {
     fn new(
         entries: Vec<(db::Hand, Option<db::HandPlayer>)>,
         game_getter: impl FnMut(u64) -> Result<Game> + 'static,
+        tags_db: Option<DB>,
     ) -> Result<Self> {
-        Ok(Self {
-            history: HistoryView::new(entries, Box::new(game_getter)),
-        })
+        let mut history = HistoryView::new(entries, Box::new(game_getter));
+        if let Some(tags_db) = tags_db {
+            history.set_tags_db(tags_db);
+        }
+        Ok(Self { history })
     }
 }
 
@@ -0,0 +1,213 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::game::{milli_big_blind_to_amount_rounded, Game, MilliBigBlind, Street};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BetSize {
+    /// Relative to the pot after calling, 1.0 is a pot sized bet or raise.
+    PotFraction(f64),
+    /// The total amount in the street.
+    MilliBigBlinds(MilliBigBlind),
+    AllIn,
+}
+
+impl BetSize {
+    /// The legal bet or raise amount for the current player.
+    pub fn to_amount(self, game: &Game) -> Option<u32> {
+        let amount = match self {
+            BetSize::PotFraction(fraction) => amount_from_pot_fraction(game, fraction)?,
+            BetSize::MilliBigBlinds(amount) => {
+                milli_big_blind_to_amount_rounded(amount, game.big_blind())?
+            }
+            BetSize::AllIn => game.can_all_in()?,
+        };
+        legal_amount(game, amount)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StreetSizes {
+    pub bets: Vec<BetSize>,
+    pub raises: Vec<BetSize>,
+}
+
+/// The bet and raise sizes bots pick from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BetSizing {
+    pub pre_flop: StreetSizes,
+    pub flop: StreetSizes,
+    pub turn: StreetSizes,
+    pub river: StreetSizes,
+}
+
+impl Default for BetSizing {
+    fn default() -> Self {
+        let post_flop = StreetSizes {
+            bets: vec![BetSize::PotFraction(0.33), BetSize::PotFraction(0.66)],
+            raises: vec![BetSize::PotFraction(0.75)],
+        };
+
+        Self {
+            pre_flop: StreetSizes {
+                bets: Vec::new(),
+                raises: vec![BetSize::PotFraction(1.0)],
+            },
+            flop: post_flop.clone(),
+            turn: post_flop.clone(),
+            river: post_flop,
+        }
+    }
+}
+
+impl BetSizing {
+    pub fn street(&self, street: Street) -> &StreetSizes {
+        match street {
+            Street::PreFlop => &self.pre_flop,
+            Street::Flop => &self.flop,
+            Street::Turn => &self.turn,
+            Street::River => &self.river,
+        }
+    }
+
+    /// The legal amounts of the configured bet or raise sizes
+    /// for the current player, sorted and without duplicates.
+    pub fn amounts(&self, game: &Game) -> Vec<u32> {
+        let sizes = self.street(game.board().street());
+        let sizes = if game.can_bet().is_some() {
+            &sizes.bets
+        } else if game.can_raise().is_some() {
+            &sizes.raises
+        } else {
+            return Vec::new();
+        };
+
+        let mut amounts: Vec<_> = sizes
+            .iter()
+            .filter_map(|size| size.to_amount(game))
+            .collect();
+        amounts.sort_unstable();
+        amounts.dedup();
+        amounts
+    }
+}
+
+/// The size of a bet or raise of the current player relative to the pot after calling.
+pub fn pot_fraction(game: &Game, amount: u32) -> Option<f64> {
+    let (call_to, pot) = call_to_and_pot(game)?;
+    Some((f64::from(amount) - f64::from(call_to)) / f64::from(pot))
+}
+
+/// The bet or raise amount of the current player for a pot fraction, not clamped.
+pub fn amount_from_pot_fraction(game: &Game, fraction: f64) -> Option<u32> {
+    let (call_to, pot) = call_to_and_pot(game)?;
+    Some(call_to + (f64::from(pot) * fraction.max(0.0)).round() as u32)
+}
+
+fn call_to_and_pot(game: &Game) -> Option<(u32, u32)> {
+    let player = game.current_player()?;
+    let call = game.can_call().unwrap_or(0);
+    let pot = game.total_pot() + call;
+    if pot == 0 {
+        return None;
+    }
+    Some((game.invested_in_street(player) + call, pot))
+}
+
+/// Clamps a bet or raise amount between the minimum and all-in,
+/// none if the current player can neither bet nor raise.
+pub fn legal_amount(game: &Game, amount: u32) -> Option<u32> {
+    let player = game.current_player()?;
+    let min_amount = game
+        .can_bet()
+        .or_else(|| game.can_raise().map(|(_, min_to)| min_to))?;
+    let max_amount = game.previous_street_stacks()[player];
+    Some(amount.max(min_amount).min(max_amount))
+}
+
+/// The probability of translating the size `x` to the smaller size `a` instead of `b`,
+/// all as pot fractions (Ganzfried and Sandholm's pseudo-harmonic mapping).
+pub fn pseudo_harmonic(a: f64, b: f64, x: f64) -> f64 {
+    if x <= a {
+        return 1.0;
+    }
+    if x >= b {
+        return 0.0;
+    }
+    ((b - x) * (1.0 + a)) / ((b - a) * (1.0 + x))
+}
+
+/// Translates an off-tree size to the index of one of the neighbouring sizes,
+/// randomized by the pseudo-harmonic mapping. Sizes are pot fractions in any order.
+pub fn translate(rng: &mut impl Rng, sizes: &[f64], x: f64) -> Option<usize> {
+    let below = sizes
+        .iter()
+        .enumerate()
+        .filter(|(_, size)| **size <= x)
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(index, _)| index);
+    let above = sizes
+        .iter()
+        .enumerate()
+        .filter(|(_, size)| **size >= x)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(index, _)| index);
+
+    match (below, above) {
+        (Some(below), Some(above)) if sizes[below] != sizes[above] => {
+            let probability = pseudo_harmonic(sizes[below], sizes[above], x);
+            if rng.gen_bool(probability.clamp(0.0, 1.0)) {
+                Some(below)
+            } else {
+                Some(above)
+            }
+        }
+        (Some(index), _) | (None, Some(index)) => Some(index),
+        (None, None) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::game::GameData;
+
+    use super::*;
+
+    #[test]
+    fn test_translate() {
+        unsafe {
+            crate::init::init();
+        }
+
+        assert_eq!(pseudo_harmonic(0.5, 1.0, 0.5), 1.0);
+        assert_eq!(pseudo_harmonic(0.5, 1.0, 1.0), 0.0);
+        // Closer to the larger size than the arithmetic midpoint.
+        assert!((pseudo_harmonic(0.5, 1.0, 0.75) - 3.0 / 7.0).abs() < 1e-9);
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let sizes = [1.0, 0.5, 2.0];
+        assert_eq!(translate(&mut rng, &sizes, 0.25), Some(1));
+        assert_eq!(translate(&mut rng, &sizes, 5.0), Some(2));
+        assert_eq!(translate(&mut rng, &sizes, 1.0), Some(0));
+        let smaller = (0..1_000)
+            .filter(|_| translate(&mut rng, &sizes, 0.75) == Some(1))
+            .count();
+        assert!((380..480).contains(&smaller));
+
+        let mut game = Game::from_game_data(&GameData::default()).unwrap();
+        game.post_small_and_big_blind().unwrap();
+        // A pot sized open is 3.5 big blinds.
+        assert_eq!(BetSize::PotFraction(1.0).to_amount(&game), Some(35));
+        assert_eq!(pot_fraction(&game, 35), Some(1.0));
+        // Below the minimum raise and above all-in.
+        assert_eq!(BetSize::PotFraction(0.0).to_amount(&game), Some(20));
+        assert_eq!(
+            BetSize::MilliBigBlinds(200_000).to_amount(&game),
+            Some(1_000)
+        );
+        assert_eq!(BetSizing::default().amounts(&game), vec![35]);
+    }
+}
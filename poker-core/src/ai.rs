@@ -7,13 +7,19 @@ use std::{
 use rand::{rngs::StdRng, SeedableRng};
 
 use crate::{
-    game::{milli_big_blind_to_amount_rounded, Game, Street},
+    abstraction::{self, BetSizing},
+    card::Card,
+    cards::Cards,
+    equity::EquityTable,
+    game::{milli_big_blind_to_amount_rounded, milli_big_blind_to_f64_approximate, Game, Street},
+    opponent::OpponentModel,
     range::{
-        PreFlopAction, PreFlopRangeConfig, RangeActionKind, RangeConfigEntry, RangeEntry,
-        RangeTable, RangeTableWith, MAX_FREQUENCY,
+        PreFlopAction, PreFlopRangeConfigSet, RangeAction, RangeActionKind, RangeConfigEntry,
+        RangeEntry, RangeTable, RangeTableWith, MAX_FREQUENCY,
     },
     rank::Rank,
     result::Result,
+    suite::Suite,
 };
 
 #[derive(Debug)]
@@ -157,7 +163,8 @@ impl AiAction {
     }
 }
 
-pub trait PlayerActionGenerator {
+/// Generators run on a worker thread in the GUI.
+pub trait PlayerActionGenerator: Send {
     fn update_villain(&mut self, _game: &Game, log: &mut String) -> Result<()> {
         writeln!(log, "Not implemented")?;
         Ok(())
@@ -217,21 +224,50 @@ impl PlayerActionGenerator for AlwaysAllIn {
 pub struct SimpleStrategy {
     rng: StdRng,
     current_ranges: Vec<RangeTableWith<u16>>,
-    pre_flop_ranges: Arc<PreFlopRangeConfig>,
+    pre_flop_ranges: Arc<PreFlopRangeConfigSet>,
+    opponent_model: OpponentModel,
+    bet_sizing: BetSizing,
 }
 
 impl PlayerActionGenerator for SimpleStrategy {
     fn update_villain(&mut self, game: &Game, log: &mut String) -> Result<()> {
-        // Using self range calculation for enemy.
-
         let action = game.actions().last().copied().unwrap();
-        let action = RangeActionKind::from_game_action(game, action)?;
+        let Some(villain) = action.player() else {
+            return Ok(());
+        };
 
-        let mut game = game.clone();
-        assert!(game.previous());
+        if game.board().street() == Street::PreFlop {
+            if let Some(range) = self
+                .opponent_model
+                .pre_flop_range(game, &self.current_ranges[villain])
+            {
+                writeln!(log, "Villain: Pre Flop range from observed tendencies")?;
+                self.current_ranges[villain] = range;
+                return Ok(());
+            }
 
-        let range = self.player(&game, log)?;
-        self.current_ranges[game.current_player().unwrap()] = range.action_range(action).unwrap();
+            // Using self range calculation for enemy.
+            let range_action = RangeActionKind::from_game_action(game, action)?;
+            let mut previous_game = game.clone();
+            assert!(previous_game.previous());
+
+            let range = self.player(&previous_game, log)?;
+            if let Some(closest_action) = range.closest_action(range_action) {
+                self.current_ranges[villain] = range.action_range(closest_action).unwrap();
+                return Ok(());
+            }
+            writeln!(
+                log,
+                "Villain: Action {range_action} not in range, narrowing by hand strength"
+            )?;
+        }
+
+        if self
+            .opponent_model
+            .narrow(game, &mut self.current_ranges[villain])
+        {
+            writeln!(log, "Villain: Narrowed range by hand strength")?;
+        }
 
         Ok(())
     }
@@ -245,21 +281,46 @@ impl PlayerActionGenerator for SimpleStrategy {
         let action = range.pick(&mut self.rng, game.current_hand().unwrap());
         self.current_ranges[game.current_player().unwrap()] = range.action_range(action).unwrap();
 
-        let action = AiAction::from_range(action, game.big_blind())?;
+        let action = match AiAction::from_range(action, game.big_blind())? {
+            // Converting from milli big blinds might round below the minimum.
+            AiAction::BetRaise(amount) => {
+                AiAction::BetRaise(abstraction::legal_amount(game, amount).unwrap_or(amount))
+            }
+            action => action,
+        };
         Ok((action, range, Some(&self.current_ranges)))
     }
 }
 
 impl SimpleStrategy {
-    pub fn new(pre_flop_ranges: Arc<PreFlopRangeConfig>) -> Self {
+    const POST_FLOP_EQUITY_ROUNDS: u64 = 50_000;
+    /// Hands below this equity bluff heads-up when checked to.
+    const BLUFF_EQUITY: f64 = 0.25;
+    const BLUFF_FREQUENCY: u16 = 3_000;
+
+    /// The ranges closest to the effective stack are interpolated.
+    pub fn new(pre_flop_ranges: Arc<PreFlopRangeConfigSet>) -> Self {
         Self {
             rng: StdRng::from_entropy(),
             pre_flop_ranges,
             current_ranges: vec![RangeTable::FULL.to_frequencies(MAX_FREQUENCY); Game::MAX_PLAYERS],
+            opponent_model: OpponentModel::default(),
+            bet_sizing: BetSizing::default(),
         }
     }
 
-    fn player(&self, game: &Game, log: &mut String) -> Result<RangeConfigEntry> {
+    /// The post flop bet and raise sizes, pre flop sizes come from the ranges.
+    pub fn with_bet_sizing(mut self, bet_sizing: BetSizing) -> Self {
+        self.bet_sizing = bet_sizing;
+        self
+    }
+
+    pub fn with_opponent_model(mut self, opponent_model: OpponentModel) -> Self {
+        self.opponent_model = opponent_model;
+        self
+    }
+
+    fn player(&mut self, game: &Game, log: &mut String) -> Result<RangeConfigEntry> {
         if game.board().street() == Street::PreFlop {
             self.pre_flop(game, log)
         } else {
@@ -267,7 +328,7 @@ impl SimpleStrategy {
         }
     }
 
-    fn pre_flop(&self, game: &Game, log: &mut String) -> Result<RangeConfigEntry> {
+    fn pre_flop(&mut self, game: &Game, log: &mut String) -> Result<RangeConfigEntry> {
         let mut config = self.pre_flop_inner(game, log)?;
 
         let aces_kings = [
@@ -298,40 +359,95 @@ impl SimpleStrategy {
         Ok(config)
     }
 
-    fn pre_flop_inner(&self, game: &Game, log: &mut String) -> Result<RangeConfigEntry> {
+    fn pre_flop_inner(&mut self, game: &Game, log: &mut String) -> Result<RangeConfigEntry> {
         // TODO:
-        // Custom pre flop logic and adaptation for things like limping,
-        // unexpected calls, crazy sizings.
+        // Custom pre flop logic and adaptation for things like limping.
+
+        let pre_flop_ranges = self.pre_flop_ranges.clone();
+        let entries = match pre_flop_ranges.by_game_interpolated(game, &mut self.rng) {
+            Ok(entries) => entries,
+            // Just fold if the config does not match or another error occurred.
+            // Might be confusing, if the errors are just eaten by this function
+            // without any feedback.
+
+            // TODO:
+            // Ranges might have random holes that can totally happen in real life.
+            // Also stuff like limping. Use custom logic here.
+            Err(err) => {
+                writeln!(
+                    log,
+                    "Pre Flop: An error occurred while finding the range: {err}"
+                )?;
+                return self.current_range_check_fold(game);
+            }
+        };
 
-        let (range, diff_milli_big_blinds) =
-            match self.pre_flop_ranges.by_game_best_fit_raise_simple(game) {
-                Ok(range_diff) => range_diff,
-                // Just fold if the config does not match or another error occurred.
-                // Might be confusing, if the errors are just eaten by this function
-                // without any feedback.
+        let mut config: Option<RangeConfigEntry> = None;
+        let mut total_weight = 0.0;
+        for weighted in entries {
+            writeln!(
+                log,
+                "Pre Flop: Using {:.1} bb ranges with weight {:.2}",
+                milli_big_blind_to_f64_approximate(weighted.config.depth()),
+                weighted.weight
+            )?;
 
-                // TODO:
-                // Ranges might have random holes that can totally happen in real life.
-                // Also stuff like limping. Use custom logic here.
-                Err(err) => {
-                    writeln!(
-                        log,
-                        "Pre Flop: An error occurred while calculating range best fit: {err}"
-                    )?;
-                    return self.current_range_check_fold(game);
+            let entry_game = weighted.config.entry_game(weighted.entry)?;
+            let entry =
+                Self::translate_raise_sizes(game, &entry_game, weighted.entry.to_full_range())?;
+
+            total_weight += weighted.weight;
+            config = Some(match config {
+                Some(config) => config.interpolate(&entry, weighted.weight / total_weight)?,
+                None => entry,
+            });
+        }
+
+        Ok(config.unwrap())
+    }
+
+    /// Maps the raise sizes of the config to the same pot fractions in the game,
+    /// the config might have been picked for other sizes.
+    fn translate_raise_sizes(
+        game: &Game,
+        config_game: &Game,
+        config: RangeConfigEntry,
+    ) -> Result<RangeConfigEntry> {
+        let mut actions: Vec<(RangeActionKind, RangeTableWith<u16>)> = Vec::new();
+        for action in config.actions() {
+            let kind = match action.action() {
+                // Facing an all-in raises turn into calls.
+                RangeActionKind::Raise(_) if game.can_raise().is_none() => RangeActionKind::Call,
+                RangeActionKind::Raise(to) => {
+                    // Config games use a big blind of 1000.
+                    let amount = u32::try_from(to)?;
+                    let amount = abstraction::pot_fraction(config_game, amount)
+                        .and_then(|fraction| abstraction::amount_from_pot_fraction(game, fraction))
+                        .and_then(|amount| abstraction::legal_amount(game, amount))
+                        .ok_or("pre flop: raise size translation failed")?;
+                    RangeActionKind::Raise(game.amount_to_milli_big_blinds_rounded(amount))
                 }
+                kind => kind,
             };
 
-        if diff_milli_big_blinds >= 15_000 {
-            // TODO: Arbitrary choice, in reality this might be way too large in most situations.
-            writeln!(
-                log,
-                "Pre Flop: Diff milli big blinds too big: {diff_milli_big_blinds}"
-            )?;
-            return self.current_range_check_fold(game);
+            // Sizes might end up the same after clamping.
+            if let Some((_, range)) = actions.iter_mut().find(|(current, _)| *current == kind) {
+                for (hand, frequency) in range.iter_mut() {
+                    *frequency = frequency
+                        .saturating_add(action.range()[hand])
+                        .min(MAX_FREQUENCY);
+                }
+            } else {
+                actions.push((kind, action.range().clone()));
+            }
         }
 
-        Ok(range.to_full_range())
+        let total_range = config.total_range().clone();
+        let actions = actions
+            .into_iter()
+            .map(|(kind, range)| RangeAction::new(kind, &total_range, range))
+            .collect();
+        RangeConfigEntry::new(total_range, actions)
     }
 
     fn current_range_check_fold(&self, game: &Game) -> Result<RangeConfigEntry> {
@@ -342,12 +458,238 @@ impl SimpleStrategy {
     }
 
     fn post_flop(&self, game: &Game, log: &mut String) -> Result<RangeConfigEntry> {
-        // TODO
-        writeln!(log, "Post Flop: Currently only check/call")?;
+        let player = game.current_player().unwrap();
+        let board = game.board();
+        let Some(board_cards) = Cards::from_slice(board.cards()) else {
+            return Err("post flop: invalid board".into());
+        };
+
+        let mut total_range = self.current_ranges[player].clone();
+        for (hand, frequency) in total_range.iter_mut() {
+            if board_cards.has(hand.high()) || board_cards.has(hand.low()) {
+                *frequency = 0;
+            }
+        }
 
-        let total_range = self.current_ranges[game.current_player().unwrap()].clone();
-        let action = AiAction::CheckCall.to_range(game)?;
-        let config = RangeConfigEntry::distribute_action(total_range, action)?;
-        Ok(config)
+        // TODO: Weight the equity calculation by the range frequencies.
+        let mut ranges = vec![Box::new(RangeTable::from_frequencies_not_zero(
+            &total_range,
+        ))];
+        for villain in game
+            .players_not_folded()
+            .filter(|villain| *villain != player)
+        {
+            ranges.push(Box::new(RangeTable::from_frequencies_not_zero(
+                &self.current_ranges[villain],
+            )));
+        }
+        let villain_count = ranges.len() - 1;
+
+        // Enumerating is cheap enough heads-up on the river.
+        let equity_tables = if board.street() == Street::River && villain_count == 1 {
+            EquityTable::enumerate(board_cards, &ranges)
+        } else {
+            EquityTable::simulate(board_cards, &ranges, Self::POST_FLOP_EQUITY_ROUNDS)
+        };
+        let Some(equity_tables) = equity_tables else {
+            writeln!(
+                log,
+                "Post Flop: Equity calculation failed, check/call with the whole range"
+            )?;
+            let action = AiAction::CheckCall.to_range(game)?;
+            return RangeConfigEntry::distribute_action(total_range, action);
+        };
+        let equity_table = &equity_tables[0];
+
+        let texture = BoardTexture::new(board.cards());
+        writeln!(
+            log,
+            "Post Flop: {texture:?}, range equity {:.2}% against {villain_count} villain(s)",
+            equity_table.total_equity_percent() * 100.0
+        )?;
+
+        let pot = game.total_pot();
+        let call = game.can_call();
+        // Larger bets on wet boards, raises use the smallest size.
+        let amounts = self.bet_sizing.amounts(game);
+        let amount = if call.is_none() && texture.wet {
+            amounts.last()
+        } else {
+            amounts.first()
+        };
+        let aggressive_action = amount
+            .map(|amount| AiAction::BetRaise(*amount).to_range(game))
+            .transpose()?;
+        let passive_action = AiAction::CheckCall.to_range(game)?;
+
+        // Multiway pots and missing implied odds on the river need more equity.
+        let villain_penalty = 0.05 * (villain_count - 1) as f64;
+        let (aggressive_equity, passive_equity) = if let Some(call) = call {
+            let pot_odds = f64::from(call) / f64::from(pot + call);
+            let margin = if board.street() == Street::River {
+                0.05
+            } else {
+                0.0
+            };
+            (0.8 + villain_penalty, pot_odds + margin + villain_penalty)
+        } else {
+            // Checking is always possible without a bet.
+            (0.65 + villain_penalty, 0.0)
+        };
+
+        let mut aggressive_range = RangeTableWith::default();
+        let mut passive_range = RangeTableWith::default();
+        for (hand, frequency) in total_range.iter() {
+            if *frequency == 0 {
+                continue;
+            }
+
+            // Hands without data are blocked by all villain combos.
+            let equity = if equity_table.has_data(hand) {
+                equity_table.equity_percent(hand)
+            } else {
+                0.5
+            };
+
+            let aggressive_frequency = if aggressive_action.is_none() {
+                0
+            } else if equity >= aggressive_equity {
+                MAX_FREQUENCY
+            } else if call.is_none() && villain_count == 1 && equity < Self::BLUFF_EQUITY {
+                Self::BLUFF_FREQUENCY
+            } else {
+                0
+            };
+            aggressive_range[hand] = aggressive_frequency;
+
+            // The rest folds.
+            if equity >= passive_equity {
+                passive_range[hand] = MAX_FREQUENCY - aggressive_frequency;
+            }
+        }
+
+        let mut actions = Vec::new();
+        if let Some(aggressive_action) = aggressive_action {
+            actions.push(RangeAction::new(
+                aggressive_action,
+                &total_range,
+                aggressive_range,
+            ));
+        }
+        actions.push(RangeAction::new(
+            passive_action,
+            &total_range,
+            passive_range,
+        ));
+
+        RangeConfigEntry::new(total_range, actions)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct BoardTexture {
+    /// Flush or straight draws are possible.
+    wet: bool,
+}
+
+impl BoardTexture {
+    fn new(board: &[Card]) -> Self {
+        let mut suite_counts = [0u8; Suite::COUNT];
+        let mut ranks = 0u16;
+        for card in board {
+            suite_counts[card.suite().to_usize()] += 1;
+            ranks |= 1 << card.rank().to_u16();
+        }
+
+        // The ace also plays low.
+        if ranks & (1 << Rank::Ace.to_u16()) != 0 {
+            ranks = (ranks << 1) | 1;
+        } else {
+            ranks <<= 1;
+        }
+
+        let flush_draw = suite_counts.iter().any(|count| *count >= 2);
+        let straight_draw =
+            (0..Rank::COUNT + 1).any(|low| (ranks >> low & 0b1_1111).count_ones() >= 3);
+
+        Self {
+            wet: flush_draw || straight_draw,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        hand::Hand,
+        range::{PostFlopRangeConfigEntry, PreFlopRangeConfig},
+    };
+
+    use super::*;
+
+    /// Heads up after a 2.5 bb open and a call on Jh 7h 2c (3d), the villain has an
+    /// overpair or top pair.
+    fn game(board: &[Card], actions: &[RangeActionKind]) -> Game {
+        PostFlopRangeConfigEntry::build_game(
+            2,
+            100_000,
+            500,
+            &[PreFlopAction::Raise(2_500), PreFlopAction::Call],
+            board,
+            actions,
+        )
+        .unwrap()
+    }
+
+    /// The action of the current player holding `hand`, their range is just that hand.
+    fn hero_action(game: &Game, hand: &str) -> AiAction {
+        let hero = game.current_player().unwrap();
+        let hand: Hand = hand.parse().unwrap();
+        let mut game = game.clone();
+        game.set_hand(hero, hand).unwrap();
+
+        let pre_flop_ranges =
+            PreFlopRangeConfigSet::new(vec![PreFlopRangeConfig::default()]).unwrap();
+        let mut strategy = SimpleStrategy::new(Arc::new(pre_flop_ranges));
+        strategy.rng = StdRng::seed_from_u64(42);
+        strategy.current_ranges[hero] = RangeTable::parse(&hand.to_string())
+            .unwrap()
+            .to_frequencies(MAX_FREQUENCY);
+        strategy.current_ranges[1 - hero] = RangeTable::parse("TT+,AJs,AJo")
+            .unwrap()
+            .to_frequencies(MAX_FREQUENCY);
+
+        let (action, _, _) = strategy.update_hero(&game, &mut String::new()).unwrap();
+        action
+    }
+
+    #[test]
+    fn test_simple_strategy_post_flop() {
+        unsafe {
+            crate::init::init();
+        }
+
+        let cards = |cards: &[&str]| -> Vec<Card> {
+            cards.iter().map(|card| card.parse().unwrap()).collect()
+        };
+
+        // Checked to on the flop, the set bets and the flush draw checks.
+        let flop = game(&cards(&["Jh", "7h", "2c"]), &[]);
+        assert!(matches!(hero_action(&flop, "JdJs"), AiAction::BetRaise(_)));
+        assert!(matches!(hero_action(&flop, "KhQh"), AiAction::CheckFold));
+
+        // Facing 1 bb into 5 bb on the turn, the set raises, the flush draw calls
+        // and the gutshot folds.
+        let turn = game(
+            &cards(&["Jh", "7h", "2c", "3d"]),
+            &[
+                RangeActionKind::Check,
+                RangeActionKind::Check,
+                RangeActionKind::Bet(1_000),
+            ],
+        );
+        assert!(matches!(hero_action(&turn, "JdJs"), AiAction::BetRaise(_)));
+        assert!(matches!(hero_action(&turn, "KhQh"), AiAction::CheckCall));
+        assert!(matches!(hero_action(&turn, "9c8c"), AiAction::Fold));
     }
 }
@@ -1,11 +1,11 @@
-use std::{fmt::Write, path::Path, str::FromStr, sync::Arc};
+use std::{collections::HashMap, fmt::Write, io, path::Path, str::FromStr, sync::Arc};
 
 use chrono::NaiveDateTime;
 use rusqlite::{
     functions::{Context, FunctionFlags},
     params,
     types::{FromSql, FromSqlError, FromSqlResult, Type, Value, ValueRef},
-    Connection, Params, Row, RowIndex, Transaction,
+    Connection, OpenFlags, Params, Row, RowIndex, Transaction,
 };
 
 use crate::{
@@ -14,37 +14,81 @@ use crate::{
     cards::Cards,
     game::{Game, GameData, State, Street},
     hand,
-    result::Result,
+    opponent::PlayerTendencies,
+    result::{Error, Result},
 };
 
 // TODO
-// - Extra checks, e.g. every hand has matching metadata entries
 // - Check entries for correctness when reading from the database
+//   (currently only done by verify)
 
 pub struct DB {
     conn: Connection,
 }
 
 const SCHEMA: &str = include_str!("schema.sql");
+/// Tables added to the schema later, missing in older databases until they are
+/// opened for writing.
+const ADDED_TABLES: [&str; 2] = ["hands_tags", "players_notes"];
 
 impl DB {
-    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
-        // TODO: Extra open when not creating db.
+    pub const ALL_HAND_IDS_QUERY: &str = "SELECT id FROM hands ORDER BY id";
+    pub const BOOKMARK_TAG: &str = "bookmark";
 
+    /// Opens the database and creates it first if it does not exist yet.
+    pub fn create(path: impl AsRef<Path>) -> Result<Self> {
+        let flags = OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_CREATE;
         let db = Self {
-            conn: Connection::open(path)?,
+            conn: Connection::open_with_flags(path, flags | Self::DEFAULT_OPEN_FLAGS)?,
         };
-        db.init_schema()?;
+        db.conn.pragma_update(None, "encoding", "UTF-8")?;
+        db.init_write_pragmas()?;
+        db.conn.execute_batch(SCHEMA)?;
         db.create_scalars()?;
         db.check_schema()?;
         Ok(db)
     }
 
-    fn init_schema(&self) -> Result<()> {
-        self.conn.pragma_update(None, "encoding", "UTF-8")?;
+    /// Older databases are upgraded, the schema is only ever extended.
+    pub fn open_existing(path: impl AsRef<Path>) -> Result<Self> {
+        let flags = OpenFlags::SQLITE_OPEN_READ_WRITE;
+        let db = Self {
+            conn: Connection::open_with_flags(path, flags | Self::DEFAULT_OPEN_FLAGS)?,
+        };
+        db.init_write_pragmas()?;
+        db.conn.execute_batch(SCHEMA)?;
+        db.create_scalars()?;
+        db.check_schema()?;
+        Ok(db)
+    }
+
+    /// Can be used while another process is writing to the database.
+    /// Older databases are not upgraded, the notes and tags are missing until then.
+    pub fn open_read_only(path: impl AsRef<Path>) -> Result<Self> {
+        let flags = OpenFlags::SQLITE_OPEN_READ_ONLY;
+        let db = Self {
+            conn: Connection::open_with_flags(path, flags | Self::DEFAULT_OPEN_FLAGS)?,
+        };
+        db.conn.pragma_update(None, "query_only", "ON")?;
+        db.create_scalars()?;
+        db.check_schema()?;
+        Ok(db)
+    }
+
+    const DEFAULT_OPEN_FLAGS: OpenFlags =
+        OpenFlags::SQLITE_OPEN_URI.union(OpenFlags::SQLITE_OPEN_NO_MUTEX);
+
+    fn init_write_pragmas(&self) -> Result<()> {
+        // WAL allows readers in other processes while writing.
+        let journal_mode: String =
+            self.conn
+                .pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get(0))?;
+        if !journal_mode.eq_ignore_ascii_case("wal") {
+            return Err(format!("db: could not enable WAL, journal mode is {journal_mode}").into());
+        }
+
         self.conn.pragma_update(None, "synchronous", "EXTRA")?;
         self.conn.pragma_update(None, "foreign_keys", "ON")?;
-        self.conn.execute_batch(SCHEMA)?;
         Ok(())
     }
 
@@ -88,24 +132,30 @@ impl DB {
         let mem = Connection::open_in_memory()?;
         mem.execute_batch(SCHEMA)?;
 
+        let schema = Self::schema(&self.conn)?;
+        let mut expected = Self::schema(&mem)?;
+        expected.retain(|(name, _)| {
+            !ADDED_TABLES.contains(&name.as_str()) || schema.iter().any(|(other, _)| other == name)
+        });
+
         // Only a simple check, schemas might still be equal,
         // except for some formatting etc.
-        if Self::schema(&self.conn)? != Self::schema(&mem)? {
-            // TODO: Automatically update schema.
+        if schema != expected {
             Err("db: schema does not match expected schema".into())
         } else {
             Ok(())
         }
     }
 
-    fn schema(conn: &Connection) -> Result<Vec<String>> {
+    fn schema(conn: &Connection) -> Result<Vec<(String, String)>> {
         let mut stmt = conn.prepare(
-            "SELECT sql FROM sqlite_schema
+            "SELECT name, sql FROM sqlite_schema
             WHERE name NOT LIKE 'sqlite_%'
             ORDER BY name",
         )?;
-        let sql: std::result::Result<Vec<String>, _> =
-            stmt.query_map((), |row| row.get(0))?.collect();
+        let sql: std::result::Result<Vec<_>, _> = stmt
+            .query_map((), |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect();
         Ok(sql?)
     }
 
@@ -245,6 +295,195 @@ impl DB {
         Ok(count != 0)
     }
 
+    /// Replays all stored hand data and compares the results with the derived rows.
+    /// Derived rows are only changed if repair is set.
+    pub fn verify(&mut self, repair: bool) -> Result<VerifyReport> {
+        let tx = self.conn.transaction()?;
+        let mut report = VerifyReport {
+            hands_checked: 0,
+            issues: Vec::new(),
+        };
+
+        {
+            let mut hands_data = tx.prepare("SELECT id, hand_data FROM hands_data ORDER BY id")?;
+            let mut rows = hands_data.query(())?;
+            while let Some(row) = rows.next()? {
+                let id: u64 = row.get("id")?;
+                let data: String = row.get("hand_data")?;
+                Self::verify_hand(&tx, id, &data, repair, &mut report.issues)?;
+                report.hands_checked += 1;
+            }
+        }
+
+        let orphan_hands: Vec<u64> = tx
+            .prepare(
+                "SELECT id FROM hands WHERE id NOT IN (SELECT id FROM hands_data) ORDER BY id",
+            )?
+            .query_map((), |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        for hand_id in orphan_hands {
+            if repair {
+                tx.execute("DELETE FROM hands_tags WHERE hand_id = ?", (hand_id,))?;
+                Self::delete_derived_rows(&tx, hand_id)?;
+            }
+            report.issues.push(VerifyIssue {
+                hand_id,
+                kind: VerifyIssueKind::OrphanHand,
+                message: "hand without hand data".to_owned(),
+                repaired: repair,
+            });
+        }
+
+        let orphan_players: Vec<u64> = tx
+            .prepare(
+                "SELECT DISTINCT hand_id FROM hands_players
+                WHERE hand_id NOT IN (SELECT id FROM hands)
+                ORDER BY hand_id",
+            )?
+            .query_map((), |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        for hand_id in orphan_players {
+            if repair {
+                tx.execute("DELETE FROM hands_players WHERE hand_id = ?", (hand_id,))?;
+            }
+            report.issues.push(VerifyIssue {
+                hand_id,
+                kind: VerifyIssueKind::OrphanPlayers,
+                message: "players without hand".to_owned(),
+                repaired: repair,
+            });
+        }
+
+        if repair {
+            tx.commit()?;
+        }
+        Ok(report)
+    }
+
+    fn verify_hand(
+        tx: &Transaction<'_>,
+        id: u64,
+        data: &str,
+        repair: bool,
+        issues: &mut Vec<VerifyIssue>,
+    ) -> Result<()> {
+        let mut issue = |kind, message| {
+            issues.push(VerifyIssue {
+                hand_id: id,
+                kind,
+                message,
+                repaired: repair && kind.repairable(),
+            });
+        };
+
+        let replay = || -> Result<(Game, GameData)> {
+            let game_data: GameData = serde_json::from_str(data)?;
+            Ok((Game::from_game_data(&game_data)?, game_data))
+        };
+        let (game, game_data) = match replay() {
+            Ok((game, _)) if game.state() != State::End => {
+                issue(
+                    VerifyIssueKind::InvalidHandData,
+                    "game not in end state".to_owned(),
+                );
+                return Ok(());
+            }
+            Ok(game) => game,
+            Err(err) => {
+                issue(VerifyIssueKind::InvalidHandData, err.to_string());
+                return Ok(());
+            }
+        };
+
+        if game.to_game_data() != game_data {
+            issue(
+                VerifyIssueKind::HandDataNotNormalized,
+                "hand data differs from the replayed game".to_owned(),
+            );
+        }
+
+        let mut expected = HandBundle::from_game(&game);
+        expected.hand.id = Some(id);
+        for player in &mut expected.players {
+            player.hand_id = Some(id);
+        }
+
+        let stored_hand = tx
+            .prepare("SELECT * FROM hands WHERE id = ?")?
+            .query_map((id,), Hand::from_row)?
+            .next()
+            .transpose();
+        let stored_players = tx
+            .prepare("SELECT * FROM hands_players WHERE hand_id = ? ORDER BY player")?
+            .query_map((id,), HandPlayer::from_row)?
+            .collect::<rusqlite::Result<Option<Vec<_>>>>();
+
+        let mut needs_repair = false;
+        match stored_hand {
+            Ok(Some(hand)) if hand == expected.hand => (),
+            Ok(Some(hand)) => {
+                issue(
+                    VerifyIssueKind::HandMismatch,
+                    format!("stored: {hand:?}\nexpected: {:?}", expected.hand),
+                );
+                needs_repair = true;
+            }
+            Ok(None) => {
+                issue(VerifyIssueKind::MissingHand, "hand row missing".to_owned());
+                needs_repair = true;
+            }
+            Err(err) => {
+                issue(
+                    VerifyIssueKind::HandMismatch,
+                    format!("invalid hand row: {err}"),
+                );
+                needs_repair = true;
+            }
+        }
+
+        match stored_players {
+            Ok(Some(players)) if players == expected.players => (),
+            Ok(Some(players)) => {
+                issue(
+                    VerifyIssueKind::PlayersMismatch,
+                    format!("stored: {players:?}\nexpected: {:?}", expected.players),
+                );
+                needs_repair = true;
+            }
+            // Only possible with a NULL hand id or player, which the schema forbids.
+            Ok(None) => {
+                issue(
+                    VerifyIssueKind::PlayersMismatch,
+                    "player row without hand id".to_owned(),
+                );
+                needs_repair = true;
+            }
+            Err(err) => {
+                issue(
+                    VerifyIssueKind::PlayersMismatch,
+                    format!("invalid player row: {err}"),
+                );
+                needs_repair = true;
+            }
+        }
+
+        if repair && needs_repair {
+            Self::delete_derived_rows(tx, id)?;
+            Self::add_hand_info(tx, expected.hand, id)?;
+            for player in expected.players {
+                Self::add_hand_player(tx, player, id)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn delete_derived_rows(tx: &Transaction<'_>, hand_id: u64) -> Result<()> {
+        tx.execute("DELETE FROM hands_players WHERE hand_id = ?", (hand_id,))?;
+        tx.execute("DELETE FROM hands WHERE id = ?", (hand_id,))?;
+        Ok(())
+    }
+
     pub fn query_for_each(
         &self,
         query: &str,
@@ -309,6 +548,300 @@ impl DB {
         let game_data: GameData = serde_json::from_str(&game_data)?;
         Ok(game_data)
     }
+
+    pub fn add_game_data(&mut self, data: &[GameData]) -> Result<u64> {
+        let games = data
+            .iter()
+            .enumerate()
+            .map(|(index, game_data)| {
+                Game::from_game_data(game_data)
+                    .map_err(|err| format!("db: invalid game data at index {index}: {err}").into())
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        self.add_games(games.iter())
+    }
+
+    /// The hand ids query is raw SQL run as a single read only statement,
+    /// it has to return exactly one column with the hand ids, e.g. [`Self::ALL_HAND_IDS_QUERY`].
+    pub fn query_hand_ids(&self, hand_ids_query: &str, params: impl Params) -> Result<Vec<u64>> {
+        let mut hand_ids = Vec::new();
+        self.query_for_each(hand_ids_query, params, |row| {
+            let [Value::Integer(hand_id)] = row else {
+                return Err("db: hand ids query has to return a single integer column".into());
+            };
+            hand_ids.push(u64::try_from(*hand_id)?);
+            Ok(true)
+        })?;
+        Ok(hand_ids)
+    }
+
+    /// See [`Self::query_hand_ids`] for the hand ids query.
+    pub fn export_game_data_json_lines(
+        &self,
+        hand_ids_query: &str,
+        params: impl Params,
+        mut out: impl io::Write,
+    ) -> Result<u64> {
+        let hand_ids = self.query_hand_ids(hand_ids_query, params)?;
+        for hand_id in &hand_ids {
+            let game_data = self.get_game_data(*hand_id)?;
+            serde_json::to_writer(&mut out, &game_data)?;
+            out.write_all(b"\n")?;
+        }
+
+        out.flush()?;
+        Ok(hand_ids.len() as u64)
+    }
+
+    /// See [`Self::query_hand_ids`] for the hand ids query.
+    pub fn export_hands_csv(
+        &self,
+        hand_ids_query: &str,
+        params: impl Params,
+        out: impl io::Write,
+    ) -> Result<u64> {
+        // The hand ids are bound as a JSON array instead of pasting the query.
+        let hand_ids = serde_json::to_string(&self.query_hand_ids(hand_ids_query, params)?)?;
+        self.export_csv(
+            "SELECT * FROM hands WHERE id IN (SELECT value FROM json_each(?)) ORDER BY id",
+            (hand_ids,),
+            out,
+        )
+    }
+
+    /// See [`Self::query_hand_ids`] for the hand ids query.
+    pub fn export_hands_players_csv(
+        &self,
+        hand_ids_query: &str,
+        params: impl Params,
+        out: impl io::Write,
+    ) -> Result<u64> {
+        let hand_ids = serde_json::to_string(&self.query_hand_ids(hand_ids_query, params)?)?;
+        self.export_csv(
+            "SELECT
+                hands.hand_name,
+                position(hands.player_count, hands.button_index, hands_players.player) AS position,
+                hands_players.*
+            FROM hands_players
+            JOIN hands ON hands.id = hands_players.hand_id
+            WHERE hands_players.hand_id IN (SELECT value FROM json_each(?))
+            ORDER BY hands_players.hand_id, hands_players.player",
+            (hand_ids,),
+            out,
+        )
+    }
+
+    fn export_csv(&self, query: &str, params: impl Params, mut out: impl io::Write) -> Result<u64> {
+        let mut stmt = self.conn.prepare(query)?;
+        // TODO: Can still potentially modify the database.
+        if !stmt.readonly() {
+            return Err("db: running non readonly query to export csv".into());
+        }
+
+        let column_names: Vec<_> = stmt
+            .column_names()
+            .into_iter()
+            .map(|name| name.to_owned())
+            .collect();
+        write_csv_record(&mut out, column_names.iter().map(|name| name.as_str()))?;
+
+        let mut count = 0u64;
+        let mut rows = stmt.query(params)?;
+        let mut fields = vec![String::new(); column_names.len()];
+        while let Some(row) = rows.next()? {
+            for (index, field) in fields.iter_mut().enumerate() {
+                field.truncate(0);
+                match row.get(index)? {
+                    Value::Null => (),
+                    Value::Integer(n) => write!(field, "{n}")?,
+                    Value::Real(n) => write!(field, "{n}")?,
+                    Value::Text(s) => field.push_str(&s),
+                    Value::Blob(_) => return Err("db: can't export blob as csv".into()),
+                }
+            }
+
+            write_csv_record(&mut out, fields.iter().map(|field| field.as_str()))?;
+            count += 1;
+        }
+
+        out.flush()?;
+        Ok(count)
+    }
+
+    /// An empty note without a color label removes the player entry.
+    pub fn set_player_note(
+        &self,
+        player_name: &str,
+        note: &str,
+        color_label: Option<ColorLabel>,
+    ) -> Result<()> {
+        if player_name.is_empty() {
+            return Err("db: empty player name".into());
+        }
+
+        if note.is_empty() && color_label.is_none() {
+            self.conn.execute(
+                "DELETE FROM players_notes WHERE player_name = ?",
+                (player_name,),
+            )?;
+        } else {
+            self.conn.execute(
+                "INSERT INTO players_notes (player_name, note, color_label) VALUES (?, ?, ?)
+                ON CONFLICT(player_name) DO UPDATE SET
+                    note = excluded.note,
+                    color_label = excluded.color_label",
+                (player_name, note, color_label.map(ColorLabel::to_str)),
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn get_player_note(&self, player_name: &str) -> Result<Option<PlayerNote>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT * FROM players_notes WHERE player_name = ?")?;
+        let mut rows = stmt.query_map((player_name,), PlayerNote::from_row)?;
+        Ok(rows.next().transpose()?)
+    }
+
+    pub fn get_player_notes(&self) -> Result<Vec<PlayerNote>> {
+        let notes = self
+            .conn
+            .prepare("SELECT * FROM players_notes ORDER BY player_name")?
+            .query_map((), PlayerNote::from_row)?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(notes)
+    }
+
+    /// Pre flop tendencies of every named player, walks are skipped.
+    pub fn get_players_tendencies(&self) -> Result<HashMap<String, PlayerTendencies>> {
+        let tendencies = self
+            .conn
+            .prepare(
+                "SELECT
+                    hands_players.player_name,
+                    COUNT(*),
+                    SUM(hands_players.pre_flop_action GLOB '*[cr]*'),
+                    SUM(hands_players.pre_flop_action GLOB '*r*')
+                FROM hands_players
+                JOIN hands ON hands.id = hands_players.hand_id
+                WHERE hands_players.player_name IS NOT NULL AND hands.pot_kind != 'walk'
+                GROUP BY hands_players.player_name",
+            )?
+            .query_map((), |row| {
+                let hands: u64 = row.get(1)?;
+                let vpip_hands: u64 = row.get(2)?;
+                let pfr_hands: u64 = row.get(3)?;
+                Ok((
+                    row.get(0)?,
+                    PlayerTendencies {
+                        hands,
+                        vpip: vpip_hands as f64 / hands as f64,
+                        pfr: pfr_hands as f64 / hands as f64,
+                    },
+                ))
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(tendencies)
+    }
+
+    /// Returns false if the hand already had the tag.
+    pub fn add_hand_tag(&self, hand_id: u64, tag: &str) -> Result<bool> {
+        let tag = Self::check_tag(tag)?;
+        let has_hand = self
+            .conn
+            .prepare("SELECT 1 FROM hands WHERE id = ?")?
+            .exists((hand_id,))?;
+        if !has_hand {
+            return Err(format!("db: unknown hand id {hand_id}").into());
+        }
+
+        let inserted = self.conn.execute(
+            "INSERT OR IGNORE INTO hands_tags (hand_id, tag) VALUES (?, ?)",
+            (hand_id, tag),
+        )?;
+        Ok(inserted != 0)
+    }
+
+    /// Returns false if the hand did not have the tag.
+    pub fn remove_hand_tag(&self, hand_id: u64, tag: &str) -> Result<bool> {
+        let deleted = self.conn.execute(
+            "DELETE FROM hands_tags WHERE hand_id = ? AND tag = ?",
+            (hand_id, tag.trim()),
+        )?;
+        Ok(deleted != 0)
+    }
+
+    pub fn get_hand_tags(&self, hand_id: u64) -> Result<Vec<String>> {
+        let tags = self
+            .conn
+            .prepare("SELECT tag FROM hands_tags WHERE hand_id = ? ORDER BY tag")?
+            .query_map((hand_id,), |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(tags)
+    }
+
+    /// All tags in use with their hand count.
+    pub fn get_tags(&self) -> Result<Vec<(String, u64)>> {
+        let tags = self
+            .conn
+            .prepare("SELECT tag, COUNT(*) FROM hands_tags GROUP BY tag ORDER BY tag")?
+            .query_map((), |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(tags)
+    }
+
+    pub fn get_hand_ids_with_tag(&self, tag: &str) -> Result<Vec<u64>> {
+        let hand_ids = self
+            .conn
+            .prepare("SELECT hand_id FROM hands_tags WHERE tag = ? ORDER BY hand_id")?
+            .query_map((tag.trim(),), |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(hand_ids)
+    }
+
+    fn check_tag(tag: &str) -> Result<&str> {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            return Err("db: empty tag".into());
+        }
+        if tag.chars().any(|c| c.is_control()) {
+            return Err(format!("db: invalid tag '{}'", tag.escape_debug()).into());
+        }
+        Ok(tag)
+    }
+}
+
+/// Accepts a JSON array of game data (e.g. the output of parse-gg)
+/// or one game data object per line.
+pub fn parse_game_data_json(content: &str) -> Result<Vec<GameData>> {
+    if content.trim_start().starts_with('[') {
+        return Ok(serde_json::from_str(content)?);
+    }
+
+    let data = serde_json::Deserializer::from_str(content)
+        .into_iter::<GameData>()
+        .collect::<serde_json::Result<Vec<_>>>()?;
+    Ok(data)
+}
+
+fn write_csv_record<'a>(
+    out: &mut impl io::Write,
+    fields: impl Iterator<Item = &'a str>,
+) -> io::Result<()> {
+    for (index, field) in fields.enumerate() {
+        if index != 0 {
+            out.write_all(b",")?;
+        }
+
+        if field.contains([',', '"', '\n', '\r']) {
+            write!(out, "\"{}\"", field.replace('"', "\"\""))?;
+        } else {
+            out.write_all(field.as_bytes())?;
+        }
+    }
+    out.write_all(b"\r\n")
 }
 
 fn get_string(row: &Row<'_>, idx: impl RowIndex) -> rusqlite::Result<Option<Arc<String>>> {
@@ -330,7 +863,7 @@ impl FromSql for Card {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Hand {
     pub id: Option<u64>,
     pub unit: Option<Arc<String>>,
@@ -513,7 +1046,7 @@ impl Hand {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Flop(pub [Card; 3]);
 
 impl FromSql for Flop {
@@ -602,7 +1135,78 @@ impl PotKind {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayerNote {
+    pub player_name: Arc<String>,
+    pub note: String,
+    pub color_label: Option<ColorLabel>,
+}
+
+impl PlayerNote {
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Self> {
+        Ok(Self {
+            player_name: Arc::new(row.get("player_name")?),
+            note: row.get("note")?,
+            color_label: row.get("color_label")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorLabel {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+}
+
+impl FromSql for ColorLabel {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value.as_str()?.parse().map_err(FromSqlError::Other)
+    }
+}
+
+impl FromStr for ColorLabel {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "red" => Ok(Self::Red),
+            "orange" => Ok(Self::Orange),
+            "yellow" => Ok(Self::Yellow),
+            "green" => Ok(Self::Green),
+            "blue" => Ok(Self::Blue),
+            "purple" => Ok(Self::Purple),
+            _ => Err(format!("db: unknown color label '{s}'").into()),
+        }
+    }
+}
+
+impl ColorLabel {
+    pub const ALL: [Self; 6] = [
+        Self::Red,
+        Self::Orange,
+        Self::Yellow,
+        Self::Green,
+        Self::Blue,
+        Self::Purple,
+    ];
+
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Self::Red => "red",
+            Self::Orange => "orange",
+            Self::Yellow => "yellow",
+            Self::Green => "green",
+            Self::Blue => "blue",
+            Self::Purple => "purple",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct HandPlayer {
     pub hand_id: Option<u64>,
     pub player: u8,
@@ -657,7 +1261,7 @@ impl HandPlayer {
 }
 
 #[repr(u8)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Action {
     Post = b'p',
     Straddle = b's',
@@ -702,7 +1306,7 @@ impl Action {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Actions(pub Vec<Action>);
 
 impl FromSql for Actions {
@@ -740,6 +1344,53 @@ impl Actions {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct VerifyReport {
+    pub hands_checked: u64,
+    pub issues: Vec<VerifyIssue>,
+}
+
+#[derive(Debug, Clone)]
+pub struct VerifyIssue {
+    pub hand_id: u64,
+    pub kind: VerifyIssueKind,
+    pub message: String,
+    pub repaired: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyIssueKind {
+    /// The hand data cannot be parsed or replayed, e.g. because of invalid cards.
+    InvalidHandData,
+    HandDataNotNormalized,
+    MissingHand,
+    HandMismatch,
+    PlayersMismatch,
+    OrphanHand,
+    OrphanPlayers,
+}
+
+impl VerifyIssueKind {
+    pub fn repairable(self) -> bool {
+        !matches!(
+            self,
+            VerifyIssueKind::InvalidHandData | VerifyIssueKind::HandDataNotNormalized
+        )
+    }
+
+    pub fn to_str(self) -> &'static str {
+        match self {
+            VerifyIssueKind::InvalidHandData => "invalid hand data",
+            VerifyIssueKind::HandDataNotNormalized => "hand data not normalized",
+            VerifyIssueKind::MissingHand => "missing hand",
+            VerifyIssueKind::HandMismatch => "hand mismatch",
+            VerifyIssueKind::PlayersMismatch => "players mismatch",
+            VerifyIssueKind::OrphanHand => "orphan hand",
+            VerifyIssueKind::OrphanPlayers => "orphan players",
+        }
+    }
+}
+
 pub struct HandData {
     pub id: Option<u64>,
     pub data: GameData,
@@ -811,3 +1462,170 @@ impl HandBundle {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use crate::parser::GGHandHistoryParser;
+
+    use super::*;
+
+    /// Removed again on drop, together with the WAL files.
+    struct TempDb(PathBuf);
+
+    impl TempDb {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("db-{name}-{}.db", std::process::id()));
+            let temp_db = Self(path);
+            temp_db.remove();
+            temp_db
+        }
+
+        fn remove(&self) {
+            for suffix in ["", "-wal", "-shm"] {
+                let mut path = self.0.clone().into_os_string();
+                path.push(suffix);
+                let _ = fs::remove_file(path);
+            }
+        }
+    }
+
+    impl Drop for TempDb {
+        fn drop(&mut self) {
+            self.remove();
+        }
+    }
+
+    fn example_games() -> Vec<Game> {
+        let path = Path::new("src")
+            .join("test_data")
+            .join("gg_hands_example.txt");
+        let history = fs::read_to_string(path).unwrap();
+        GGHandHistoryParser::new(false).parse_str(&history).unwrap()
+    }
+
+    #[test]
+    fn test_export_import_round_trip() {
+        unsafe {
+            crate::init::init();
+        }
+
+        let games = example_games();
+        let temp_db = TempDb::new("export");
+        let mut db = DB::create(&temp_db.0).unwrap();
+        let count = db.add_games(games.iter()).unwrap();
+        assert_eq!(count, games.len() as u64);
+
+        let mut json_lines = Vec::new();
+        let exported = db
+            .export_game_data_json_lines(DB::ALL_HAND_IDS_QUERY, (), &mut json_lines)
+            .unwrap();
+        assert_eq!(exported, count);
+        let data = parse_game_data_json(std::str::from_utf8(&json_lines).unwrap()).unwrap();
+        assert_eq!(data.len() as u64, count);
+
+        // Deduplicated by the hand name.
+        assert_eq!(db.add_game_data(&data).unwrap(), 0);
+        let other_temp_db = TempDb::new("import");
+        let mut other_db = DB::create(&other_temp_db.0).unwrap();
+        assert_eq!(other_db.add_game_data(&data).unwrap(), count);
+        assert_eq!(other_db.add_game_data(&data).unwrap(), 0);
+
+        let mut csv = Vec::new();
+        let query = "SELECT id FROM hands ORDER BY id LIMIT 1";
+        assert_eq!(db.export_hands_csv(query, (), &mut csv).unwrap(), 1);
+        assert!(db
+            .export_hands_csv("SELECT id FROM hands) OR (1", (), io::sink())
+            .is_err());
+        assert!(db
+            .export_hands_csv("SELECT id FROM hands; DELETE FROM hands", (), io::sink())
+            .is_err());
+    }
+
+    #[test]
+    fn test_verify_and_repair() {
+        unsafe {
+            crate::init::init();
+        }
+
+        let temp_db = TempDb::new("verify");
+        let mut db = DB::create(&temp_db.0).unwrap();
+        let count = db.add_games(example_games().iter()).unwrap();
+        let report = db.verify(false).unwrap();
+        assert_eq!(report.hands_checked, count);
+        assert_eq!(report.issues.len(), 0);
+
+        let hand_id: u64 = db
+            .conn
+            .query_row("SELECT MIN(id) FROM hands", (), |row| row.get(0))
+            .unwrap();
+        let stored_pot = |db: &DB| -> u64 {
+            db.conn
+                .query_row(
+                    "SELECT final_full_pot_size FROM hands WHERE id = ?",
+                    (hand_id,),
+                    |row| row.get(0),
+                )
+                .unwrap()
+        };
+        let pot = stored_pot(&db);
+        db.conn
+            .execute(
+                "UPDATE hands SET final_full_pot_size = final_full_pot_size + 1 WHERE id = ?",
+                (hand_id,),
+            )
+            .unwrap();
+        db.conn
+            .execute(
+                "DELETE FROM hands_players WHERE hand_id = ? AND player = 0",
+                (hand_id,),
+            )
+            .unwrap();
+
+        let report = db.verify(false).unwrap();
+        let kinds: Vec<_> = report.issues.iter().map(|issue| issue.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                VerifyIssueKind::HandMismatch,
+                VerifyIssueKind::PlayersMismatch
+            ]
+        );
+        assert!(report.issues.iter().all(|issue| issue.hand_id == hand_id));
+        assert!(report.issues.iter().all(|issue| !issue.repaired));
+        assert_eq!(stored_pot(&db), pot + 1);
+
+        let report = db.verify(true).unwrap();
+        assert!(report.issues.iter().all(|issue| issue.repaired));
+        assert_eq!(stored_pot(&db), pot);
+        assert_eq!(db.verify(false).unwrap().issues.len(), 0);
+    }
+
+    #[test]
+    fn test_open_older_schema() {
+        let temp_db = TempDb::new("older-schema");
+        drop(DB::create(&temp_db.0).unwrap());
+        let conn = Connection::open(&temp_db.0).unwrap();
+        conn.execute_batch("DROP TABLE hands_tags; DROP TABLE players_notes;")
+            .unwrap();
+        drop(conn);
+
+        // Read only databases are not upgraded, but everything else works.
+        let db = DB::open_read_only(&temp_db.0).unwrap();
+        assert_eq!(db.get_players_tendencies().unwrap().len(), 0);
+        assert!(db.get_tags().is_err());
+        drop(db);
+
+        let db = DB::open_existing(&temp_db.0).unwrap();
+        assert_eq!(db.get_tags().unwrap(), []);
+        drop(db);
+        let db = DB::open_read_only(&temp_db.0).unwrap();
+        assert_eq!(db.get_player_notes().unwrap().len(), 0);
+
+        let conn = Connection::open(&temp_db.0).unwrap();
+        conn.execute_batch("DROP TABLE hands_players").unwrap();
+        drop(conn);
+        assert!(DB::open_read_only(&temp_db.0).is_err());
+    }
+}
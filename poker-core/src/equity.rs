@@ -7,9 +7,12 @@ use crate::{
     cards::{Cards, Score},
     deck::Deck,
     hand::Hand,
-    range::{RangeTable, RangeTableWith},
+    range::{RangeEntry, RangeTable, RangeTableWith},
 };
 
+/// How many Monte Carlo rounds pass between progress reports.
+const PROGRESS_INTERVAL: u64 = 1 << 16;
+
 fn try_u64_to_f64(n: u64) -> Option<f64> {
     const F64_MAX_SAFE_INT: u64 = 2 << 53;
     if (F64_MAX_SAFE_INT - 1) & n != n {
@@ -106,7 +109,7 @@ impl Equity {
         let equity_calculator = EquityCalculator::new(community_cards, ranges, |_, scores| {
             showdown(scores, &mut wins, &mut ties)
         })?;
-        let total = equity_calculator.enumerate()?;
+        let total = equity_calculator.enumerate(&mut |_| true)?;
         Some(Self::from_total_wins_ties(total, &wins, &ties))
     }
 
@@ -117,9 +120,16 @@ impl Equity {
     ) -> Option<Vec<Equity>> {
         let mut wins = vec![0.0; ranges.len()];
         let mut ties = vec![0.0; ranges.len()];
-        let total = simulate(start_community_cards, ranges, rounds, |_, scores, diff| {
-            showdown_simulate(scores, &mut wins, &mut ties, diff);
-        })?;
+        let total = simulate(
+            start_community_cards,
+            ranges,
+            rounds,
+            SmallRng::from_entropy(),
+            |_, scores, diff| {
+                showdown_simulate(scores, &mut wins, &mut ties, diff);
+            },
+            |_| true,
+        )?;
         Some(Self::from_total_wins_ties_simulate(total, &wins, &ties))
     }
 
@@ -180,6 +190,16 @@ impl EquityTable {
     pub fn enumerate(
         community_cards: Cards,
         ranges: &[impl AsRef<RangeTable>],
+    ) -> Option<Vec<EquityTable>> {
+        Self::enumerate_with_progress(community_cards, ranges, |_| true)
+    }
+
+    /// Reports the done fraction from time to time,
+    /// returning false from `progress` cancels the calculation.
+    pub fn enumerate_with_progress(
+        community_cards: Cards,
+        ranges: &[impl AsRef<RangeTable>],
+        mut progress: impl FnMut(f64) -> bool,
     ) -> Option<Vec<EquityTable>> {
         let mut totals = vec![RangeTableWith::default(); ranges.len()];
         let mut wins = vec![RangeTableWith::default(); ranges.len()];
@@ -187,7 +207,7 @@ impl EquityTable {
         let equity_calculator = EquityCalculator::new(community_cards, ranges, |hands, scores| {
             showdown_table(hands, scores, &mut totals, &mut wins, &mut ties, 1.0);
         })?;
-        let total = equity_calculator.enumerate()?;
+        let total = equity_calculator.enumerate(&mut progress)?;
         let total = try_u64_to_f64(total).unwrap();
         Some(Self::from_totals_wins_ties(total, totals, wins, ties))
     }
@@ -196,6 +216,38 @@ impl EquityTable {
         start_community_cards: Cards,
         ranges: &[impl AsRef<RangeTable>],
         rounds: u64,
+    ) -> Option<Vec<Self>> {
+        Self::simulate_with_progress(start_community_cards, ranges, rounds, |_| true)
+    }
+
+    /// See [`Self::enumerate_with_progress`].
+    pub fn simulate_with_progress(
+        start_community_cards: Cards,
+        ranges: &[impl AsRef<RangeTable>],
+        rounds: u64,
+        progress: impl FnMut(f64) -> bool,
+    ) -> Option<Vec<Self>> {
+        let rng = SmallRng::from_entropy();
+        Self::simulate_with_rng(start_community_cards, ranges, rounds, rng, progress)
+    }
+
+    /// The same seed gives the same result.
+    pub fn simulate_seeded(
+        start_community_cards: Cards,
+        ranges: &[impl AsRef<RangeTable>],
+        rounds: u64,
+        seed: u64,
+    ) -> Option<Vec<Self>> {
+        let rng = SmallRng::seed_from_u64(seed);
+        Self::simulate_with_rng(start_community_cards, ranges, rounds, rng, |_| true)
+    }
+
+    fn simulate_with_rng(
+        start_community_cards: Cards,
+        ranges: &[impl AsRef<RangeTable>],
+        rounds: u64,
+        rng: SmallRng,
+        progress: impl FnMut(f64) -> bool,
     ) -> Option<Vec<Self>> {
         let mut totals = vec![RangeTableWith::default(); ranges.len()];
         let mut wins = vec![RangeTableWith::default(); ranges.len()];
@@ -204,9 +256,11 @@ impl EquityTable {
             start_community_cards,
             ranges,
             rounds,
+            rng,
             |hands, scores, diff| {
                 showdown_table(hands, scores, &mut totals, &mut wins, &mut ties, diff);
             },
+            progress,
         )?;
         Some(Self::from_totals_wins_ties(total, totals, wins, ties))
     }
@@ -260,13 +314,34 @@ impl EquityTable {
     pub fn has_data(&self, hand: Hand) -> bool {
         self.totals[hand] != 0.0
     }
+
+    /// Combines the combos of the entry, none if there is no data for any.
+    pub fn entry_equity(&self, entry: RangeEntry) -> Option<Equity> {
+        let (mut totals, mut wins, mut ties) = (0.0, 0.0, 0.0);
+        for hand in entry.iter_hands() {
+            totals += self.totals[hand];
+            wins += self.wins[hand];
+            ties += self.ties[hand];
+        }
+
+        if totals == 0.0 {
+            None
+        } else {
+            Some(Equity {
+                win_percent: wins / totals,
+                tie_percent: ties / totals,
+            })
+        }
+    }
 }
 
 fn simulate(
     start_community_cards: Cards,
     ranges: &[impl AsRef<RangeTable>],
     rounds: u64,
+    mut rng: SmallRng,
     mut f: impl FnMut(&[Hand], &[Score], f64),
+    mut progress: impl FnMut(f64) -> bool,
 ) -> Option<f64> {
     if !valid_input(start_community_cards, ranges) {
         return None;
@@ -275,7 +350,6 @@ fn simulate(
         return None;
     }
 
-    let mut rng = SmallRng::from_entropy();
     let remaining_community_cards = 5 - start_community_cards.count();
     let player_count = ranges.len();
     let full_ranges_original: Vec<_> = ranges
@@ -296,7 +370,11 @@ fn simulate(
     // We accept that this might loose precision here.
     let upper_bound = total_combos_upper_bound(start_community_cards, ranges) as f64;
 
-    'outer: for _ in 0..rounds {
+    'outer: for round in 0..rounds {
+        if round.is_multiple_of(PROGRESS_INTERVAL) && !progress(round as f64 / rounds as f64) {
+            return None;
+        }
+
         deck.reset();
 
         let community_cards = {
@@ -359,6 +437,11 @@ struct EquityCalculator<'a, RT: AsRef<RangeTable>, F: FnMut(&[Hand], &[Score])>
     hands: Vec<Hand>,
     hand_ranking_scores: Vec<Score>,
     total: u64,
+    boards_done: u64,
+    board_count: u64,
+    /// How many boards pass between progress reports, about a percent of all boards.
+    board_progress_interval: u64,
+    cancelled: bool,
     f: F,
 }
 
@@ -375,29 +458,51 @@ impl<'a, RT: AsRef<RangeTable>, F: FnMut(&[Hand], &[Score])> EquityCalculator<'a
                 hands: vec![Hand::UNDEFINED; ranges.len()],
                 hand_ranking_scores: vec![Score::ZERO; ranges.len()],
                 total: 0,
+                boards_done: 0,
+                board_count: 0,
+                board_progress_interval: 1,
+                cancelled: false,
                 f,
             })
         }
     }
 
-    fn enumerate(mut self) -> Option<u64> {
+    fn enumerate(mut self, progress: &mut dyn FnMut(f64) -> bool) -> Option<u64> {
         let upper_bound = total_combos_upper_bound(self.community_cards, self.ranges);
         let upper_bound = u64::try_from(upper_bound).ok()?;
         try_u64_to_f64(upper_bound)?;
         let remaining_community_cards = 5 - self.community_cards.count();
-        self.community_cards(remaining_community_cards.into());
+        let remaining_cards = Card::COUNT as u64 - u64::from(self.community_cards.count());
+        self.board_count = (remaining_cards - u64::from(remaining_community_cards) + 1
+            ..=remaining_cards)
+            .product::<u64>()
+            / (1..=u64::from(remaining_community_cards)).product::<u64>();
+        self.board_progress_interval = (self.board_count / 100).max(1);
+        self.community_cards(remaining_community_cards.into(), progress);
         assert!(self.total <= upper_bound);
-        if self.total != 0 {
+        if self.cancelled {
+            None
+        } else if self.total != 0 {
             Some(self.total)
         } else {
             None
         }
     }
 
-    fn community_cards(&mut self, remainder: usize) {
+    fn community_cards(&mut self, remainder: usize, progress: &mut dyn FnMut(f64) -> bool) {
         if remainder == 0 {
             self.known_cards = self.community_cards;
             self.players(self.ranges.len() - 1);
+
+            // Reporting per board keeps the callback out of the player loops.
+            self.boards_done += 1;
+            if self
+                .boards_done
+                .is_multiple_of(self.board_progress_interval)
+                && !progress(self.boards_done as f64 / self.board_count as f64)
+            {
+                self.cancelled = true;
+            }
             return;
         }
 
@@ -407,7 +512,10 @@ impl<'a, RT: AsRef<RangeTable>, F: FnMut(&[Hand], &[Score])> EquityCalculator<'a
             self.community_cards = current_community_cards.with(card);
             current_visited_community_cards.add(card);
             self.visited_community_cards = current_visited_community_cards;
-            self.community_cards(remainder - 1);
+            self.community_cards(remainder - 1, progress);
+            if self.cancelled {
+                return;
+            }
         }
     }
 
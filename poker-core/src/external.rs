@@ -0,0 +1,461 @@
+//! Bots running in an external process, e.g. prototypes written in Python.
+//!
+//! The process gets one JSON request per line on stdin and answers hero
+//! requests with one JSON line on stdout. Stderr is passed through.
+//!
+//! Requests:
+//! - `{"type":"new_hand"}` before the first request of every hand.
+//! - `{"type":"villain","player":2,"game":{..}}` after an action of another player,
+//!   `player` is the player who acted and all hands are hidden.
+//! - `{"type":"hero","player":0,"game":{..}}` when the bot has to act,
+//!   `player` (and `hero_index` of the game) is the bot, only its hand is visible.
+//!
+//! The game is `GameData` with the actions up to the current decision.
+//! Only hero requests are answered, e.g.
+//! `{"action":"bet_raise","amount":300,"range":{"AhKh":10000},"log":"value"}`.
+//! The action is one of `fold`, `check_fold`, `check_call`, `bet_raise` and `all_in`,
+//! the bet or raise amount is the total in the street like `Game::raise`.
+//! The optional range maps hands to frequencies up to `MAX_FREQUENCY`,
+//! missing hands are not in the range.
+//!
+//! The process is reused for later hands. After an error it is killed
+//! and a new one is started on the next request. Every request has to be read
+//! and every answer written within the timeout.
+
+use std::{
+    fmt::Write,
+    io::{self, BufRead, BufReader, Write as _},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ai::{AiAction, PlayerActionGenerator},
+    game::{Game, GameData},
+    range::{RangeConfigEntry, RangeTable, RangeTableWith, MAX_FREQUENCY},
+    result::Result,
+};
+
+/// Starts the processes of an external bot and keeps idle ones for later hands.
+#[derive(Clone)]
+pub struct ExternalEngine {
+    inner: Arc<EngineInner>,
+}
+
+struct EngineInner {
+    program: String,
+    args: Vec<String>,
+    timeout: Duration,
+    idle: Mutex<Vec<ExternalProcess>>,
+}
+
+impl ExternalEngine {
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+    pub fn new(program: impl Into<String>, args: Vec<String>) -> Self {
+        Self::with_timeout(program, args, Self::DEFAULT_TIMEOUT)
+    }
+
+    /// The timeout applies to every answer of the process.
+    pub fn with_timeout(program: impl Into<String>, args: Vec<String>, timeout: Duration) -> Self {
+        Self {
+            inner: Arc::new(EngineInner {
+                program: program.into(),
+                args,
+                timeout,
+                idle: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    /// Splits the command line at whitespace like a shell, e.g. `python3 "my bot.py"`.
+    /// Single and double quotes group words and a backslash escapes the next character.
+    pub fn from_command_line(command_line: &str) -> Result<Self> {
+        let mut parts = split_command_line(command_line)?.into_iter();
+        let Some(program) = parts.next() else {
+            return Err("external bot: empty command".into());
+        };
+        Ok(Self::new(program, parts.collect()))
+    }
+
+    pub fn generator(&self) -> ExternalBot {
+        ExternalBot {
+            engine: self.clone(),
+            process: None,
+        }
+    }
+
+    fn acquire(&self) -> Result<ExternalProcess> {
+        let idle = self.inner.idle.lock().unwrap().pop();
+        let mut process = match idle {
+            Some(process) => process,
+            None => ExternalProcess::spawn(&self.inner.program, &self.inner.args)?,
+        };
+        process.send(&Request::NewHand, self.inner.timeout)?;
+        Ok(process)
+    }
+
+    fn release(&self, mut process: ExternalProcess) {
+        if let Ok(None) = process.child.try_wait() {
+            self.inner.idle.lock().unwrap().push(process);
+        }
+    }
+}
+
+/// One hand of an external bot.
+pub struct ExternalBot {
+    engine: ExternalEngine,
+    process: Option<ExternalProcess>,
+}
+
+impl Drop for ExternalBot {
+    fn drop(&mut self) {
+        if let Some(process) = self.process.take() {
+            self.engine.release(process);
+        }
+    }
+}
+
+impl PlayerActionGenerator for ExternalBot {
+    fn update_villain(&mut self, game: &Game, _log: &mut String) -> Result<()> {
+        let Some(player) = game.actions().last().and_then(|action| action.player()) else {
+            return Ok(());
+        };
+
+        let game = Self::game_data(game, None);
+        let timeout = self.engine.inner.timeout;
+        self.request(|process| {
+            process.send(
+                &Request::Villain {
+                    player,
+                    game: &game,
+                },
+                timeout,
+            )
+        })
+    }
+
+    fn update_hero(
+        &mut self,
+        game: &Game,
+        log: &mut String,
+    ) -> Result<(AiAction, RangeConfigEntry, Option<&[RangeTableWith<u16>]>)> {
+        let Some(player) = game.current_player() else {
+            return Err("external bot: no player to act".into());
+        };
+
+        let game_data = Self::game_data(game, Some(player));
+        let timeout = self.engine.inner.timeout;
+        let line = self.request(|process| {
+            process.send(
+                &Request::Hero {
+                    player,
+                    game: &game_data,
+                },
+                timeout,
+            )?;
+            process.receive(timeout)
+        })?;
+
+        let answer: HeroAnswer = serde_json::from_str(&line)
+            .map_err(|err| format!("external bot: invalid answer '{line}': {err}"))?;
+        if let Some(answer_log) = &answer.log {
+            writeln!(log, "{answer_log}")?;
+        }
+
+        let action = answer.action.to_ai_action();
+        let range_action = action
+            .to_range(game)
+            .map_err(|err| format!("external bot: invalid action {action:?}: {err}"))?;
+
+        let range = match answer.range {
+            Some(range) => {
+                if range
+                    .iter()
+                    .any(|(_, frequency)| *frequency > MAX_FREQUENCY)
+                {
+                    return Err("external bot: range frequency above maximum".into());
+                }
+                range
+            }
+            None => RangeTable::FULL.to_frequencies(MAX_FREQUENCY),
+        };
+        let config = RangeConfigEntry::distribute_action(range, range_action)?;
+
+        Ok((action, config, None))
+    }
+}
+
+impl ExternalBot {
+    /// Kills the process on errors, it might be out of sync.
+    fn request<T>(&mut self, f: impl FnOnce(&mut ExternalProcess) -> Result<T>) -> Result<T> {
+        let process = match &mut self.process {
+            Some(process) => process,
+            process @ None => process.insert(self.engine.acquire()?),
+        };
+
+        let result = f(process);
+        if result.is_err() {
+            self.process = None;
+        }
+        result
+    }
+
+    fn game_data(game: &Game, hero: Option<usize>) -> GameData {
+        let mut data = game.to_game_data();
+        data.actions.truncate(game.actions().len());
+        data.hero_index = hero.map(|hero| u8::try_from(hero).unwrap());
+        data.showdown_stacks = None;
+        for (player, player_data) in data.players.iter_mut().enumerate() {
+            if Some(player) != hero {
+                player_data.hand = None;
+            }
+        }
+        data
+    }
+}
+
+struct ExternalProcess {
+    child: Child,
+    requests: Sender<String>,
+    written: Receiver<io::Result<()>>,
+    lines: Receiver<io::Result<String>>,
+}
+
+impl Drop for ExternalProcess {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl ExternalProcess {
+    fn spawn(program: &str, args: &[String]) -> Result<Self> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|err| format!("external bot: can't start {program}: {err}"))?;
+
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+
+        // Writing and reading on threads allows timeouts, a process that doesn't read
+        // its requests blocks writes once the pipe is full.
+        let (requests, requests_receiver) = mpsc::channel();
+        let (written_sender, written) = mpsc::channel();
+        thread::spawn(move || write_requests(stdin, requests_receiver, written_sender));
+
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            child,
+            requests,
+            written,
+            lines,
+        })
+    }
+
+    fn send(&mut self, request: &Request<'_>, timeout: Duration) -> Result<()> {
+        let mut line = serde_json::to_string(request)?;
+        line.push('\n');
+        if self.requests.send(line).is_err() {
+            return Err("external bot: process exited".into());
+        }
+
+        match self.written.recv_timeout(timeout) {
+            Ok(result) => {
+                result.map_err(|err| format!("external bot: can't send request: {err}").into())
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                Err(format!("external bot: request not read within {timeout:?}").into())
+            }
+            Err(RecvTimeoutError::Disconnected) => Err("external bot: process exited".into()),
+        }
+    }
+
+    fn receive(&mut self, timeout: Duration) -> Result<String> {
+        match self.lines.recv_timeout(timeout) {
+            Ok(line) => Ok(line?),
+            Err(RecvTimeoutError::Timeout) => {
+                Err(format!("external bot: no answer within {timeout:?}").into())
+            }
+            Err(RecvTimeoutError::Disconnected) => Err("external bot: process exited".into()),
+        }
+    }
+}
+
+fn write_requests(
+    mut stdin: ChildStdin,
+    requests: Receiver<String>,
+    written: Sender<io::Result<()>>,
+) {
+    for line in requests {
+        let result = stdin.write_all(line.as_bytes()).and_then(|_| stdin.flush());
+        let failed = result.is_err();
+        if written.send(result).is_err() || failed {
+            break;
+        }
+    }
+}
+
+fn split_command_line(command_line: &str) -> Result<Vec<String>> {
+    let mut parts = Vec::new();
+    let mut part = None;
+    let mut quote = None;
+    let mut chars = command_line.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (None | Some('"'), '\\') => {
+                let Some(escaped) = chars.next() else {
+                    return Err("external bot: command ends with a backslash".into());
+                };
+                part.get_or_insert_with(String::new).push(escaped);
+            }
+            (Some(_), c) => part.get_or_insert_with(String::new).push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                part.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => parts.extend(part.take()),
+            (None, c) => part.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    if quote.is_some() {
+        return Err("external bot: unterminated quote in command".into());
+    }
+    parts.extend(part);
+    Ok(parts)
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Request<'a> {
+    NewHand,
+    Villain { player: usize, game: &'a GameData },
+    Hero { player: usize, game: &'a GameData },
+}
+
+#[derive(Deserialize)]
+struct HeroAnswer {
+    #[serde(flatten)]
+    action: ExternalAction,
+    #[serde(default)]
+    range: Option<RangeTableWith<u16>>,
+    #[serde(default)]
+    log: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum ExternalAction {
+    Fold,
+    CheckFold,
+    CheckCall,
+    BetRaise { amount: u32 },
+    AllIn,
+}
+
+impl ExternalAction {
+    fn to_ai_action(&self) -> AiAction {
+        match self {
+            ExternalAction::Fold => AiAction::Fold,
+            ExternalAction::CheckFold => AiAction::CheckFold,
+            ExternalAction::CheckCall => AiAction::CheckCall,
+            ExternalAction::BetRaise { amount } => AiAction::BetRaise(*amount),
+            ExternalAction::AllIn => AiAction::AllIn,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::range::PreFlopRangeConfigEntry;
+
+    use super::*;
+
+    /// An engine running the shell script, with a short timeout.
+    fn engine(script: &str) -> ExternalEngine {
+        ExternalEngine::with_timeout(
+            "sh",
+            vec!["-c".to_owned(), script.to_owned()],
+            Duration::from_millis(500),
+        )
+    }
+
+    fn error(result: Result<impl std::fmt::Debug>) -> String {
+        result.unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_external_bot() {
+        unsafe {
+            crate::init::init();
+        }
+
+        let game = PreFlopRangeConfigEntry::build_game(2, 100_000, 500, &[]).unwrap();
+        let mut log = String::new();
+
+        // Answers every hero request.
+        let mut bot = engine(
+            r#"while read line; do
+                case "$line" in
+                    *'"type":"hero"'*) echo '{"action":"check_call","log":"called"}' ;;
+                esac
+            done"#,
+        )
+        .generator();
+        bot.update_villain(&game, &mut log).unwrap();
+        let (action, _, _) = bot.update_hero(&game, &mut log).unwrap();
+        assert!(matches!(action, AiAction::CheckCall));
+        assert_eq!(log, "called\n");
+
+        let mut bot = engine("while read line; do echo 'call'; done").generator();
+        assert!(error(bot.update_hero(&game, &mut log)).contains("invalid answer 'call'"));
+
+        // Reads the new hand request and exits, a new process is started on the next request.
+        let mut bot = engine("read line").generator();
+        assert!(bot.update_hero(&game, &mut log).is_err());
+        assert!(bot.update_hero(&game, &mut log).is_err());
+
+        let mut bot = engine("while read line; do :; done").generator();
+        assert_eq!(
+            error(bot.update_hero(&game, &mut log)),
+            "external bot: no answer within 500ms"
+        );
+
+        // Never reads, the writes block once the pipe is full.
+        let mut bot = engine("exec sleep 10").generator();
+        let result = (0..10_000).try_for_each(|_| bot.update_villain(&game, &mut log));
+        assert_eq!(error(result), "external bot: request not read within 500ms");
+    }
+
+    #[test]
+    fn test_from_command_line() {
+        let engine =
+            ExternalEngine::from_command_line(r#"python3  "my bot.py" 'a b' c\ d """#).unwrap();
+        assert_eq!(engine.inner.program, "python3");
+        assert_eq!(engine.inner.args, ["my bot.py", "a b", "c d", ""]);
+
+        assert!(ExternalEngine::from_command_line("  ").is_err());
+        assert!(ExternalEngine::from_command_line("python3 'bot.py").is_err());
+    }
+}
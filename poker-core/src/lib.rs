@@ -5,6 +5,7 @@
 //   - IntoIterator
 //   - ...
 
+pub mod abstraction;
 pub mod ai;
 mod bitset;
 pub mod card;
@@ -12,11 +13,22 @@ pub mod cards;
 pub mod db;
 pub mod deck;
 pub mod equity;
+pub mod external;
 pub mod game;
 pub mod hand;
 pub mod init;
+pub mod opponent;
 pub mod parser;
 pub mod range;
+pub mod range_binary;
+pub mod range_blockers;
+pub mod range_diff;
+pub mod range_filter;
+pub mod range_ordering;
+pub mod range_parser;
 pub mod rank;
 pub mod result;
+pub mod simulation;
 pub mod suite;
+pub mod trainer;
+pub mod writer;
@@ -0,0 +1,380 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cards::{Cards, Score},
+    game::{Action, Game, Street},
+    hand::Hand,
+    range::{RangeTableWith, MAX_FREQUENCY},
+    rank::Rank,
+};
+
+/// A part of a range by relative hand strength, from 0 (weakest) to 1 (strongest).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StrengthBand {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl StrengthBand {
+    pub fn new(min: f64, max: f64) -> Self {
+        Self { min, max }
+    }
+
+    pub fn contains(self, strength: f64) -> bool {
+        strength >= self.min && strength <= self.max
+    }
+}
+
+/// The hands of the range an action is expected from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActionMapping {
+    pub bands: Vec<StrengthBand>,
+    /// Hands outside of all bands keep this part of their frequency,
+    /// so unexpected holdings are never removed completely.
+    pub outside_frequency: f64,
+}
+
+impl ActionMapping {
+    pub fn new(bands: Vec<StrengthBand>, outside_frequency: f64) -> Self {
+        Self {
+            bands,
+            outside_frequency,
+        }
+    }
+
+    fn frequency_factor(&self, strength: f64) -> f64 {
+        if self.bands.iter().any(|band| band.contains(strength)) {
+            1.0
+        } else {
+            self.outside_frequency
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpponentModelConfig {
+    pub check: ActionMapping,
+    pub call: ActionMapping,
+    /// Value bets at the top and bluffs at the bottom.
+    pub bet: ActionMapping,
+    pub raise: ActionMapping,
+    /// Tendencies from fewer hands are ignored.
+    pub min_tendency_hands: u64,
+}
+
+impl Default for OpponentModelConfig {
+    fn default() -> Self {
+        Self {
+            check: ActionMapping::new(vec![StrengthBand::new(0.0, 0.85)], 0.3),
+            call: ActionMapping::new(vec![StrengthBand::new(0.3, 0.9)], 0.3),
+            bet: ActionMapping::new(
+                vec![StrengthBand::new(0.6, 1.0), StrengthBand::new(0.0, 0.15)],
+                0.2,
+            ),
+            raise: ActionMapping::new(
+                vec![StrengthBand::new(0.8, 1.0), StrengthBand::new(0.0, 0.08)],
+                0.1,
+            ),
+            min_tendency_hands: 50,
+        }
+    }
+}
+
+/// Observed pre flop frequencies of a player, from 0 to 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlayerTendencies {
+    pub hands: u64,
+    /// Voluntarily put money in the pot.
+    pub vpip: f64,
+    /// Pre flop raise.
+    pub pfr: f64,
+}
+
+/// Narrows villain ranges by the hand strength their actions represent.
+#[derive(Debug, Clone)]
+pub struct OpponentModel {
+    config: OpponentModelConfig,
+    /// By player name.
+    tendencies: HashMap<String, PlayerTendencies>,
+}
+
+impl Default for OpponentModel {
+    fn default() -> Self {
+        Self::new(OpponentModelConfig::default())
+    }
+}
+
+impl OpponentModel {
+    pub fn new(config: OpponentModelConfig) -> Self {
+        Self {
+            config,
+            tendencies: HashMap::new(),
+        }
+    }
+
+    pub fn config(&self) -> &OpponentModelConfig {
+        &self.config
+    }
+
+    pub fn set_tendencies(&mut self, tendencies: HashMap<String, PlayerTendencies>) {
+        self.tendencies = tendencies;
+    }
+
+    /// Only tendencies from enough hands are returned.
+    pub fn tendencies(&self, player_name: &str) -> Option<PlayerTendencies> {
+        self.tendencies
+            .get(player_name)
+            .copied()
+            .filter(|tendencies| tendencies.hands >= self.config.min_tendency_hands)
+    }
+
+    /// Narrows the range of the player who made the last action of the game.
+    /// Returns false if the action does not tell anything about the hand strength.
+    pub fn narrow(&self, game: &Game, range: &mut RangeTableWith<u16>) -> bool {
+        let Some(action) = game.actions().last().copied() else {
+            return false;
+        };
+        let mapping = match action {
+            Action::Check(_) => &self.config.check,
+            Action::Call { .. } => &self.config.call,
+            Action::Bet { .. } => &self.config.bet,
+            Action::Raise { .. } => &self.config.raise,
+            _ => return false,
+        };
+
+        Self::remove_blocked(game, range);
+
+        let strengths = Self::strengths(game, range);
+        for (hand, frequency) in range.iter_mut() {
+            if *frequency == 0 {
+                continue;
+            }
+
+            let factor = mapping.frequency_factor(strengths[hand]);
+            *frequency = (f64::from(*frequency) * factor).round() as u16;
+        }
+
+        true
+    }
+
+    /// The range of a player acting pre flop, the top VPIP hands when calling and the
+    /// top PFR hands when raising. Hands the current range already narrowed stay narrowed,
+    /// e.g. a re-raise is a part of the first raise.
+    pub fn pre_flop_range(
+        &self,
+        game: &Game,
+        current_range: &RangeTableWith<u16>,
+    ) -> Option<RangeTableWith<u16>> {
+        let action = game.actions().last().copied()?;
+        let player = action.player()?;
+        if game.board().street() != Street::PreFlop {
+            return None;
+        }
+
+        let tendencies = self.tendencies(game.player_name(player))?;
+        let top = match action {
+            Action::Call { .. } => tendencies.vpip,
+            Action::Raise { .. } => tendencies.pfr,
+            _ => return None,
+        };
+
+        let mut range = RangeTableWith::default();
+        for hand in Hand::all() {
+            range[hand] = MAX_FREQUENCY;
+        }
+        Self::remove_blocked(game, &mut range);
+
+        let strengths = Self::strengths(game, &range);
+        for (hand, frequency) in range.iter_mut() {
+            if strengths[hand] < 1.0 - top {
+                *frequency = 0;
+            } else {
+                *frequency = current_range[hand];
+            }
+        }
+
+        Some(range)
+    }
+
+    fn remove_blocked(game: &Game, range: &mut RangeTableWith<u16>) {
+        for card in game.board().cards() {
+            for (hand, frequency) in range.iter_mut() {
+                if hand.high() == *card || hand.low() == *card {
+                    *frequency = 0;
+                }
+            }
+        }
+    }
+
+    /// The weighted percentile of every hand in the range,
+    /// hands with the same strength share their percentile.
+    fn strengths(game: &Game, range: &RangeTableWith<u16>) -> RangeTableWith<f64> {
+        let board = Cards::from_slice(game.board().cards()).unwrap_or(Cards::EMPTY);
+
+        let mut hands: Vec<_> = range
+            .iter()
+            .filter(|(_, frequency)| **frequency != 0)
+            .map(|(hand, frequency)| (hand, *frequency, Self::strength(board, hand)))
+            .collect();
+        hands.sort_by_key(|(_, _, strength)| *strength);
+
+        let total: f64 = hands
+            .iter()
+            .map(|(_, frequency, _)| f64::from(*frequency))
+            .sum();
+
+        let mut strengths = RangeTableWith::default();
+        let mut below = 0.0;
+        for chunk in hands.chunk_by(|a, b| a.2 == b.2) {
+            let weight: f64 = chunk
+                .iter()
+                .map(|(_, frequency, _)| f64::from(*frequency))
+                .sum();
+            let percentile = (below + weight / 2.0) / total;
+            for (hand, _, _) in chunk {
+                strengths[*hand] = percentile;
+            }
+            below += weight;
+        }
+
+        strengths
+    }
+
+    fn strength(board: Cards, hand: Hand) -> HandStrength {
+        if board.count() < 3 {
+            HandStrength::PreFlop(Self::chen_score(hand))
+        } else {
+            HandStrength::Made(board.with(hand.high()).with(hand.low()).score_fast())
+        }
+    }
+
+    /// The Chen formula, doubled to stay integral.
+    fn chen_score(hand: Hand) -> i32 {
+        let points = |rank: Rank| match rank {
+            Rank::Ace => 20,
+            Rank::King => 16,
+            Rank::Queen => 14,
+            Rank::Jack => 12,
+            rank => i32::from(rank.to_u16()) + 2,
+        };
+
+        let high = hand.high().rank();
+        let low = hand.low().rank();
+        if high == low {
+            return (points(high) * 2).max(10);
+        }
+
+        let mut score = points(high);
+        if hand.suited() {
+            score += 4;
+        }
+
+        let gap = high.to_i16() - low.to_i16() - 1;
+        score -= match gap {
+            0 => 0,
+            1 => 2,
+            2 => 4,
+            3 => 8,
+            _ => 10,
+        };
+        if gap <= 1 && high < Rank::Queen {
+            score += 2;
+        }
+
+        score
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum HandStrength {
+    PreFlop(i32),
+    Made(Score),
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{game::GameData, range::RangeTable};
+
+    use super::*;
+
+    #[test]
+    fn test_narrow() {
+        unsafe {
+            crate::init::init();
+        }
+
+        let mut game = Game::from_game_data(&GameData::default()).unwrap();
+        game.post_small_and_big_blind().unwrap();
+        // Folded to the small blind, who completes.
+        while game.current_player() != Some(1) {
+            game.fold().unwrap();
+        }
+        game.call().unwrap();
+        game.check().unwrap();
+        game.flop([
+            "Ah".parse().unwrap(),
+            "7c".parse().unwrap(),
+            "2d".parse().unwrap(),
+        ])
+        .unwrap();
+        game.bet(game.big_blind()).unwrap();
+
+        let model = OpponentModel::default();
+        let mut range = RangeTable::FULL.to_frequencies(MAX_FREQUENCY);
+        assert!(model.narrow(&game, &mut range));
+
+        let hand = |s: &str| s.parse::<Hand>().unwrap();
+        // Sets bet for value, weak draws are kept as bluffs.
+        assert_eq!(range[hand("7h7s")], MAX_FREQUENCY);
+        assert_eq!(range[hand("4s3s")], MAX_FREQUENCY);
+        assert!(range[hand("Th9h")] < MAX_FREQUENCY);
+        // Blocked by the board.
+        assert_eq!(range[hand("Kd2d")], 0);
+    }
+
+    #[test]
+    fn test_pre_flop_range() {
+        unsafe {
+            crate::init::init();
+        }
+
+        let mut game = Game::from_game_data(&GameData::default()).unwrap();
+        game.post_small_and_big_blind().unwrap();
+        let opener = game.current_player().unwrap();
+        game.raise(30).unwrap();
+
+        let mut model = OpponentModel::default();
+        model.set_tendencies(HashMap::from([(
+            game.player_name(opener).to_owned(),
+            PlayerTendencies {
+                hands: 100,
+                vpip: 0.3,
+                pfr: 0.2,
+            },
+        )]));
+
+        let hand = |s: &str| s.parse::<Hand>().unwrap();
+        let full = RangeTable::FULL.to_frequencies(MAX_FREQUENCY);
+        let mut open_range = model.pre_flop_range(&game, &full).unwrap();
+        assert_eq!(open_range[hand("AsKs")], MAX_FREQUENCY);
+        assert_eq!(open_range[hand("7c2d")], 0);
+        // Narrowed after the open.
+        open_range[hand("AsKs")] = 0;
+        open_range[hand("AhKh")] = MAX_FREQUENCY / 2;
+
+        // 3-bet and folded around to the opener, who 4-bets.
+        game.raise(90).unwrap();
+        while game.current_player() != Some(opener) {
+            game.fold().unwrap();
+        }
+        game.raise(250).unwrap();
+
+        let range = model.pre_flop_range(&game, &open_range).unwrap();
+        assert_eq!(range[hand("AsAh")], MAX_FREQUENCY);
+        assert_eq!(range[hand("AsKs")], 0);
+        assert_eq!(range[hand("AhKh")], MAX_FREQUENCY / 2);
+        assert_eq!(range[hand("7c2d")], 0);
+    }
+}
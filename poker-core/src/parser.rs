@@ -72,7 +72,8 @@ impl GGHandHistoryParser {
             r"^\*\*\* .*RIVER \*\*\* \[{REGEX_CARD} {REGEX_CARD} {REGEX_CARD} {REGEX_CARD}\] \[{REGEX_CARD}\]$"
         );
         let re_uncalled_bet = format!(r"^Uncalled bet \({REGEX_PRICE}\) returned to {REGEX_NAME}$");
-        let re_shows = format!(r"^{REGEX_NAME}: shows \[({REGEX_CARD} {REGEX_CARD})?\].*$");
+        let re_shows =
+            format!(r"^{REGEX_NAME}: (?:shows \[({REGEX_CARD} {REGEX_CARD})?\].*|mucks hand)$");
         let re_showdown_title = format!(r"^\*\*\* .*SHOWDOWN \*\*\*$");
         let re_showdown = format!(r"^{REGEX_NAME} collected {REGEX_PRICE} from pot$");
         let re_summary = format!(r"^Total pot {REGEX_PRICE} \| Rake {REGEX_PRICE}")
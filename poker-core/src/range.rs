@@ -1,21 +1,25 @@
 use std::cmp::{self, max, min};
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::num::NonZeroU32;
 use std::ops::{BitAndAssign, Index, IndexMut};
 use std::str::FromStr;
 use std::sync::Arc;
-use std::{array, fmt, iter};
+use std::{array, fmt, iter, ptr};
 
 use rand::distributions::WeightedIndex;
 use rand::prelude::Distribution;
 use rand::Rng;
 use serde::{de, Deserialize, Serialize, Serializer};
 
+use crate::abstraction;
 use crate::card::Card;
 use crate::cards::{Cards, CardsByRank};
 use crate::game::{
-    milli_big_blind_to_f64_approximate, Action, Game, MilliBigBlind, Player, State, Street,
+    milli_big_blind_to_amount_rounded, milli_big_blind_to_f64_approximate, Action, Game,
+    MilliBigBlind, Player, State, Street,
 };
 use crate::hand::Hand;
+use crate::range_parser::RangeParser;
 use crate::rank::Rank;
 use crate::result::{Error, Result};
 use crate::suite::Suite;
@@ -149,6 +153,15 @@ impl RangeEntry {
         }
     }
 
+    /// The combos without a dead card, e.g. from the board or the hero hand.
+    pub fn live_combo_count(self, dead_cards: Cards) -> u8 {
+        let live = self
+            .iter_hands()
+            .filter(|hand| !hand_has_dead_card(*hand, dead_cards))
+            .count();
+        u8::try_from(live).unwrap()
+    }
+
     pub fn iter_hands(self) -> impl Iterator<Item = Hand> {
         Suite::SUITES
             .into_iter()
@@ -334,6 +347,11 @@ impl PreFlopRangeTable {
         count
     }
 
+    /// The share of all hands, from 0 to 100.
+    pub fn percent(&self) -> f64 {
+        f64::from(self.count_hands()) / Hand::COUNT as f64 * 100.0
+    }
+
     pub fn card_set(&self) -> Cards {
         let mut cards = Cards::EMPTY;
         self.for_each_hand(|hand| {
@@ -565,98 +583,15 @@ impl RangeTable {
         out
     }
 
+    /// See [`RangeParser`] for the syntax, hands with any weight are included.
     pub fn parse(range_str: &str) -> Result<Self> {
-        let range_str = range_str.trim();
-        if range_str == "full" {
-            return Ok(Self::FULL);
-        }
-
-        let mut range = Self::EMPTY;
-        for def in range_str.split(',') {
-            let result = match def.as_bytes() {
-                [pair_a, pair_b] if pair_a == pair_b => range.parse_pair(*pair_a),
-                [pair_a, pair_b, b'+'] if pair_a == pair_b => range.parse_pairs_asc(*pair_a),
-                [high, low, b'o'] => range.parse_one(*high, *low, false),
-                [high, low, b'o', b'+'] => range.parse_asc(*high, *low, false),
-                [high, low, b's'] => range.parse_one(*high, *low, true),
-                [high, low, b's', b'+'] => range.parse_asc(*high, *low, true),
-                hand if def.len() == 4 => range.parse_hand(hand),
-                _ => Err("parsing failed".into()),
-            };
-
-            if let Err(err) = result {
-                return Err(format!(
-                    "invalid range '{}': invalid entry '{}': {}",
-                    range_str, def, err,
-                )
-                .into());
-            }
-        }
-
-        Ok(range)
-    }
-
-    fn parse_pair(&mut self, raw_rank: u8) -> Result<()> {
-        let rank = Rank::from_ascii(raw_rank)?;
-        self.add_pairs(rank)
-    }
-
-    fn parse_pairs_asc(&mut self, raw_rank: u8) -> Result<()> {
-        let from = Rank::from_ascii(raw_rank)?;
-        for rank in Rank::range(from, Rank::Ace) {
-            self.add_pairs(rank)?;
-        }
-        Ok(())
-    }
-
-    fn parse_one(&mut self, raw_high: u8, raw_low: u8, suited: bool) -> Result<()> {
-        let high = Rank::from_ascii(raw_high)?;
-        let low = Rank::from_ascii(raw_low)?;
-        self.add_unpaired(high, low, suited)
-    }
-
-    fn parse_asc(&mut self, raw_high: u8, raw_low: u8, suited: bool) -> Result<()> {
-        let high = Rank::from_ascii(raw_high)?;
-        let low = Rank::from_ascii(raw_low)?;
-        for rank in Rank::range(low, high.predecessor().unwrap()) {
-            self.add_unpaired(high, rank, suited)?;
-        }
-        Ok(())
-    }
-
-    fn parse_hand(&mut self, hand: &[u8]) -> Result<()> {
-        let hand = Hand::from_bytes(hand)?;
-        self.try_add_hand(hand)
+        let range = Self::parse_weighted(range_str)?;
+        Ok(Self::from_frequencies_not_zero(&range))
     }
 
-    fn add_pairs(&mut self, rank: Rank) -> Result<()> {
-        for suite_a in Suite::SUITES {
-            for suite_b in Suite::SUITES {
-                if suite_b.to_usize() > suite_a.to_usize() {
-                    let hand = Hand::of_two_cards(Card::of(rank, suite_a), Card::of(rank, suite_b))
-                        .unwrap();
-                    self.try_add_hand(hand)?;
-                }
-            }
-        }
-        Ok(())
-    }
-
-    fn add_unpaired(&mut self, high: Rank, low: Rank, suited: bool) -> Result<()> {
-        if low >= high {
-            return Err(format!("{low} >= {high}").into());
-        }
-        for suite_low in Suite::SUITES {
-            for suite_high in Suite::SUITES {
-                if (suite_low == suite_high) != suited {
-                    continue;
-                }
-                let hand = Hand::of_two_cards(Card::of(high, suite_high), Card::of(low, suite_low))
-                    .unwrap();
-                self.try_add_hand(hand)?;
-            }
-        }
-        Ok(())
+    /// Parses `top N%` with the default ordering.
+    pub fn parse_weighted(range_str: &str) -> Result<RangeTableWith<u16>> {
+        RangeParser::default().parse(range_str)
     }
 
     pub fn has_hand(&self, hand: Hand) -> bool {
@@ -702,6 +637,13 @@ impl RangeTable {
         self == &Self::EMPTY
     }
 
+    /// Without the hands holding a dead card, e.g. from the board or the hero hand.
+    pub fn without_dead_cards(&self, dead_cards: Cards) -> Self {
+        self.into_iter()
+            .filter(|hand| !hand_has_dead_card(*hand, dead_cards))
+            .collect()
+    }
+
     pub fn to_frequencies(&self, frequency: u16) -> RangeTableWith<u16> {
         let mut out = RangeTableWith::default();
 
@@ -747,6 +689,16 @@ impl<'a> Iterator for RangeTableIter<'a> {
     }
 }
 
+impl FromIterator<RangeEntry> for PreFlopRangeTable {
+    fn from_iter<T: IntoIterator<Item = RangeEntry>>(iter: T) -> Self {
+        let mut range = Self::empty();
+        for entry in iter {
+            range.add(entry);
+        }
+        range
+    }
+}
+
 impl FromIterator<Hand> for RangeTable {
     fn from_iter<T: IntoIterator<Item = Hand>>(iter: T) -> Self {
         let mut t = Self::EMPTY;
@@ -772,6 +724,70 @@ impl fmt::Debug for RangeTable {
     }
 }
 
+/// Formats the range in the syntax accepted by [`RangeTable::parse`].
+impl fmt::Display for RangeTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self == &Self::FULL {
+            return write!(f, "full");
+        }
+
+        // Entries are ordered from the highest to the lowest, so a complete
+        // run from the start can be written with the '+' shorthand.
+        let mut defs = Vec::new();
+        let mut push_entries = |entries: &[RangeEntry]| {
+            let is_complete =
+                |entry: &RangeEntry| entry.iter_hands().all(|hand| self.has_hand(hand));
+
+            let complete_run = entries
+                .iter()
+                .take_while(|entry| is_complete(entry))
+                .count();
+            let mut rest = entries;
+            if complete_run >= 2 {
+                defs.push(format!(
+                    "{}+",
+                    entries[complete_run - 1].to_regular_string()
+                ));
+                rest = &entries[complete_run..];
+            }
+
+            for entry in rest {
+                if is_complete(entry) {
+                    defs.push(entry.to_regular_string());
+                } else {
+                    defs.extend(
+                        entry
+                            .iter_hands()
+                            .filter(|hand| self.has_hand(*hand))
+                            .map(|hand| hand.to_string()),
+                    );
+                }
+            }
+        };
+
+        let pairs: Vec<_> = Rank::RANKS
+            .into_iter()
+            .rev()
+            .map(RangeEntry::paired)
+            .collect();
+        push_entries(&pairs);
+
+        for high in Rank::RANKS.into_iter().rev() {
+            for suited in [true, false] {
+                let entries: Vec<_> = Rank::RANKS
+                    .into_iter()
+                    .rev()
+                    .filter(|low| *low < high)
+                    .map(|low| RangeEntry::new(high, low, suited).unwrap())
+                    .collect();
+                push_entries(&entries);
+            }
+        }
+
+        write!(f, "{}", defs.join(","))
+    }
+}
+
 #[derive(Clone, PartialEq, Eq)]
 pub struct RangeTableWith<T> {
     table: [T; Hand::COUNT],
@@ -785,6 +801,38 @@ impl<T: Default> Default for RangeTableWith<T> {
     }
 }
 
+/// Serialized as a map from hand to value, e.g. `{"AhKh": 10000}`.
+impl<T: Serialize> Serialize for RangeTableWith<T> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        use serde::ser::SerializeMap;
+
+        let mut state = serializer.serialize_map(Some(Hand::COUNT))?;
+        for (hand, value) in self.iter() {
+            state.serialize_entry(&hand, value)?;
+        }
+        state.end()
+    }
+}
+
+/// Missing hands get the default value.
+impl<'de, T: Deserialize<'de> + Default> Deserialize<'de> for RangeTableWith<T> {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let range: HashMap<Hand, T> = HashMap::deserialize(deserializer)?;
+
+        let mut out = Self::default();
+        for (hand, value) in range {
+            out[hand] = value;
+        }
+        Ok(out)
+    }
+}
+
 impl<T: fmt::Debug> fmt::Debug for RangeTableWith<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut state = f.debug_map();
@@ -813,6 +861,31 @@ impl<T> RangeTableWith<T> {
     }
 }
 
+impl RangeTableWith<u16> {
+    /// Sets the frequency of the hands holding a dead card to zero.
+    pub fn without_dead_cards(&self, dead_cards: Cards) -> Self {
+        let mut range = self.clone();
+        for (hand, frequency) in range.iter_mut() {
+            if hand_has_dead_card(hand, dead_cards) {
+                *frequency = 0;
+            }
+        }
+        range
+    }
+
+    /// The combos weighted by their frequency, without the hands holding a dead card.
+    pub fn effective_combos(&self, dead_cards: Cards) -> f64 {
+        self.iter()
+            .filter(|(hand, _)| !hand_has_dead_card(*hand, dead_cards))
+            .map(|(_, frequency)| frequency_to_f64(*frequency))
+            .sum()
+    }
+}
+
+fn hand_has_dead_card(hand: Hand, dead_cards: Cards) -> bool {
+    dead_cards.has(hand.high()) || dead_cards.has(hand.low())
+}
+
 impl<T> Index<Hand> for RangeTableWith<T> {
     type Output = T;
 
@@ -949,6 +1022,19 @@ impl PreFlopRangeAction {
 
         full_range
     }
+
+    pub fn full_ev(&self) -> Option<RangeTableWith<MilliBigBlind>> {
+        let ev = self.ev.as_ref()?;
+        let mut full_ev: RangeTableWith<MilliBigBlind> = RangeTableWith::default();
+
+        for (entry, ev) in ev.iter() {
+            for hand in entry.iter_hands() {
+                full_ev[hand] = *ev;
+            }
+        }
+
+        Some(full_ev)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -1192,6 +1278,26 @@ impl PreFlopRangeConfigEntry {
         }
     }
 
+    /// The frequency of the action for the entry, folds include the hands no action covers.
+    pub fn entry_frequency(&self, action: PreFlopAction, entry: RangeEntry) -> u16 {
+        if action == PreFlopAction::Fold {
+            return self.fold_frequency(entry);
+        }
+
+        self.actions
+            .iter()
+            .find(|current_action| current_action.action == action)
+            .map_or(0, |action| action.range[entry])
+    }
+
+    pub fn entry_ev(&self, action: PreFlopAction, entry: RangeEntry) -> Option<MilliBigBlind> {
+        let action = self
+            .actions
+            .iter()
+            .find(|current_action| current_action.action == action)?;
+        action.ev.as_ref().map(|ev| ev[entry])
+    }
+
     fn raise_diff_unchecked(&self, skip_players: usize, game: &Game) -> u64 {
         let allowed_actions = &game.actions()[2..];
 
@@ -1305,10 +1411,11 @@ impl PreFlopRangeConfigEntry {
             .actions
             .iter()
             .map(|action| {
-                RangeAction::new(
+                RangeAction::with_ev(
                     action.action.to_full_range(),
                     &total_range,
                     action.full_range(),
+                    action.full_ev(),
                 )
             })
             .collect();
@@ -1407,6 +1514,22 @@ impl PreFlopRangeConfig {
         }
     }
 
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref().map(String::as_str)
+    }
+
+    pub fn max_players(&self) -> usize {
+        self.max_players
+    }
+
+    pub fn depth(&self) -> MilliBigBlind {
+        self.depth
+    }
+
+    pub fn small_blind(&self) -> MilliBigBlind {
+        self.small_blind
+    }
+
     pub fn ranges(&self) -> &[PreFlopRangeConfigEntry] {
         &self.ranges
     }
@@ -1414,6 +1537,23 @@ impl PreFlopRangeConfig {
     pub fn by_game_action_kinds<'a>(
         &'a self,
         game: &'a Game,
+    ) -> Result<impl Iterator<Item = &'a PreFlopRangeConfigEntry> + 'a> {
+        // Other depths need a `PreFlopRangeConfigSet`.
+        let stack_depth_matches = game
+            .starting_stacks()
+            .iter()
+            .copied()
+            .all(|stack| game.amount_to_milli_big_blinds_rounded(stack) == self.depth);
+        if !stack_depth_matches {
+            return Err("ranges by action kinds: at least on stack has an unexpected depth".into());
+        }
+
+        self.by_game_action_kinds_any_depth(game)
+    }
+
+    fn by_game_action_kinds_any_depth<'a>(
+        &'a self,
+        game: &'a Game,
     ) -> Result<impl Iterator<Item = &'a PreFlopRangeConfigEntry> + 'a> {
         if game.player_count() > self.max_players {
             return Err("ranges by action kinds: more players than maximally allowed".into());
@@ -1427,16 +1567,6 @@ impl PreFlopRangeConfig {
             return Err("ranges by action kinds: small blind does not match".into());
         }
 
-        // TODO: Pretty big limitation.
-        let stack_depth_matches = game
-            .starting_stacks()
-            .iter()
-            .copied()
-            .all(|stack| game.amount_to_milli_big_blinds_rounded(stack) == self.depth);
-        if !stack_depth_matches {
-            return Err("ranges by action kinds: at least on stack has an unexpected depth".into());
-        }
-
         let actions = game.actions();
 
         if game.state() == State::Post {
@@ -1472,12 +1602,91 @@ impl PreFlopRangeConfig {
         Ok(ranges)
     }
 
+    /// Picks the range for the game, off-tree raise sizes are translated
+    /// to the neighbouring sizes of the config with the pseudo-harmonic mapping.
+    pub fn by_game_translated<'a>(
+        &'a self,
+        game: &'a Game,
+        rng: &mut impl Rng,
+    ) -> Result<&'a PreFlopRangeConfigEntry> {
+        let candidates = self.by_game_action_kinds(game)?.collect();
+        self.translated(game, rng, candidates)
+    }
+
+    fn translated<'a>(
+        &'a self,
+        game: &Game,
+        rng: &mut impl Rng,
+        mut candidates: Vec<&'a PreFlopRangeConfigEntry>,
+    ) -> Result<&'a PreFlopRangeConfigEntry> {
+        if candidates.is_empty() {
+            return Err("range by actions: no range matches".into());
+        }
+
+        let skip_players = self.max_players.checked_sub(game.player_count()).unwrap();
+        let actions = game.actions();
+        for (offset, action) in actions.iter().enumerate().skip(2) {
+            let Action::Raise { to, .. } = *action else {
+                continue;
+            };
+
+            let mut before = game.clone();
+            while before.actions().len() > offset {
+                assert!(before.previous());
+            }
+
+            let index = skip_players + offset - 2;
+            let sizes: Vec<_> = candidates
+                .iter()
+                .map(|entry| match entry.previous_actions[index] {
+                    PreFlopAction::Raise(to) => to,
+                    _ => unreachable!(),
+                })
+                .collect();
+            let fractions = sizes
+                .iter()
+                .map(|size| {
+                    milli_big_blind_to_amount_rounded(*size, game.big_blind())
+                        .and_then(|amount| abstraction::pot_fraction(&before, amount))
+                })
+                .collect::<Option<Vec<_>>>()
+                .ok_or("range by actions: invalid raise size")?;
+            let Some(x) = abstraction::pot_fraction(&before, to) else {
+                return Err("range by actions: invalid raise".into());
+            };
+
+            let chosen = sizes[abstraction::translate(rng, &fractions, x).unwrap()];
+            candidates
+                .retain(|entry| entry.previous_actions[index] == PreFlopAction::Raise(chosen));
+        }
+
+        Ok(candidates[0])
+    }
+
+    /// The game of the config with the previous actions of the entry applied,
+    /// amounts are in milli big blinds.
+    pub fn entry_game(&self, entry: &PreFlopRangeConfigEntry) -> Result<Game> {
+        PreFlopRangeConfigEntry::build_game(
+            self.max_players,
+            self.depth,
+            self.small_blind,
+            &entry.previous_actions,
+        )
+    }
+
     pub fn by_game_best_fit_raise_simple<'a>(
         &'a self,
         game: &'a Game,
     ) -> Result<(&'a PreFlopRangeConfigEntry, u64)> {
-        let mut ranges = self.by_game_action_kinds(game)?;
+        let ranges = self.by_game_action_kinds(game)?;
+        self.best_fit_raise_simple(game, ranges)
+    }
 
+    fn best_fit_raise_simple<'a>(
+        &'a self,
+        game: &Game,
+        mut ranges: impl Iterator<Item = &'a PreFlopRangeConfigEntry>,
+    ) -> Result<(&'a PreFlopRangeConfigEntry, u64)> {
         let Some(mut best_range) = ranges.next() else {
             return Err("range by actions: no range matches".into());
         };
@@ -1497,48 +1706,222 @@ impl PreFlopRangeConfig {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
-pub enum RangeActionKind {
-    Post { player: u8, amount: MilliBigBlind },
-    Straddle { player: u8, amount: MilliBigBlind },
-    Fold,
-    Check,
-    Call,
-    Bet(MilliBigBlind),
-    Raise(MilliBigBlind),
+/// Pre flop configs of several depths for the same table,
+/// picked by the effective stack of the game.
+#[derive(Debug, Clone)]
+pub struct PreFlopRangeConfigSet {
+    /// Sorted by depth.
+    configs: Vec<PreFlopRangeConfig>,
 }
 
-impl fmt::Display for RangeActionKind {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match *self {
-            RangeActionKind::Post { player, amount } => write!(
-                f,
-                "Post from {player} to {:.2}",
-                milli_big_blind_to_f64_approximate(amount)
-            ),
-            RangeActionKind::Straddle { player, amount } => {
-                write!(
-                    f,
-                    "Straddle from {player} to {:.2}",
-                    milli_big_blind_to_f64_approximate(amount)
-                )
-            }
-            RangeActionKind::Fold => write!(f, "Fold"),
-            RangeActionKind::Check => write!(f, "Check"),
-            RangeActionKind::Call => write!(f, "Call"),
-            RangeActionKind::Bet(amount) => {
-                write!(f, "Bet {:.2}", milli_big_blind_to_f64_approximate(amount))
-            }
-            RangeActionKind::Raise(to) => {
-                write!(f, "Raise {:.2}", milli_big_blind_to_f64_approximate(to))
-            }
+#[derive(Debug, Clone, Copy)]
+pub struct WeightedRangeEntry<'a> {
+    pub config: &'a PreFlopRangeConfig,
+    pub entry: &'a PreFlopRangeConfigEntry,
+    /// The weights of all entries sum up to 1.0.
+    pub weight: f64,
+}
+
+impl From<PreFlopRangeConfig> for PreFlopRangeConfigSet {
+    fn from(config: PreFlopRangeConfig) -> Self {
+        Self {
+            configs: vec![config],
         }
     }
 }
 
-impl RangeActionKind {
-    pub fn from_game_action(game: &Game, action: Action) -> Result<Self> {
+impl PreFlopRangeConfigSet {
+    pub fn new(mut configs: Vec<PreFlopRangeConfig>) -> Result<Self> {
+        let Some(first) = configs.first() else {
+            return Err("range config set: no configs".into());
+        };
+        let same_table = configs.iter().all(|config| {
+            config.max_players == first.max_players && config.small_blind == first.small_blind
+        });
+        if !same_table {
+            return Err(
+                "range config set: configs must have the same max players and small blind".into(),
+            );
+        }
+
+        configs.sort_by_key(|config| config.depth);
+        if configs
+            .windows(2)
+            .any(|configs| configs[0].depth == configs[1].depth)
+        {
+            return Err("range config set: contains duplicated depth".into());
+        }
+
+        Ok(Self { configs })
+    }
+
+    pub fn configs(&self) -> &[PreFlopRangeConfig] {
+        &self.configs
+    }
+
+    /// The smaller of the starting stack of the current player and the biggest
+    /// starting stack of the other players.
+    pub fn effective_stack(game: &Game) -> Result<MilliBigBlind> {
+        let Some(player) = game.current_player() else {
+            return Err("range config set: no current player".into());
+        };
+
+        let stacks = game.starting_stacks();
+        let Some(villain_stack) = stacks
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| *index != player)
+            .map(|(_, stack)| *stack)
+            .max()
+        else {
+            return Err("range config set: no other players".into());
+        };
+
+        Ok(game.amount_to_milli_big_blinds_rounded(stacks[player].min(villain_stack)))
+    }
+
+    /// The configs with the closest depths below and above, the weight is the share of the
+    /// deeper one. Depths outside of the set use the closest config only.
+    fn bracket(&self, depth: MilliBigBlind) -> (&PreFlopRangeConfig, &PreFlopRangeConfig, f64) {
+        let upper_index = self.configs.iter().position(|config| config.depth >= depth);
+
+        match upper_index {
+            Some(0) => (&self.configs[0], &self.configs[0], 0.0),
+            Some(index) => {
+                let lower = &self.configs[index - 1];
+                let upper = &self.configs[index];
+                let weight = (depth - lower.depth) as f64 / (upper.depth - lower.depth) as f64;
+                (lower, upper, weight)
+            }
+            None => {
+                let last = self.configs.last().unwrap();
+                (last, last, 0.0)
+            }
+        }
+    }
+
+    /// The configs ordered by the distance of their depth to the effective stack.
+    fn by_distance(&self, game: &Game) -> Result<Vec<&PreFlopRangeConfig>> {
+        let depth = Self::effective_stack(game)?;
+        let mut configs: Vec<_> = self.configs.iter().collect();
+        configs.sort_by_key(|config| config.depth.abs_diff(depth));
+        Ok(configs)
+    }
+
+    /// Like `PreFlopRangeConfig::by_game_best_fit_raise_simple` with the config closest
+    /// to the effective stack, configs further away are used if no range matches.
+    pub fn by_game_best_fit_raise_simple<'a>(
+        &'a self,
+        game: &'a Game,
+    ) -> Result<(&'a PreFlopRangeConfig, &'a PreFlopRangeConfigEntry, u64)> {
+        let mut last_err = None;
+        for config in self.by_distance(game)? {
+            let ranges = config.by_game_action_kinds_any_depth(game)?;
+            match config.best_fit_raise_simple(game, ranges) {
+                Ok((entry, diff)) => return Ok((config, entry, diff)),
+                Err(err) => last_err = Some(err),
+            }
+        }
+        Err(last_err.unwrap())
+    }
+
+    /// The translated ranges of the configs with the closest depths below and above the
+    /// effective stack, weighted by the distance of their depth. Falls back to the closest
+    /// config with a matching range.
+    pub fn by_game_interpolated<'a>(
+        &'a self,
+        game: &'a Game,
+        rng: &mut impl Rng,
+    ) -> Result<Vec<WeightedRangeEntry<'a>>> {
+        let (lower, upper, weight) = self.bracket(Self::effective_stack(game)?);
+
+        let mut candidates = vec![(lower, 1.0 - weight)];
+        if !ptr::eq(lower, upper) {
+            candidates.push((upper, weight));
+        }
+
+        let mut entries = Vec::new();
+        for (config, weight) in candidates {
+            let ranges = config.by_game_action_kinds_any_depth(game)?.collect();
+            if let Ok(entry) = config.translated(game, rng, ranges) {
+                entries.push(WeightedRangeEntry {
+                    config,
+                    entry,
+                    weight,
+                });
+            }
+        }
+        // A config without weight doesn't count as a match.
+        entries.retain(|entry| entry.weight > 0.0);
+
+        if entries.is_empty() {
+            for config in self.by_distance(game)? {
+                let ranges = config.by_game_action_kinds_any_depth(game)?.collect();
+                if let Ok(entry) = config.translated(game, rng, ranges) {
+                    entries.push(WeightedRangeEntry {
+                        config,
+                        entry,
+                        weight: 1.0,
+                    });
+                    break;
+                }
+            }
+        }
+
+        let total_weight: f64 = entries.iter().map(|entry| entry.weight).sum();
+        if entries.is_empty() {
+            return Err("range config set: no range matches".into());
+        }
+        for entry in &mut entries {
+            entry.weight /= total_weight;
+        }
+
+        Ok(entries)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RangeActionKind {
+    Post { player: u8, amount: MilliBigBlind },
+    Straddle { player: u8, amount: MilliBigBlind },
+    Fold,
+    Check,
+    Call,
+    Bet(MilliBigBlind),
+    Raise(MilliBigBlind),
+}
+
+impl fmt::Display for RangeActionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            RangeActionKind::Post { player, amount } => write!(
+                f,
+                "Post from {player} to {:.2}",
+                milli_big_blind_to_f64_approximate(amount)
+            ),
+            RangeActionKind::Straddle { player, amount } => {
+                write!(
+                    f,
+                    "Straddle from {player} to {:.2}",
+                    milli_big_blind_to_f64_approximate(amount)
+                )
+            }
+            RangeActionKind::Fold => write!(f, "Fold"),
+            RangeActionKind::Check => write!(f, "Check"),
+            RangeActionKind::Call => write!(f, "Call"),
+            RangeActionKind::Bet(amount) => {
+                write!(f, "Bet {:.2}", milli_big_blind_to_f64_approximate(amount))
+            }
+            RangeActionKind::Raise(to) => {
+                write!(f, "Raise {:.2}", milli_big_blind_to_f64_approximate(to))
+            }
+        }
+    }
+}
+
+impl RangeActionKind {
+    pub fn from_game_action(game: &Game, action: Action) -> Result<Self> {
         match action {
             Action::Post { player, amount, .. } => {
                 let Some(player) = Game::player_to_button_offset(
@@ -1588,15 +1971,31 @@ impl RangeActionKind {
             _ => Err("range action from game action: action type not supported".into()),
         }
     }
+
+    /// Amounts are chips in games with a big blind of 1000.
+    fn apply_to_game(self, game: &mut Game) -> Result<()> {
+        match self {
+            RangeActionKind::Post { .. } | RangeActionKind::Straddle { .. } => {
+                Err("range action: posts and straddles can't be applied".into())
+            }
+            RangeActionKind::Fold => game.fold(),
+            RangeActionKind::Check => game.check(),
+            RangeActionKind::Call => game.call(),
+            RangeActionKind::Bet(amount) => game.bet(u32::try_from(amount)?),
+            RangeActionKind::Raise(to) => game.raise(u32::try_from(to)?),
+        }
+    }
 }
 
-/// Might not be valid after initialization.
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// Might not be valid after initialization or deserialization.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RangeAction {
     action: RangeActionKind,
     frequency: u64,
     /// Frequencies valid from 0 to 10_000, divide by 100 to get the percentage.
     range: RangeTableWith<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ev: Option<RangeTableWith<MilliBigBlind>>,
 }
 
 impl RangeAction {
@@ -1604,11 +2003,21 @@ impl RangeAction {
         action: RangeActionKind,
         total_range: &RangeTableWith<u16>,
         range: RangeTableWith<u16>,
+    ) -> Self {
+        Self::with_ev(action, total_range, range, None)
+    }
+
+    pub fn with_ev(
+        action: RangeActionKind,
+        total_range: &RangeTableWith<u16>,
+        range: RangeTableWith<u16>,
+        ev: Option<RangeTableWith<MilliBigBlind>>,
     ) -> Self {
         let mut action = Self {
             action,
             frequency: 0,
             range,
+            ev,
         };
         action.frequency = action.init_frequency(total_range);
         action
@@ -1654,6 +2063,10 @@ impl RangeAction {
     pub fn range(&self) -> &RangeTableWith<u16> {
         &self.range
     }
+
+    pub fn ev(&self) -> Option<&RangeTableWith<MilliBigBlind>> {
+        self.ev.as_ref()
+    }
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
@@ -1784,6 +2197,26 @@ impl RangeConfigEntry {
         self.actions.iter().map(|action| action.action).chain(fold)
     }
 
+    /// The action itself if known, otherwise the bet or raise with the closest size.
+    pub fn closest_action(&self, action: RangeActionKind) -> Option<RangeActionKind> {
+        if self.action_kinds().any(|kind| kind == action) {
+            return Some(action);
+        }
+
+        let (RangeActionKind::Bet(amount) | RangeActionKind::Raise(amount)) = action else {
+            return None;
+        };
+        self.action_kinds()
+            .filter_map(|kind| match kind {
+                RangeActionKind::Bet(to) | RangeActionKind::Raise(to) => {
+                    Some((kind, to.abs_diff(amount)))
+                }
+                _ => None,
+            })
+            .min_by_key(|(_, distance)| *distance)
+            .map(|(kind, _)| kind)
+    }
+
     pub fn action_range(&self, action: RangeActionKind) -> Option<RangeTableWith<u16>> {
         let mut fold_range = RangeTable::FULL.to_frequencies(MAX_FREQUENCY);
 
@@ -1887,6 +2320,79 @@ impl RangeConfigEntry {
         }
     }
 
+    /// The average EV of the hands of the entry taking the action, if known.
+    pub fn entry_ev(&self, action: RangeActionKind, entry: RangeEntry) -> Option<MilliBigBlind> {
+        let range_action = self.actions.iter().find(|a| a.action == action)?;
+        let ev = range_action.ev.as_ref()?;
+
+        let (weighted_ev, weight) = entry
+            .iter_hands()
+            .map(|hand| {
+                let weight =
+                    i64::from(self.total_range[hand]) * i64::from(range_action.range[hand]);
+                (ev[hand] * weight, weight)
+            })
+            .fold((0, 0), |(a, b), (c, d)| (a + c, b + d));
+
+        if weight == 0 {
+            None
+        } else {
+            Some(weighted_ev / weight)
+        }
+    }
+
+    /// Blends two entries, `weight` is the share of `other` from 0.0 to 1.0.
+    /// Actions missing in one entry have a frequency of 0 there.
+    pub fn interpolate(&self, other: &Self, weight: f64) -> Result<Self> {
+        if !(0.0..=1.0).contains(&weight) {
+            return Err("range config entry: invalid interpolation weight".into());
+        }
+
+        let mut kinds: Vec<_> = self.action_kinds().chain(other.action_kinds()).collect();
+        kinds.sort();
+        kinds.dedup();
+        let has_fold = kinds.contains(&RangeActionKind::Fold);
+
+        let mut total_range = RangeTableWith::default();
+        let mut ranges = vec![RangeTableWith::<u16>::default(); kinds.len()];
+
+        for hand in Hand::all() {
+            let self_weight = (1.0 - weight) * f64::from(self.total_range[hand]);
+            let other_weight = weight * f64::from(other.total_range[hand]);
+            let hand_weight = self_weight + other_weight;
+            total_range[hand] = hand_weight.round() as u16;
+
+            // Hands in neither range keep a valid strategy.
+            let (self_weight, other_weight) = if hand_weight == 0.0 {
+                (1.0 - weight, weight)
+            } else {
+                (self_weight / hand_weight, other_weight / hand_weight)
+            };
+
+            for (kind, range) in kinds.iter().copied().zip(&mut ranges) {
+                let frequency = self_weight * f64::from(self.hand_frequency(kind, hand))
+                    + other_weight * f64::from(other.hand_frequency(kind, hand));
+                range[hand] = frequency as u16;
+            }
+
+            // Adds the frequency lost by truncation to the most frequent action.
+            if has_fold {
+                let total: u16 = ranges.iter().map(|range| range[hand]).sum();
+                let index = (0..ranges.len())
+                    .max_by_key(|index| ranges[*index][hand])
+                    .unwrap();
+                ranges[index][hand] += MAX_FREQUENCY.saturating_sub(total);
+            }
+        }
+
+        let actions = kinds
+            .into_iter()
+            .zip(ranges)
+            .map(|(kind, range)| RangeAction::new(kind, &total_range, range))
+            .collect();
+        Self::new(total_range, actions)
+    }
+
     pub fn hand_frequency(&self, action: RangeActionKind, hand: Hand) -> u16 {
         let range_action = self
             .actions()
@@ -1947,6 +2453,7 @@ impl RangeConfigEntry {
     }
 
     fn finalize_update(&mut self) -> Result<()> {
+        self.total_frequency = self.init_total_frequency();
         for action in &mut self.actions {
             action.frequency = action.init_frequency(&self.total_range);
         }
@@ -2011,6 +2518,457 @@ impl RangeConfigEntry {
         }
         self.finalize_update()
     }
+
+    /// Adds an action that is not taken with any hand yet.
+    pub fn add_action(&mut self, action: RangeActionKind) -> Result<()> {
+        if self
+            .action_kinds()
+            .any(|current_action| current_action == action)
+        {
+            return Err(format!("update range: duplicate action {action}").into());
+        }
+
+        let range = if action == RangeActionKind::Fold {
+            // An explicit fold takes everything not taken by other actions.
+            let mut range = RangeTable::FULL.to_frequencies(MAX_FREQUENCY);
+            for range_action in &self.actions {
+                for (hand, frequency) in range_action.range.iter() {
+                    range[hand] -= frequency;
+                }
+            }
+            range
+        } else {
+            RangeTable::EMPTY.to_frequencies(0)
+        };
+
+        self.actions
+            .push(RangeAction::new(action, &self.total_range, range));
+        self.finalize_update()
+    }
+
+    /// The frequencies of the removed action are moved to folding.
+    pub fn remove_action(&mut self, action: RangeActionKind) -> Result<()> {
+        let Some(index) = self.actions.iter().position(|a| a.action == action) else {
+            return Err(format!("update range: can't remove action {action}").into());
+        };
+
+        let removed = self.actions.remove(index);
+        if let Some(fold_action) = self
+            .actions
+            .iter_mut()
+            .find(|a| a.action == RangeActionKind::Fold)
+        {
+            for (hand, frequency) in removed.range.iter() {
+                fold_action.range[hand] += frequency;
+            }
+        }
+
+        self.finalize_update()
+    }
+
+    fn update_hand_action_frequency_inner(
+        &mut self,
+        hand: Hand,
+        action: RangeActionKind,
+        frequency: u16,
+    ) -> Result<()> {
+        if frequency > MAX_FREQUENCY {
+            return Err("update range: frequency must be less than 10_000 (100%)".into());
+        }
+
+        if self.total_range[hand] == 0 {
+            if frequency == 0 {
+                return Ok(());
+            }
+            self.total_range[hand] = MAX_FREQUENCY;
+        }
+
+        if self
+            .action_kinds()
+            .all(|current_action| action != current_action)
+        {
+            let range_action = RangeAction::new(
+                action,
+                &self.total_range,
+                RangeTable::EMPTY.to_frequencies(0),
+            );
+            self.actions.push(range_action);
+        }
+
+        // The other actions keep their ratios, without any other action
+        // the rest is folded.
+        let action_kinds: Vec<_> = self.action_kinds().collect();
+        let mut frequencies: Vec<_> = action_kinds
+            .iter()
+            .map(|action| self.hand_frequency(*action, hand))
+            .collect();
+        let target = action_kinds.iter().position(|a| *a == action).unwrap();
+        let remaining = MAX_FREQUENCY - frequency;
+
+        let count = frequencies.len();
+        let others = || (0..count).filter(|index| *index != target);
+        let others_sum: u32 = others().map(|index| u32::from(frequencies[index])).sum();
+        let largest = others().max_by_key(|index| frequencies[*index]);
+
+        frequencies[target] = frequency;
+        if let Some(others_sum) = NonZeroU32::new(others_sum) {
+            let mut assigned = 0;
+            for index in others() {
+                let scaled = u32::from(frequencies[index]) * u32::from(remaining) / others_sum;
+                frequencies[index] = scaled as u16;
+                assigned += scaled as u16;
+            }
+            frequencies[largest.unwrap()] += remaining - assigned;
+        } else {
+            let fold = action_kinds
+                .iter()
+                .position(|a| *a == RangeActionKind::Fold)
+                .unwrap();
+            frequencies[fold] += remaining;
+        }
+
+        for (action, frequency) in action_kinds.into_iter().zip(frequencies) {
+            if let Some(range_action) = self.actions.iter_mut().find(|a| a.action == action) {
+                range_action.range[hand] = frequency;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sets how often the hand takes the action, the other actions
+    /// are scaled to fill the rest. Adds the hand to the range if needed.
+    pub fn update_hand_action_frequency(
+        &mut self,
+        hand: Hand,
+        action: RangeActionKind,
+        frequency: u16,
+    ) -> Result<()> {
+        self.update_hand_action_frequency_inner(hand, action, frequency)?;
+        self.finalize_update()
+    }
+
+    pub fn update_entry_action_frequency(
+        &mut self,
+        entry: RangeEntry,
+        action: RangeActionKind,
+        frequency: u16,
+    ) -> Result<()> {
+        for hand in entry.iter_hands() {
+            self.update_hand_action_frequency_inner(hand, action, frequency)?;
+        }
+        self.finalize_update()
+    }
+
+    pub fn update_hand_total_frequency(&mut self, hand: Hand, frequency: u16) -> Result<()> {
+        if frequency > MAX_FREQUENCY {
+            return Err("update range: frequency must be less than 10_000 (100%)".into());
+        }
+        self.total_range[hand] = frequency;
+        self.finalize_update()
+    }
+
+    pub fn update_entry_total_frequency(
+        &mut self,
+        entry: RangeEntry,
+        frequency: u16,
+    ) -> Result<()> {
+        if frequency > MAX_FREQUENCY {
+            return Err("update range: frequency must be less than 10_000 (100%)".into());
+        }
+        for hand in entry.iter_hands() {
+            self.total_range[hand] = frequency;
+        }
+        self.finalize_update()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RangeConfigEntryData {
+    pub total_range: RangeTableWith<u16>,
+    pub actions: Vec<RangeAction>,
+}
+
+impl RangeConfigEntry {
+    pub fn from_data(data: RangeConfigEntryData) -> Result<Self> {
+        Self::new(data.total_range, data.actions)
+    }
+
+    pub fn to_data(self) -> RangeConfigEntryData {
+        RangeConfigEntryData {
+            total_range: self.total_range,
+            actions: self.actions,
+        }
+    }
+}
+
+/// The ranges of the player to act in a post flop spot.
+#[derive(Debug, Clone)]
+pub struct PostFlopRangeConfigEntry {
+    board: Vec<Card>,
+    /// The post flop actions before the spot.
+    previous_actions: Vec<RangeActionKind>,
+    entry: RangeConfigEntry,
+}
+
+impl PostFlopRangeConfigEntry {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        board: Vec<Card>,
+        previous_actions: Vec<RangeActionKind>,
+        entry: RangeConfigEntry,
+        max_players: usize,
+        depth: MilliBigBlind,
+        small_blind: MilliBigBlind,
+        pre_flop_actions: &[PreFlopAction],
+    ) -> Result<Self> {
+        let entry = Self {
+            board,
+            previous_actions,
+            entry,
+        };
+
+        let mut game = entry.game(max_players, depth, small_blind, pre_flop_actions)?;
+        if !matches!(game.state(), State::Player(_)) {
+            return Err("post flop range config entry: no player to act".into());
+        }
+        for action in entry.entry.action_kinds() {
+            action.apply_to_game(&mut game)?;
+            game.undo().unwrap();
+        }
+
+        Ok(entry)
+    }
+
+    /// Deals the board when a street starts, all board cards must be dealt.
+    pub fn build_game(
+        max_players: usize,
+        depth: MilliBigBlind,
+        small_blind: MilliBigBlind,
+        pre_flop_actions: &[PreFlopAction],
+        board: &[Card],
+        previous_actions: &[RangeActionKind],
+    ) -> Result<Game> {
+        let mut game =
+            PreFlopRangeConfigEntry::build_game(max_players, depth, small_blind, pre_flop_actions)?;
+
+        let mut actions = previous_actions.iter().copied();
+        loop {
+            match game.state() {
+                State::Street(street) => {
+                    let dealt = game.board().cards().len();
+                    let Some(cards) = board.get(dealt..street.community_card_count()) else {
+                        return Err("post flop range config entry: board too short".into());
+                    };
+                    match (street, cards) {
+                        (Street::Flop, [a, b, c]) => game.flop([*a, *b, *c])?,
+                        (Street::Turn, [card]) => game.turn(*card)?,
+                        (Street::River, [card]) => game.river(*card)?,
+                        _ => return Err("post flop range config entry: invalid street".into()),
+                    }
+                }
+                State::Player(_) => {
+                    let Some(action) = actions.next() else {
+                        break;
+                    };
+                    action.apply_to_game(&mut game)?;
+                }
+                _ => return Err("post flop range config entry: hand ended".into()),
+            }
+        }
+
+        if actions.next().is_some() {
+            return Err("post flop range config entry: actions after the hand ended".into());
+        }
+        if game.board().cards().len() != board.len() {
+            return Err("post flop range config entry: board too long".into());
+        }
+
+        Ok(game)
+    }
+
+    pub fn game(
+        &self,
+        max_players: usize,
+        depth: MilliBigBlind,
+        small_blind: MilliBigBlind,
+        pre_flop_actions: &[PreFlopAction],
+    ) -> Result<Game> {
+        Self::build_game(
+            max_players,
+            depth,
+            small_blind,
+            pre_flop_actions,
+            &self.board,
+            &self.previous_actions,
+        )
+    }
+
+    /// Actions with at least the minimum frequency after which
+    /// another player acts on the same street.
+    pub fn possible_next_actions(
+        &self,
+        max_players: usize,
+        depth: MilliBigBlind,
+        small_blind: MilliBigBlind,
+        pre_flop_actions: &[PreFlopAction],
+        min_frequency: f64,
+    ) -> Result<Vec<RangeActionKind>> {
+        let mut game = self.game(max_players, depth, small_blind, pre_flop_actions)?;
+        let mut possible_next_actions = Vec::new();
+
+        for action in self.entry.action_kinds() {
+            action.apply_to_game(&mut game)?;
+            let next_state = game.state();
+            game.undo().unwrap();
+
+            if matches!(next_state, State::Player(_))
+                && self.entry.frequency(action) >= min_frequency
+            {
+                possible_next_actions.push(action);
+            }
+        }
+
+        Ok(possible_next_actions)
+    }
+
+    pub fn board(&self) -> &[Card] {
+        &self.board
+    }
+
+    pub fn previous_actions(&self) -> &[RangeActionKind] {
+        &self.previous_actions
+    }
+
+    pub fn entry(&self) -> &RangeConfigEntry {
+        &self.entry
+    }
+
+    pub fn from_data(
+        data: PostFlopRangeConfigEntryData,
+        max_players: usize,
+        depth: MilliBigBlind,
+        small_blind: MilliBigBlind,
+        pre_flop_actions: &[PreFlopAction],
+    ) -> Result<Self> {
+        Self::new(
+            data.board,
+            data.previous_actions,
+            RangeConfigEntry::from_data(data.entry)?,
+            max_players,
+            depth,
+            small_blind,
+            pre_flop_actions,
+        )
+    }
+
+    pub fn to_data(self) -> PostFlopRangeConfigEntryData {
+        PostFlopRangeConfigEntryData {
+            board: self.board,
+            previous_actions: self.previous_actions,
+            entry: self.entry.to_data(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PostFlopRangeConfigEntryData {
+    pub board: Vec<Card>,
+    pub previous_actions: Vec<RangeActionKind>,
+    pub entry: RangeConfigEntryData,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PostFlopRangeConfigData {
+    pub description: Option<Arc<String>>,
+    pub max_players: usize,
+    pub depth: MilliBigBlind,
+    pub small_blind: MilliBigBlind,
+    /// The pre flop line all spots follow.
+    pub pre_flop_actions: Vec<PreFlopAction>,
+    pub spots: Vec<PostFlopRangeConfigEntryData>,
+}
+
+/// Post flop spots after one pre flop line, e.g. flop c-bets on several boards.
+#[derive(Debug, Clone)]
+pub struct PostFlopRangeConfig {
+    description: Option<Arc<String>>,
+    max_players: usize,
+    depth: MilliBigBlind,
+    small_blind: MilliBigBlind,
+    pre_flop_actions: Vec<PreFlopAction>,
+    spots: Vec<PostFlopRangeConfigEntry>,
+}
+
+impl PostFlopRangeConfig {
+    pub fn from_data(data: PostFlopRangeConfigData) -> Result<Self> {
+        if data.depth < 1_000 {
+            return Err("range config: depth must be greater or equal to one big blind".into());
+        }
+        if data.max_players < Game::MIN_PLAYERS || data.max_players > Game::MAX_PLAYERS {
+            return Err("range config: invalid max players value".into());
+        }
+        if data.small_blind <= 0 || data.small_blind > 1_000 {
+            return Err("range config: invalid small blind size".into());
+        }
+
+        let mut keys = HashSet::new();
+        let mut spots = Vec::new();
+        for spot in data.spots {
+            if !keys.insert((spot.board.clone(), spot.previous_actions.clone())) {
+                return Err("range config: contains duplicated spot".into());
+            }
+            let spot = PostFlopRangeConfigEntry::from_data(
+                spot,
+                data.max_players,
+                data.depth,
+                data.small_blind,
+                &data.pre_flop_actions,
+            )?;
+            spots.push(spot);
+        }
+
+        Ok(Self {
+            description: data.description,
+            max_players: data.max_players,
+            depth: data.depth,
+            small_blind: data.small_blind,
+            pre_flop_actions: data.pre_flop_actions,
+            spots,
+        })
+    }
+
+    pub fn to_data(self) -> PostFlopRangeConfigData {
+        PostFlopRangeConfigData {
+            description: self.description,
+            max_players: self.max_players,
+            depth: self.depth,
+            small_blind: self.small_blind,
+            pre_flop_actions: self.pre_flop_actions,
+            spots: self.spots.into_iter().map(|spot| spot.to_data()).collect(),
+        }
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref().map(String::as_str)
+    }
+
+    pub fn pre_flop_actions(&self) -> &[PreFlopAction] {
+        &self.pre_flop_actions
+    }
+
+    pub fn spots(&self) -> &[PostFlopRangeConfigEntry] {
+        &self.spots
+    }
+
+    pub fn spot_game(&self, spot: &PostFlopRangeConfigEntry) -> Result<Game> {
+        spot.game(
+            self.max_players,
+            self.depth,
+            self.small_blind,
+            &self.pre_flop_actions,
+        )
+    }
 }
 
 // TODO: Probably better to use a custom type for range frequencies.
@@ -2029,3 +2987,185 @@ pub fn frequency_to_f64(n: u16) -> f64 {
     let n = cmp::min(n, MAX_FREQUENCY);
     f64::from(n) / f64::from(MAX_FREQUENCY)
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A heads up node with every hand in the total range and the actions taking the
+    /// hands of their range strings, e.g. `[75]22+` for 75% of the pairs.
+    pub(crate) fn config_entry(
+        previous_actions: Vec<PreFlopAction>,
+        depth: MilliBigBlind,
+        actions: &[(PreFlopAction, &str)],
+        ev: Option<PreFlopRangeTableWith<MilliBigBlind>>,
+    ) -> PreFlopRangeConfigEntry {
+        let mut total_range = PreFlopRangeTableWith::default();
+        for entry in PreFlopRangeTable::entries() {
+            total_range[entry] = MAX_FREQUENCY;
+        }
+
+        let actions = actions
+            .iter()
+            .map(|(action, range_str)| {
+                let hands = RangeTable::parse_weighted(range_str).unwrap();
+                let mut range = PreFlopRangeTableWith::default();
+                for entry in PreFlopRangeTable::entries() {
+                    range[entry] = hands[entry.iter_hands().next().unwrap()];
+                }
+                PreFlopRangeAction::new(*action, &total_range, range, ev.clone())
+            })
+            .collect();
+
+        PreFlopRangeConfigEntry::new(previous_actions, total_range, actions, 2, depth, 500, false)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_range_table_to_string_round_trip() {
+        unsafe {
+            crate::init::init();
+        }
+
+        for range_str in [
+            "",
+            "full",
+            "QQ+,A2s+,KJo+,T9s,55",
+            "AA,KK,AhKh,AsKd,72o",
+            "22+,AKs,AKo",
+        ] {
+            let range = RangeTable::parse(range_str).unwrap();
+            let formatted = range.to_string();
+            assert_eq!(RangeTable::parse(&formatted).unwrap(), range, "{formatted}");
+        }
+
+        let range = RangeTable::parse("KK,AA,QQ,A9s,ATs,AJs,AQs,AKs").unwrap();
+        assert_eq!(range.to_string(), "QQ+,A9s+");
+    }
+
+    #[test]
+    fn test_update_action_frequency() {
+        unsafe {
+            crate::init::init();
+        }
+
+        let raise = RangeActionKind::Raise(2500);
+        let entry: RangeEntry = "AKs".parse().unwrap();
+        let hand = entry.iter_hands().next().unwrap();
+
+        let mut config = RangeConfigEntry::default();
+        config
+            .update_entry_action_frequency(entry, raise, 6_000)
+            .unwrap();
+        assert_eq!(config.total_range()[hand], MAX_FREQUENCY);
+        assert_eq!(config.hand_frequency(raise, hand), 6_000);
+        assert_eq!(config.hand_frequency(RangeActionKind::Fold, hand), 4_000);
+
+        config
+            .update_hand_action_frequency(hand, RangeActionKind::Call, 2_500)
+            .unwrap();
+        assert_eq!(config.hand_frequency(RangeActionKind::Call, hand), 2_500);
+        assert_eq!(config.hand_frequency(raise, hand), 4_500);
+        assert_eq!(config.hand_frequency(RangeActionKind::Fold, hand), 3_000);
+
+        config.remove_action(RangeActionKind::Call).unwrap();
+        assert_eq!(config.hand_frequency(RangeActionKind::Fold, hand), 5_500);
+        assert!(config.remove_action(RangeActionKind::Fold).is_err());
+    }
+
+    #[test]
+    fn test_config_set_interpolation() {
+        unsafe {
+            crate::init::init();
+        }
+
+        let config = |depth, open_raise: &str| {
+            let entry = config_entry(
+                vec![],
+                depth,
+                &[
+                    (PreFlopAction::Raise(2_500), open_raise),
+                    (PreFlopAction::Fold, &format!("full!({open_raise})")),
+                ],
+                None,
+            );
+
+            PreFlopRangeConfig::from_data(PreFlopRangeConfigData {
+                description: None,
+                max_players: 2,
+                depth,
+                small_blind: 500,
+                ranges: vec![entry.to_data()],
+            })
+            .unwrap()
+        };
+
+        let set =
+            PreFlopRangeConfigSet::new(vec![config(100_000, "22+,A2s+"), config(20_000, "22+")])
+                .unwrap();
+        let mut rng = rand::thread_rng();
+
+        // Halfway between both depths.
+        let game = PreFlopRangeConfigEntry::build_game(2, 60_000, 500, &[]).unwrap();
+        assert_eq!(
+            PreFlopRangeConfigSet::effective_stack(&game).unwrap(),
+            60_000
+        );
+        let entries = set.by_game_interpolated(&game, &mut rng).unwrap();
+        let depths: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.config.depth(), entry.weight))
+            .collect();
+        assert_eq!(depths, vec![(20_000, 0.5), (100_000, 0.5)]);
+
+        let interpolated = entries[0]
+            .entry
+            .to_full_range()
+            .interpolate(&entries[1].entry.to_full_range(), entries[1].weight)
+            .unwrap();
+        let raise = RangeActionKind::Raise(2_500);
+        assert_eq!(
+            interpolated.entry_frequency(raise, "55".parse().unwrap()),
+            1.0
+        );
+        assert_eq!(
+            interpolated.entry_frequency(raise, "A5s".parse().unwrap()),
+            0.5
+        );
+        assert_eq!(
+            interpolated.entry_frequency(raise, "K5s".parse().unwrap()),
+            0.0
+        );
+
+        // Deeper than every config.
+        let game = PreFlopRangeConfigEntry::build_game(2, 250_000, 500, &[]).unwrap();
+        let (best_fit, _, _) = set.by_game_best_fit_raise_simple(&game).unwrap();
+        assert_eq!(best_fit.depth(), 100_000);
+        assert!(set.configs()[0].by_game_action_kinds(&game).is_err());
+
+        // At the depth of a config without a matching range, the other one is used even
+        // though it has no weight.
+        let deep = PreFlopRangeConfig::from_data(PreFlopRangeConfigData {
+            description: None,
+            max_players: 2,
+            depth: 100_000,
+            small_blind: 500,
+            ranges: vec![config_entry(
+                vec![PreFlopAction::Raise(2_500)],
+                100_000,
+                &[(PreFlopAction::Fold, "full")],
+                None,
+            )
+            .to_data()],
+        })
+        .unwrap();
+        let set = PreFlopRangeConfigSet::new(vec![deep, config(20_000, "22+")]).unwrap();
+        let game = PreFlopRangeConfigEntry::build_game(2, 100_000, 500, &[]).unwrap();
+        let entries = set.by_game_interpolated(&game, &mut rng).unwrap();
+        let depths: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.config.depth(), entry.weight))
+            .collect();
+        assert_eq!(depths, vec![(20_000, 1.0)]);
+    }
+}
@@ -0,0 +1,347 @@
+use std::{fs, io::Read, io::Write, path::Path, sync::Arc};
+
+use crate::{
+    game::MilliBigBlind,
+    range::{
+        PreFlopAction, PreFlopRangeAction, PreFlopRangeConfig, PreFlopRangeConfigData,
+        PreFlopRangeConfigEntry, PreFlopRangeConfigEntryData, PreFlopRangeTableWith,
+        RangeTableWith,
+    },
+    result::Result,
+};
+
+// Layout, all numbers little endian:
+// - Header: magic, version, description, max players, depth and small blind.
+// - Nodes: the node count, then the previous actions, the total range and the actions
+//   with their ranges and EVs of every node.
+
+const MAGIC: &[u8; 4] = b"PRCB";
+pub const VERSION: u16 = 1;
+/// Checked before allocating, so a corrupt length can't request gigabytes.
+const MAX_BYTES_LEN: u32 = 1 << 20;
+
+/// Whether the bytes start like a binary range config.
+pub fn is_binary(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+/// Reads a pre flop range config in the binary or JSON format.
+pub fn load_pre_flop_range_config(path: &Path) -> Result<PreFlopRangeConfig> {
+    let bytes = fs::read(path)?;
+    if is_binary(&bytes) {
+        read_config(bytes.as_slice())
+    } else {
+        let data: PreFlopRangeConfigData = serde_json::from_slice(&bytes)?;
+        PreFlopRangeConfig::from_data(data)
+    }
+}
+
+pub fn write_config(mut writer: impl Write, config: &PreFlopRangeConfig) -> Result<()> {
+    writer.write_all(MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+
+    match config.description() {
+        Some(description) => {
+            writer.write_all(&[1])?;
+            write_bytes(&mut writer, description.as_bytes())?;
+        }
+        None => writer.write_all(&[0])?,
+    }
+    writer.write_all(&u8::try_from(config.max_players())?.to_le_bytes())?;
+    writer.write_all(&config.depth().to_le_bytes())?;
+    writer.write_all(&config.small_blind().to_le_bytes())?;
+
+    writer.write_all(&u32::try_from(config.ranges().len())?.to_le_bytes())?;
+    for entry in config.ranges() {
+        write_actions(&mut writer, entry.previous_actions())?;
+        write_node(&mut writer, entry)?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn read_config(mut reader: impl Read) -> Result<PreFlopRangeConfig> {
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err("range binary: not a binary range config".into());
+    }
+    let version = read_u16(&mut reader)?;
+    if version != VERSION {
+        return Err(format!("range binary: unsupported version {version}").into());
+    }
+
+    let description = match read_u8(&mut reader)? {
+        0 => None,
+        _ => Some(Arc::new(String::from_utf8(read_bytes(&mut reader)?)?)),
+    };
+    let max_players = usize::from(read_u8(&mut reader)?);
+    let depth = read_i64(&mut reader)?;
+    let small_blind = read_i64(&mut reader)?;
+
+    let node_count = read_u32(&mut reader)?;
+    let mut ranges = Vec::new();
+    for _ in 0..node_count {
+        let previous_actions = read_actions(&mut reader)?;
+        ranges.push(read_node(&mut reader, previous_actions)?);
+    }
+
+    PreFlopRangeConfig::from_data(PreFlopRangeConfigData {
+        description,
+        max_players,
+        depth,
+        small_blind,
+        ranges,
+    })
+}
+
+pub fn write_range_table(mut writer: impl Write, range: &RangeTableWith<u16>) -> Result<()> {
+    for (_, frequency) in range.iter() {
+        writer.write_all(&frequency.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+pub fn read_range_table(mut reader: impl Read) -> Result<RangeTableWith<u16>> {
+    let mut range = RangeTableWith::default();
+    for (_, frequency) in range.iter_mut() {
+        *frequency = read_u16(&mut reader)?;
+    }
+    Ok(range)
+}
+
+fn write_node(mut writer: impl Write, entry: &PreFlopRangeConfigEntry) -> Result<()> {
+    write_pre_flop_table(&mut writer, entry.total_range())?;
+
+    writer.write_all(&u8::try_from(entry.actions().len())?.to_le_bytes())?;
+    for action in entry.actions() {
+        write_action(&mut writer, action.action())?;
+        write_pre_flop_table(&mut writer, action.range())?;
+        match action.ev() {
+            Some(ev) => {
+                writer.write_all(&[1])?;
+                for (_, ev) in ev.iter() {
+                    let ev = i32::try_from(*ev)
+                        .map_err(|_| "range binary: ev out of range".to_string())?;
+                    writer.write_all(&ev.to_le_bytes())?;
+                }
+            }
+            None => writer.write_all(&[0])?,
+        }
+    }
+
+    Ok(())
+}
+
+fn read_node(
+    mut reader: impl Read,
+    previous_actions: Vec<PreFlopAction>,
+) -> Result<PreFlopRangeConfigEntryData> {
+    let total_range = read_pre_flop_table(&mut reader)?;
+
+    let action_count = read_u8(&mut reader)?;
+    let mut actions = Vec::with_capacity(usize::from(action_count));
+    for _ in 0..action_count {
+        let action = read_action(&mut reader)?;
+        let range = read_pre_flop_table(&mut reader)?;
+        let ev = match read_u8(&mut reader)? {
+            0 => None,
+            _ => {
+                let mut ev = PreFlopRangeTableWith::default();
+                for (_, ev) in ev.iter_mut() {
+                    *ev = MilliBigBlind::from(read_i32(&mut reader)?);
+                }
+                Some(ev)
+            }
+        };
+        actions.push(PreFlopRangeAction::new(action, &total_range, range, ev));
+    }
+
+    let total_frequency = total_range
+        .iter()
+        .map(|(entry, frequency)| u64::from(*frequency) * u64::from(entry.combo_count()))
+        .sum();
+
+    Ok(PreFlopRangeConfigEntryData {
+        previous_actions,
+        total_range,
+        total_frequency,
+        actions,
+    })
+}
+
+fn write_pre_flop_table(mut writer: impl Write, range: &PreFlopRangeTableWith<u16>) -> Result<()> {
+    for (_, frequency) in range.iter() {
+        writer.write_all(&frequency.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+fn read_pre_flop_table(mut reader: impl Read) -> Result<PreFlopRangeTableWith<u16>> {
+    let mut range = PreFlopRangeTableWith::default();
+    for (_, frequency) in range.iter_mut() {
+        *frequency = read_u16(&mut reader)?;
+    }
+    Ok(range)
+}
+
+fn write_actions(mut writer: impl Write, actions: &[PreFlopAction]) -> Result<()> {
+    writer.write_all(&u16::try_from(actions.len())?.to_le_bytes())?;
+    for action in actions {
+        write_action(&mut writer, *action)?;
+    }
+    Ok(())
+}
+
+fn read_actions(mut reader: impl Read) -> Result<Vec<PreFlopAction>> {
+    let count = read_u16(&mut reader)?;
+    (0..count).map(|_| read_action(&mut reader)).collect()
+}
+
+fn write_action(mut writer: impl Write, action: PreFlopAction) -> Result<()> {
+    match action {
+        PreFlopAction::Post { player, amount } => {
+            writer.write_all(&[0, player])?;
+            writer.write_all(&amount.to_le_bytes())?;
+        }
+        PreFlopAction::Straddle { player, amount } => {
+            writer.write_all(&[1, player])?;
+            writer.write_all(&amount.to_le_bytes())?;
+        }
+        PreFlopAction::Fold => writer.write_all(&[2])?,
+        PreFlopAction::Check => writer.write_all(&[3])?,
+        PreFlopAction::Call => writer.write_all(&[4])?,
+        PreFlopAction::Raise(to) => {
+            writer.write_all(&[5])?;
+            writer.write_all(&to.to_le_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+fn read_action(mut reader: impl Read) -> Result<PreFlopAction> {
+    let action = match read_u8(&mut reader)? {
+        0 => PreFlopAction::Post {
+            player: read_u8(&mut reader)?,
+            amount: read_i64(&mut reader)?,
+        },
+        1 => PreFlopAction::Straddle {
+            player: read_u8(&mut reader)?,
+            amount: read_i64(&mut reader)?,
+        },
+        2 => PreFlopAction::Fold,
+        3 => PreFlopAction::Check,
+        4 => PreFlopAction::Call,
+        5 => PreFlopAction::Raise(read_i64(&mut reader)?),
+        tag => return Err(format!("range binary: invalid action {tag}").into()),
+    };
+    Ok(action)
+}
+
+fn write_bytes(mut writer: impl Write, bytes: &[u8]) -> Result<()> {
+    writer.write_all(&u32::try_from(bytes.len())?.to_le_bytes())?;
+    writer.write_all(bytes)?;
+    Ok(())
+}
+
+fn read_bytes(mut reader: impl Read) -> Result<Vec<u8>> {
+    let len = read_u32(&mut reader)?;
+    if len > MAX_BYTES_LEN {
+        return Err(format!("range binary: invalid length {len}").into());
+    }
+    let mut bytes = vec![0; usize::try_from(len)?];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_array<const N: usize>(mut reader: impl Read) -> Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_u8(reader: impl Read) -> Result<u8> {
+    Ok(u8::from_le_bytes(read_array(reader)?))
+}
+
+fn read_u16(reader: impl Read) -> Result<u16> {
+    Ok(u16::from_le_bytes(read_array(reader)?))
+}
+
+fn read_u32(reader: impl Read) -> Result<u32> {
+    Ok(u32::from_le_bytes(read_array(reader)?))
+}
+
+fn read_i32(reader: impl Read) -> Result<i32> {
+    Ok(i32::from_le_bytes(read_array(reader)?))
+}
+
+fn read_i64(reader: impl Read) -> Result<i64> {
+    Ok(i64::from_le_bytes(read_array(reader)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        hand::Hand,
+        range::{tests::config_entry, PreFlopRangeTable, MAX_FREQUENCY},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        unsafe {
+            crate::init::init();
+        }
+
+        let mut ev = PreFlopRangeTableWith::default();
+        for (index, entry) in PreFlopRangeTable::entries().enumerate() {
+            ev[entry] = 1_000 - i64::try_from(index).unwrap() * 20;
+        }
+        let entry = |previous_actions, action, range_str| {
+            config_entry(
+                previous_actions,
+                100_000,
+                &[(action, range_str)],
+                Some(ev.clone()),
+            )
+            .to_data()
+        };
+
+        let config = PreFlopRangeConfig::from_data(PreFlopRangeConfigData {
+            description: Some(Arc::new("HeadsUp".to_string())),
+            max_players: 2,
+            depth: 100_000,
+            small_blind: 500,
+            ranges: vec![
+                entry(vec![], PreFlopAction::Raise(2_500), "[75](22+,A2s+)"),
+                entry(
+                    vec![PreFlopAction::Raise(2_500)],
+                    PreFlopAction::Call,
+                    "[75]full",
+                ),
+            ],
+        })
+        .unwrap();
+
+        let mut bytes = Vec::new();
+        write_config(&mut bytes, &config).unwrap();
+        assert!(is_binary(&bytes));
+        let json = |config: PreFlopRangeConfig| serde_json::to_string(&config.to_data()).unwrap();
+        assert_eq!(json(read_config(bytes.as_slice()).unwrap()), json(config));
+
+        // A description claiming 4 GiB.
+        let mut corrupt = bytes[..MAGIC.len() + 3].to_vec();
+        corrupt.extend_from_slice(&u32::MAX.to_le_bytes());
+        assert!(read_config(corrupt.as_slice()).is_err());
+
+        let mut range = RangeTableWith::default();
+        range[Hand::from_index(7)] = MAX_FREQUENCY;
+        let mut bytes = Vec::new();
+        write_range_table(&mut bytes, &range).unwrap();
+        assert_eq!(bytes.len(), Hand::COUNT * 2);
+        assert!(read_range_table(bytes.as_slice()).unwrap() == range);
+    }
+}
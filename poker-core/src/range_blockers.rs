@@ -0,0 +1,142 @@
+use std::fmt;
+
+use crate::{
+    cards::Cards,
+    hand::Hand,
+    range::{RangeActionKind, RangeConfigEntry},
+    result::Result,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActionBlockers {
+    pub action: RangeActionKind,
+    /// Weighted by the frequency of the action, without the hands holding a board card.
+    pub combos: f64,
+    /// The combos holding a card of the hero hand.
+    pub removed_combos: f64,
+    /// From 0.0 to 1.0 of the range without the board cards.
+    pub frequency: f64,
+    /// From 0.0 to 1.0 of the range without the board and hero cards.
+    pub frequency_with_blockers: f64,
+}
+
+/// Which combos of every villain action the hero hand removes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockerAnalysis {
+    pub hero: Hand,
+    pub board: Cards,
+    pub actions: Vec<ActionBlockers>,
+}
+
+impl BlockerAnalysis {
+    /// The board has up to 5 cards, pre flop it is empty.
+    pub fn new(hero: Hand, board: Cards, villain: &RangeConfigEntry) -> Result<Self> {
+        if board.count() > 5 {
+            return Err(format!("range blockers: the board {board} has more than 5 cards").into());
+        }
+        if board.has(hero.high()) || board.has(hero.low()) {
+            return Err(format!("range blockers: {hero} holds a card of the board").into());
+        }
+
+        let dead_cards = board.with(hero.high()).with(hero.low());
+        let mut actions: Vec<_> = villain
+            .action_kinds()
+            .filter_map(|action| {
+                let range = villain.action_range(action)?;
+                let combos = range.effective_combos(board);
+                Some(ActionBlockers {
+                    action,
+                    combos,
+                    removed_combos: combos - range.effective_combos(dead_cards),
+                    frequency: 0.0,
+                    frequency_with_blockers: 0.0,
+                })
+            })
+            .collect();
+
+        let total: f64 = actions.iter().map(|action| action.combos).sum();
+        let total_with_blockers: f64 = actions
+            .iter()
+            .map(|action| action.combos - action.removed_combos)
+            .sum();
+        for action in &mut actions {
+            if total > 0.0 {
+                action.frequency = action.combos / total;
+            }
+            if total_with_blockers > 0.0 {
+                action.frequency_with_blockers =
+                    (action.combos - action.removed_combos) / total_with_blockers;
+            }
+        }
+
+        Ok(Self {
+            hero,
+            board,
+            actions,
+        })
+    }
+}
+
+impl fmt::Display for BlockerAnalysis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Blockers of {}", self.hero)?;
+        if self.board != Cards::EMPTY {
+            write!(f, " on {}", self.board)?;
+        }
+        writeln!(f, ":")?;
+
+        for action in &self.actions {
+            writeln!(
+                f,
+                "{}: {:.1} of {:.1} combos removed, {:.1}% -> {:.1}%",
+                action.action,
+                action.removed_combos,
+                action.combos,
+                action.frequency * 100.0,
+                action.frequency_with_blockers * 100.0,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::range::{RangeAction, RangeTable, MAX_FREQUENCY};
+
+    use super::*;
+
+    #[test]
+    fn test_blocker_analysis() {
+        unsafe {
+            crate::init::init();
+        }
+
+        // Villain bets the nut flush draws and the sets, folds everything else.
+        let total_range = RangeTable::FULL.to_frequencies(MAX_FREQUENCY);
+        let bets = RangeTable::parse("AhXh,KK,77,22").unwrap();
+        let bet = RangeAction::new(
+            RangeActionKind::Bet(3_000),
+            &total_range,
+            bets.to_frequencies(MAX_FREQUENCY),
+        );
+        let villain = RangeConfigEntry::new(total_range, vec![bet]).unwrap();
+
+        let board = Cards::from_str("Kh7h2c").unwrap();
+        let analysis = BlockerAnalysis::new("AhQd".parse().unwrap(), board, &villain).unwrap();
+        assert_eq!(analysis.actions.len(), 2);
+
+        let bet = &analysis.actions[0];
+        assert_eq!(bet.action, RangeActionKind::Bet(3_000));
+        // AhXh without the board hearts, plus 3 combos of every set.
+        assert_eq!(bet.combos, 10.0 + 9.0);
+        assert_eq!(bet.removed_combos, 10.0);
+        assert!(bet.frequency_with_blockers < bet.frequency);
+
+        let fold = &analysis.actions[1];
+        assert_eq!(fold.action, RangeActionKind::Fold);
+        assert!(fold.frequency_with_blockers > fold.frequency);
+
+        assert!(BlockerAnalysis::new("Kh7d".parse().unwrap(), board, &villain).is_err());
+    }
+}
@@ -0,0 +1,370 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use crate::{
+    game::{milli_big_blind_to_f64_approximate, MilliBigBlind},
+    range::{
+        frequency_to_f64, PreFlopAction, PreFlopRangeConfig, PreFlopRangeConfigEntry,
+        PreFlopRangeTable, RangeEntry, RangeTableWith, MAX_FREQUENCY,
+    },
+    result::Result,
+};
+
+/// Changes up to the thresholds are not reported.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiffThreshold {
+    /// From 0.0 to 1.0.
+    pub frequency: f64,
+    pub ev: MilliBigBlind,
+}
+
+impl Default for DiffThreshold {
+    fn default() -> Self {
+        Self {
+            frequency: 0.05,
+            ev: 100,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntryDiff {
+    pub entry: RangeEntry,
+    /// The total range if not set.
+    pub action: Option<PreFlopAction>,
+    pub old_frequency: f64,
+    pub new_frequency: f64,
+    pub old_ev: Option<MilliBigBlind>,
+    pub new_ev: Option<MilliBigBlind>,
+}
+
+#[derive(Debug, Clone)]
+pub struct NodeDiff {
+    pub previous_actions: Vec<PreFlopAction>,
+    pub entries: Vec<EntryDiff>,
+    /// How much the range and the strategy of every hand changed,
+    /// from 0 to `MAX_FREQUENCY`, including changes below the threshold.
+    pub heatmap: RangeTableWith<u16>,
+}
+
+/// The nodes of two configs matched on their previous actions.
+#[derive(Debug, Clone, Default)]
+pub struct PreFlopRangeConfigDiff {
+    /// Only in the new config.
+    pub added: Vec<Vec<PreFlopAction>>,
+    /// Only in the old config.
+    pub removed: Vec<Vec<PreFlopAction>>,
+    /// In both configs with at least one change above the threshold.
+    pub changed: Vec<NodeDiff>,
+}
+
+impl PreFlopRangeConfigDiff {
+    pub fn new(
+        old: &PreFlopRangeConfig,
+        new: &PreFlopRangeConfig,
+        threshold: DiffThreshold,
+    ) -> Self {
+        let new_by_actions: HashMap<_, _> = new
+            .ranges()
+            .iter()
+            .map(|entry| (entry.previous_actions(), entry))
+            .collect();
+
+        let mut diff = Self::default();
+        for old_entry in old.ranges() {
+            let Some(new_entry) = new_by_actions.get(old_entry.previous_actions()) else {
+                diff.removed.push(old_entry.previous_actions().to_vec());
+                continue;
+            };
+
+            let node = Self::diff_node(old_entry, new_entry, threshold);
+            if !node.entries.is_empty() {
+                diff.changed.push(node);
+            }
+        }
+
+        let old_actions: HashSet<_> = old
+            .ranges()
+            .iter()
+            .map(|entry| entry.previous_actions())
+            .collect();
+        diff.added = new
+            .ranges()
+            .iter()
+            .map(|entry| entry.previous_actions())
+            .filter(|actions| !old_actions.contains(actions))
+            .map(|actions| actions.to_vec())
+            .collect();
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    fn diff_node(
+        old: &PreFlopRangeConfigEntry,
+        new: &PreFlopRangeConfigEntry,
+        threshold: DiffThreshold,
+    ) -> NodeDiff {
+        let mut actions: Vec<_> = old
+            .actions()
+            .iter()
+            .chain(new.actions())
+            .map(|action| action.action())
+            .chain([PreFlopAction::Fold])
+            .collect();
+        actions.sort();
+        actions.dedup();
+
+        let mut entries = Vec::new();
+        let mut heatmap = RangeTableWith::default();
+
+        for entry in PreFlopRangeTable::entries() {
+            let old_total = old.total_range()[entry];
+            let new_total = new.total_range()[entry];
+            if old_total == 0 && new_total == 0 {
+                continue;
+            }
+
+            let total_change = old_total.abs_diff(new_total);
+            if frequency_to_f64(total_change) > threshold.frequency {
+                entries.push(EntryDiff {
+                    entry,
+                    action: None,
+                    old_frequency: frequency_to_f64(old_total),
+                    new_frequency: frequency_to_f64(new_total),
+                    old_ev: None,
+                    new_ev: None,
+                });
+            }
+
+            let mut strategy_change = 0;
+            for action in actions.iter().copied() {
+                let old_frequency = old.entry_frequency(action, entry);
+                let new_frequency = new.entry_frequency(action, entry);
+                strategy_change += u32::from(old_frequency.abs_diff(new_frequency));
+
+                let old_ev = old.entry_ev(action, entry);
+                let new_ev = new.entry_ev(action, entry);
+                let ev_changed = match (old_ev, new_ev) {
+                    (Some(old_ev), Some(new_ev)) => {
+                        old_ev.abs_diff(new_ev) > threshold.ev.unsigned_abs()
+                    }
+                    (None, None) => false,
+                    // EVs added or removed.
+                    _ => true,
+                };
+
+                if frequency_to_f64(old_frequency.abs_diff(new_frequency)) > threshold.frequency
+                    || ev_changed
+                {
+                    entries.push(EntryDiff {
+                        entry,
+                        action: Some(action),
+                        old_frequency: frequency_to_f64(old_frequency),
+                        new_frequency: frequency_to_f64(new_frequency),
+                        old_ev,
+                        new_ev,
+                    });
+                }
+            }
+
+            // Every frequency moved away from one action is moved to another.
+            let strategy_change = u16::try_from(strategy_change / 2).unwrap_or(MAX_FREQUENCY);
+            let change = total_change.max(strategy_change).min(MAX_FREQUENCY);
+            for hand in entry.iter_hands() {
+                heatmap[hand] = change;
+            }
+        }
+
+        NodeDiff {
+            previous_actions: old.previous_actions().to_vec(),
+            entries,
+            heatmap,
+        }
+    }
+}
+
+impl fmt::Display for PreFlopRangeConfigDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for previous_actions in &self.added {
+            writeln!(f, "+ {previous_actions:?}")?;
+        }
+        for previous_actions in &self.removed {
+            writeln!(f, "- {previous_actions:?}")?;
+        }
+
+        for node in &self.changed {
+            writeln!(f, "~ {:?}", node.previous_actions)?;
+            for entry in &node.entries {
+                let action = match entry.action {
+                    Some(action) => format!("{action:?}"),
+                    None => "Range".to_string(),
+                };
+                write!(
+                    f,
+                    "    {} {action}: {:.1}% -> {:.1}%",
+                    entry.entry.to_regular_string(),
+                    entry.old_frequency * 100.0,
+                    entry.new_frequency * 100.0
+                )?;
+                if entry.old_ev.is_some() || entry.new_ev.is_some() {
+                    let ev = |ev: Option<MilliBigBlind>| match ev {
+                        Some(ev) => format!("{:.2}", milli_big_blind_to_f64_approximate(ev)),
+                        None => "none".to_string(),
+                    };
+                    write!(f, ", EV {} -> {} bb", ev(entry.old_ev), ev(entry.new_ev))?;
+                }
+                writeln!(f)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Overlays the nodes of `overlay` onto `base`, nodes only in `base` are kept.
+/// Both configs need the same table size, depth and small blind.
+pub fn merge(
+    base: &PreFlopRangeConfig,
+    overlay: &PreFlopRangeConfig,
+) -> Result<PreFlopRangeConfig> {
+    if base.max_players() != overlay.max_players()
+        || base.depth() != overlay.depth()
+        || base.small_blind() != overlay.small_blind()
+    {
+        return Err(
+            "range diff: merged configs must have the same max players, depth and small blind"
+                .into(),
+        );
+    }
+
+    let mut data = base.clone().to_data();
+    let mut index_by_actions: HashMap<_, _> = data
+        .ranges
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| (entry.previous_actions.clone(), index))
+        .collect();
+    for entry in overlay.ranges() {
+        let entry = entry.clone().to_data();
+        match index_by_actions.get(&entry.previous_actions) {
+            Some(index) => data.ranges[*index] = entry,
+            None => {
+                index_by_actions.insert(entry.previous_actions.clone(), data.ranges.len());
+                data.ranges.push(entry);
+            }
+        }
+    }
+
+    PreFlopRangeConfig::from_data(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::range::{tests::config_entry, PreFlopRangeConfigData, PreFlopRangeTableWith};
+
+    use super::*;
+
+    /// The open raise has the same EV for every entry if there is one.
+    fn config(open_raise: &str, three_bet: bool, ev: Option<MilliBigBlind>) -> PreFlopRangeConfig {
+        let ev = ev.map(|ev| {
+            let mut table = PreFlopRangeTableWith::default();
+            for entry in PreFlopRangeTable::entries() {
+                table[entry] = ev;
+            }
+            table
+        });
+        let entry = |previous_actions, action, range_str, ev| {
+            config_entry(previous_actions, 100_000, &[(action, range_str)], ev).to_data()
+        };
+
+        let mut ranges = vec![entry(vec![], PreFlopAction::Raise(2_500), open_raise, ev)];
+        if three_bet {
+            ranges.push(entry(
+                vec![PreFlopAction::Raise(2_500)],
+                PreFlopAction::Call,
+                "full",
+                None,
+            ));
+        }
+
+        PreFlopRangeConfig::from_data(PreFlopRangeConfigData {
+            description: None,
+            max_players: 2,
+            depth: 100_000,
+            small_blind: 500,
+            ranges,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_diff_and_merge() {
+        unsafe {
+            crate::init::init();
+        }
+
+        let old = config("22+,A2s+", true, None);
+        let new = config("33+,A2s+,KQo", false, None);
+
+        let diff = PreFlopRangeConfigDiff::new(&old, &new, DiffThreshold::default());
+        assert!(diff.added.is_empty());
+        assert_eq!(diff.removed, vec![vec![PreFlopAction::Raise(2_500)]]);
+        assert_eq!(diff.changed.len(), 1);
+
+        let changed: Vec<_> = diff.changed[0]
+            .entries
+            .iter()
+            .filter(|entry| entry.action == Some(PreFlopAction::Raise(2_500)))
+            .map(|entry| (entry.entry.to_regular_string(), entry.new_frequency))
+            .collect();
+        assert_eq!(
+            changed,
+            vec![("KQo".to_string(), 1.0), ("22".to_string(), 0.0)]
+        );
+
+        let heatmap = &diff.changed[0].heatmap;
+        let hand = |entry: &str| {
+            entry
+                .parse::<RangeEntry>()
+                .unwrap()
+                .iter_hands()
+                .next()
+                .unwrap()
+        };
+        assert_eq!(heatmap[hand("22")], MAX_FREQUENCY);
+        assert_eq!(heatmap[hand("AA")], 0);
+
+        // The open is replaced, the call of the 3-bet is kept.
+        let merged = merge(&old, &new).unwrap();
+        let diff = PreFlopRangeConfigDiff::new(&new, &merged, DiffThreshold::default());
+        assert_eq!(diff.added, vec![vec![PreFlopAction::Raise(2_500)]]);
+        assert!(diff.removed.is_empty() && diff.changed.is_empty());
+
+        // Added EVs are a change, even without a threshold.
+        let with_ev = config("22+,A2s+", true, Some(1_000));
+        let threshold = DiffThreshold {
+            frequency: 1.0,
+            ev: MilliBigBlind::MAX,
+        };
+        let diff = PreFlopRangeConfigDiff::new(&old, &with_ev, threshold);
+        assert_eq!(diff.changed.len(), 1);
+        let entries = &diff.changed[0].entries;
+        assert_eq!(entries.len(), PreFlopRangeTable::entries().count());
+        assert!(entries
+            .iter()
+            .all(|entry| entry.old_ev.is_none() && entry.new_ev == Some(1_000)));
+
+        let diff = PreFlopRangeConfigDiff::new(&with_ev, &old, threshold);
+        assert_eq!(diff.changed.len(), 1);
+        assert!(diff.changed[0]
+            .entries
+            .iter()
+            .all(|entry| entry.old_ev == Some(1_000) && entry.new_ev.is_none()));
+    }
+}
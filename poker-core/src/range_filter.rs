@@ -0,0 +1,298 @@
+use crate::{
+    cards::{Cards, HandRanking},
+    hand::Hand,
+    range::RangeTableWith,
+    rank::Rank,
+    result::Result,
+    suite::Suite,
+};
+
+/// What the hole cards make with the board, pairs of the board alone are not counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MadeHand {
+    HighCard,
+    /// Below the second highest board rank.
+    WeakPair,
+    /// Pairing the second highest board rank, or a pocket pair below the highest.
+    MiddlePair,
+    TopPair,
+    /// A pocket pair above the board.
+    OverPair,
+    TwoPair,
+    Trips,
+    Straight,
+    Flush,
+    FullHouse,
+    Quads,
+    StraightFlush,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum StraightDraw {
+    Gutshot,
+    /// Or a double gutshot, any draw with outs of two ranks.
+    OpenEnded,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HandStrength {
+    pub made_hand: MadeHand,
+    /// Four cards of a suit including a hole card.
+    pub flush_draw: bool,
+    /// Only outs completing the straight with a hole card count.
+    pub straight_draw: Option<StraightDraw>,
+}
+
+impl HandStrength {
+    /// The board has 3 to 5 cards, there are no draws on the river.
+    pub fn new(hand: Hand, board: Cards) -> Result<Self> {
+        validate_board(board)?;
+        if board.has(hand.high()) || board.has(hand.low()) {
+            return Err(format!("range filter: {hand} holds a card of the board").into());
+        }
+        Ok(Self::new_unchecked(hand, board))
+    }
+
+    fn new_unchecked(hand: Hand, board: Cards) -> Self {
+        let cards = board.with(hand.high()).with(hand.low());
+        let river = board.count() == 5;
+
+        Self {
+            made_hand: made_hand(hand, board, cards),
+            flush_draw: !river && flush_draw(hand, cards),
+            straight_draw: if river {
+                None
+            } else {
+                straight_draw(board, cards)
+            },
+        }
+    }
+}
+
+/// Combined with "or" by [`filter_range`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandPredicate {
+    /// The made hand or better.
+    AtLeast(MadeHand),
+    FlushDraw,
+    /// The draw or better.
+    StraightDraw(StraightDraw),
+}
+
+impl HandPredicate {
+    pub fn matches(self, strength: HandStrength) -> bool {
+        match self {
+            Self::AtLeast(made_hand) => strength.made_hand >= made_hand,
+            Self::FlushDraw => strength.flush_draw,
+            Self::StraightDraw(draw) => strength.straight_draw.is_some_and(|hand| hand >= draw),
+        }
+    }
+}
+
+/// Keeps the hands matching any of the predicates, e.g. top pair or better plus
+/// flush draws. The hands holding a card of the board are removed.
+pub fn filter_range(
+    range: &RangeTableWith<u16>,
+    board: Cards,
+    predicates: &[HandPredicate],
+) -> Result<RangeTableWith<u16>> {
+    validate_board(board)?;
+
+    let mut range = range.without_dead_cards(board);
+    for (hand, frequency) in range.iter_mut() {
+        if *frequency == 0 {
+            continue;
+        }
+        let strength = HandStrength::new_unchecked(hand, board);
+        if !predicates
+            .iter()
+            .any(|predicate| predicate.matches(strength))
+        {
+            *frequency = 0;
+        }
+    }
+    Ok(range)
+}
+
+fn validate_board(board: Cards) -> Result<()> {
+    if (3..=5).contains(&board.count()) {
+        Ok(())
+    } else {
+        Err(format!("range filter: the board {board} needs 3 to 5 cards").into())
+    }
+}
+
+fn made_hand(hand: Hand, board: Cards, cards: Cards) -> MadeHand {
+    let hole_ranks = [hand.high().rank(), hand.low().rank()];
+    let in_hole = |rank: Rank| hole_ranks.contains(&rank);
+
+    // A five card hand of the board alone is played by everyone.
+    let plays_board = board.count() == 5 && board.score_fast() == cards.score_fast();
+    match cards.score_fast().to_hand_ranking() {
+        HandRanking::StraightFlush | HandRanking::RoyalFlush if !plays_board => {
+            return MadeHand::StraightFlush;
+        }
+        HandRanking::FourOfAKind(quads) if in_hole(quads) => return MadeHand::Quads,
+        HandRanking::FullHouse { trips, pair } if in_hole(trips) || in_hole(pair) => {
+            return MadeHand::FullHouse;
+        }
+        HandRanking::Flush if !plays_board => return MadeHand::Flush,
+        HandRanking::Straight if !plays_board => return MadeHand::Straight,
+        _ => {}
+    }
+
+    let count = |rank: Rank| cards.iter().filter(|card| card.rank() == rank).count();
+    if hole_ranks.iter().any(|rank| count(*rank) >= 3) {
+        return MadeHand::Trips;
+    }
+
+    let mut board_ranks: Vec<_> = board.iter().map(|card| card.rank()).collect();
+    board_ranks.sort_by(|a, b| b.cmp(a));
+    board_ranks.dedup();
+
+    let mut pairs: Vec<_> = hole_ranks
+        .into_iter()
+        .filter(|rank| count(*rank) == 2)
+        .collect();
+    pairs.dedup();
+
+    // Trips or quads on the board leave a single board rank.
+    let second_rank = board_ranks.get(1).copied();
+    match pairs[..] {
+        [] => MadeHand::HighCard,
+        [pair] if hole_ranks[0] == hole_ranks[1] => {
+            if pair > board_ranks[0] {
+                MadeHand::OverPair
+            } else if second_rank.is_some_and(|rank| pair > rank) {
+                MadeHand::MiddlePair
+            } else {
+                MadeHand::WeakPair
+            }
+        }
+        [pair] if pair == board_ranks[0] => MadeHand::TopPair,
+        [pair] if Some(pair) == second_rank => MadeHand::MiddlePair,
+        [_] => MadeHand::WeakPair,
+        _ => MadeHand::TwoPair,
+    }
+}
+
+fn flush_draw(hand: Hand, cards: Cards) -> bool {
+    let suite_count = |suite: Suite| cards.iter().filter(|card| card.suite() == suite).count();
+    Suite::SUITES.iter().all(|suite| suite_count(*suite) < 5)
+        && [hand.high().suite(), hand.low().suite()]
+            .into_iter()
+            .any(|suite| suite_count(suite) == 4)
+}
+
+/// One bit per rank from the ace playing low to the ace playing high.
+fn rank_mask(cards: Cards) -> u16 {
+    let mut mask = 0;
+    for card in cards.iter() {
+        mask |= 1 << (card.rank().to_u16() + 1);
+        if card.rank() == Rank::Ace {
+            mask |= 1;
+        }
+    }
+    mask
+}
+
+fn with_rank(mask: u16, rank: Rank) -> u16 {
+    let ace_low = if rank == Rank::Ace { 1 } else { 0 };
+    mask | 1 << (rank.to_u16() + 1) | ace_low
+}
+
+fn has_straight(mask: u16) -> bool {
+    (0..=Rank::COUNT - 4).any(|low| mask >> low & 0b1_1111 == 0b1_1111)
+}
+
+fn straight_draw(board: Cards, cards: Cards) -> Option<StraightDraw> {
+    let mask = rank_mask(cards);
+    if has_straight(mask) {
+        return None;
+    }
+
+    let board_mask = rank_mask(board);
+    let outs = Rank::RANKS
+        .into_iter()
+        .filter(|rank| {
+            has_straight(with_rank(mask, *rank)) && !has_straight(with_rank(board_mask, *rank))
+        })
+        .count();
+    match outs {
+        0 => None,
+        1 => Some(StraightDraw::Gutshot),
+        _ => Some(StraightDraw::OpenEnded),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::range::{RangeTable, MAX_FREQUENCY};
+
+    use super::*;
+
+    #[test]
+    fn test_hand_strength_and_filter() {
+        unsafe {
+            crate::init::init();
+        }
+
+        let board = Cards::from_str("Ah9h4c").unwrap();
+        let strength = |hand: &str| HandStrength::new(hand.parse().unwrap(), board).unwrap();
+
+        assert_eq!(strength("AsKd").made_hand, MadeHand::TopPair);
+        assert_eq!(strength("9s8s").made_hand, MadeHand::MiddlePair);
+        assert_eq!(strength("KsKd").made_hand, MadeHand::MiddlePair);
+        assert_eq!(strength("3s3d").made_hand, MadeHand::WeakPair);
+        assert_eq!(strength("Ad9s").made_hand, MadeHand::TwoPair);
+        assert_eq!(strength("4s4d").made_hand, MadeHand::Trips);
+        assert_eq!(strength("KhQh").made_hand, MadeHand::HighCard);
+        assert!(strength("KhQh").flush_draw);
+        assert_eq!(strength("5h3h").straight_draw, Some(StraightDraw::Gutshot));
+        let open_ended =
+            HandStrength::new("8c7h".parse().unwrap(), Cards::from_str("Ts9d2c").unwrap()).unwrap();
+        assert_eq!(open_ended.straight_draw, Some(StraightDraw::OpenEnded));
+        assert!(HandStrength::new("AhKd".parse().unwrap(), board).is_err());
+
+        let river = Cards::from_str("7s7d7hKcKd").unwrap();
+        let strength = HandStrength::new("AsQs".parse().unwrap(), river).unwrap();
+        assert_eq!(strength.made_hand, MadeHand::HighCard);
+
+        let trips = Cards::from_str("9s9d9c").unwrap();
+        let strength = |hand: &str| HandStrength::new(hand.parse().unwrap(), trips).unwrap();
+        assert_eq!(strength("AsKd").made_hand, MadeHand::HighCard);
+        assert_eq!(strength("4s4d").made_hand, MadeHand::FullHouse);
+        assert_eq!(strength("9h4s").made_hand, MadeHand::Quads);
+
+        // Only one rank on the board.
+        let quads = Cards::from_str("9s9d9c9h").unwrap();
+        let strength = |hand: &str| HandStrength::new(hand.parse().unwrap(), quads).unwrap();
+        assert_eq!(strength("4s4d").made_hand, MadeHand::WeakPair);
+        assert_eq!(strength("AsAd").made_hand, MadeHand::OverPair);
+        assert_eq!(strength("AsKd").made_hand, MadeHand::HighCard);
+
+        let full = RangeTable::FULL.to_frequencies(MAX_FREQUENCY);
+        let range = filter_range(
+            &full,
+            board,
+            &[
+                HandPredicate::AtLeast(MadeHand::TopPair),
+                HandPredicate::FlushDraw,
+            ],
+        )
+        .unwrap();
+        let has = |hand: &str| range[hand.parse().unwrap()] > 0;
+        assert!(has("AsKd") && has("KhQh") && has("4s4d"));
+        assert!(!has("9s8s") && !has("AhKd"));
+
+        let range =
+            filter_range(&full, quads, &[HandPredicate::AtLeast(MadeHand::WeakPair)]).unwrap();
+        let has = |hand: &str| range[hand.parse().unwrap()] > 0;
+        assert!(has("4s4d") && !has("AsKd"));
+
+        assert_eq!(full.effective_combos(board), 1176.0);
+        let dead = board.with("Kd".parse().unwrap());
+        let entry = "AKo".parse::<crate::range::RangeEntry>().unwrap();
+        assert_eq!(entry.live_combo_count(dead), 7);
+    }
+}
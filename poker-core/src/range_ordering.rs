@@ -0,0 +1,231 @@
+use std::{
+    fmt,
+    sync::{Arc, OnceLock},
+};
+
+use crate::{
+    cards::Cards,
+    equity::EquityTable,
+    hand::Hand,
+    range::{PreFlopRangeTable, RangeEntry, RangeTable},
+    rank::Rank,
+    result::Result,
+};
+
+/// Orders the 169 range entries from the strongest, e.g. for "the top 20% of hands".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RangeOrdering {
+    /// All-in pre flop equity against 1 to 8 random hands.
+    Equity { opponents: u8 },
+    /// The Sklansky-Malmuth groups, ordered by the Chen formula within a group.
+    Sklansky,
+    /// The Chen formula without rounding.
+    Chen,
+}
+
+impl Default for RangeOrdering {
+    fn default() -> Self {
+        Self::Equity { opponents: 1 }
+    }
+}
+
+impl fmt::Display for RangeOrdering {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Equity { opponents: 1 } => write!(f, "Equity vs 1 hand"),
+            Self::Equity { opponents } => write!(f, "Equity vs {opponents} hands"),
+            Self::Sklansky => write!(f, "Sklansky"),
+            Self::Chen => write!(f, "Chen"),
+        }
+    }
+}
+
+type CachedEntries = OnceLock<std::result::Result<Arc<[RangeEntry]>, String>>;
+
+/// One cell per ordering, the equity orderings by opponent count first,
+/// so computing one ordering does not block the others.
+static CACHE: [CachedEntries; 10] = [const { OnceLock::new() }; 10];
+
+/// About 6000 showdowns per entry, within about a percent of the exact equity.
+const EQUITY_ROUNDS: u64 = 1_000_000;
+/// Keeps the equity orderings the same between runs.
+const EQUITY_SEED: u64 = 169;
+
+impl RangeOrdering {
+    /// Computed once and cached, the equity orderings take a few seconds.
+    pub fn entries(self) -> Result<Arc<[RangeEntry]>> {
+        let entries = self.cache()?.get_or_init(|| {
+            let entries = match self {
+                Self::Equity { opponents } => equity_ordering(opponents, EQUITY_ROUNDS),
+                Self::Sklansky => {
+                    let mut entries = chen_ordering();
+                    entries.sort_by_cached_key(|entry| sklansky_group(*entry));
+                    Ok(entries)
+                }
+                Self::Chen => Ok(chen_ordering()),
+            };
+            entries.map(Arc::from).map_err(|err| err.to_string())
+        });
+        Ok(entries.clone()?)
+    }
+
+    /// Only returns the entries if they were already computed, never blocks.
+    pub fn cached_entries(self) -> Option<Result<Arc<[RangeEntry]>>> {
+        let entries = self.cache().ok()?.get()?;
+        Some(entries.clone().map_err(Into::into))
+    }
+
+    fn cache(self) -> Result<&'static CachedEntries> {
+        let index = match self {
+            Self::Equity { opponents } if (1..=8).contains(&opponents) => {
+                usize::from(opponents) - 1
+            }
+            Self::Equity { opponents } => {
+                return Err(format!("range ordering: invalid opponent count {opponents}").into())
+            }
+            Self::Sklansky => 8,
+            Self::Chen => 9,
+        };
+        Ok(&CACHE[index])
+    }
+
+    /// The strongest entries with a hand count closest to `percent` (from 0 to 100) of all hands.
+    pub fn top(self, percent: f64) -> Result<PreFlopRangeTable> {
+        if !(0.0..=100.0).contains(&percent) {
+            return Err(format!("range ordering: invalid percentage {percent}").into());
+        }
+
+        let target = percent / 100.0 * Hand::COUNT as f64;
+        let mut count = 0.0;
+        let entries = self.entries()?;
+        let top = entries.iter().copied().take_while(|entry| {
+            let next = count + f64::from(entry.combo_count());
+            if next - target > target - count {
+                return false;
+            }
+            count = next;
+            true
+        });
+        Ok(top.collect())
+    }
+}
+
+/// From 1 to 8, 9 for the entries in no group.
+pub fn sklansky_group(entry: RangeEntry) -> u8 {
+    const GROUPS: [&str; 8] = [
+        "AA,KK,QQ,JJ,AKs",
+        "TT,AQs,AJs,KQs,AKo",
+        "99,JTs,QJs,KJs,ATs,AQo",
+        "T9s,KQo,88,QTs,98s,J9s,AJo,KTs",
+        "77,87s,Q9s,T8s,KJo,QJo,JTo,76s,97s,A9s-A2s,65s",
+        "66,ATo,55,86s,KTo,QTo,54s,K9s,J8s,75s",
+        "44,J9o,64s,T9o,53s,33,98o,43s,22,K8s-K2s,T7s,Q8s",
+        "87o,A9o,Q9o,76o,42s,32s,96s,85s,J8o,J7s,65o,54o,74s,K9o,T8o",
+    ];
+
+    let hand = entry.iter_hands().next().unwrap();
+    GROUPS
+        .iter()
+        .position(|group| RangeTable::parse(group).unwrap().has_hand(hand))
+        .map_or(9, |index| index as u8 + 1)
+}
+
+/// Ties keep the order of the entries in the grid.
+pub fn chen_ordering() -> Vec<RangeEntry> {
+    let points = |rank: Rank| match rank {
+        Rank::Ace => 10.0,
+        Rank::King => 8.0,
+        Rank::Queen => 7.0,
+        Rank::Jack => 6.0,
+        _ => f64::from(rank.to_u8() + 2) / 2.0,
+    };
+
+    let score = |entry: RangeEntry| {
+        let hand = entry.iter_hands().next().unwrap();
+        let (high, low) = (hand.high().rank(), hand.low().rank());
+        if entry.pair() {
+            return (points(high) * 2.0).max(5.0);
+        }
+
+        let gap = high.to_u8() - low.to_u8() - 1;
+        let mut score = points(high)
+            - f64::from(match gap {
+                0 => 0,
+                1 => 1,
+                2 => 2,
+                3 => 4,
+                _ => 5,
+            });
+        if entry.suited() {
+            score += 2.0;
+        }
+        if gap <= 1 && high < Rank::Queen {
+            score += 1.0;
+        }
+        score
+    };
+
+    let mut entries: Vec<_> = PreFlopRangeTable::entries().collect();
+    entries.sort_by(|a, b| score(*b).total_cmp(&score(*a)));
+    entries
+}
+
+fn equity_ordering(opponents: u8, rounds: u64) -> Result<Vec<RangeEntry>> {
+    // All hands of an entry have the same equity before the flop,
+    // so one hand per entry is enough.
+    let hand = |entry: RangeEntry| entry.iter_hands().next().unwrap();
+    let hero: RangeTable = PreFlopRangeTable::entries().map(hand).collect();
+    let mut ranges = vec![Box::new(hero)];
+    ranges.resize(usize::from(opponents) + 1, Box::new(RangeTable::FULL));
+
+    let tables = EquityTable::simulate_seeded(Cards::EMPTY, &ranges, rounds, EQUITY_SEED)
+        .ok_or("range ordering: equity simulation failed")?;
+    let equity = |entry: RangeEntry| tables[0].equity_percent(hand(entry));
+
+    let mut entries: Vec<_> = PreFlopRangeTable::entries()
+        .map(|entry| (entry, equity(entry)))
+        .collect();
+    entries.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    Ok(entries.into_iter().map(|(entry, _)| entry).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_orderings() {
+        unsafe {
+            crate::init::init();
+        }
+
+        let entry = |entry: &str| entry.parse::<RangeEntry>().unwrap();
+
+        let sklansky = RangeOrdering::Sklansky.entries().unwrap();
+        assert_eq!(sklansky.len(), PreFlopRangeTable::COUNT);
+        assert_eq!(sklansky_group(entry("AKs")), 1);
+        assert_eq!(sklansky_group(entry("K2s")), 7);
+        assert_eq!(sklansky_group(entry("72o")), 9);
+        assert_eq!(sklansky[0], entry("AA"));
+
+        // With fewer rounds than the cached ordering, it is too slow in debug builds.
+        let equity = equity_ordering(1, 100_000).unwrap();
+        assert_eq!(equity[0], entry("AA"));
+        assert!(equity[PreFlopRangeTable::COUNT - 5..].contains(&entry("32o")));
+        assert!(RangeOrdering::Equity { opponents: 9 }.entries().is_err());
+        // The cache is shared between the tests, no other test computes this ordering.
+        assert!(RangeOrdering::Equity { opponents: 8 }
+            .cached_entries()
+            .is_none());
+
+        let chen = RangeOrdering::Chen.entries().unwrap();
+        assert_eq!(&chen[..3], [entry("AA"), entry("KK"), entry("QQ")]);
+        assert!(RangeOrdering::Chen.cached_entries().is_some());
+
+        let top = RangeOrdering::Chen.top(10.0).unwrap();
+        assert!((top.percent() - 10.0).abs() < 0.5, "{}", top.percent());
+        assert!(top.contains_entry(entry("AKs")) && !top.contains_entry(entry("72o")));
+        assert_eq!(RangeOrdering::Chen.top(100.0).unwrap().percent(), 100.0);
+        assert!(RangeOrdering::Chen.top(101.0).is_err());
+    }
+}
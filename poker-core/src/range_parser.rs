@@ -0,0 +1,520 @@
+use crate::{
+    card::Card,
+    hand::Hand,
+    range::{RangeEntry, RangeTableWith, MAX_FREQUENCY},
+    range_ordering::RangeOrdering,
+    rank::Rank,
+    result::Result,
+    suite::Suite,
+};
+
+/// Parses ranges into a frequency per hand, a superset of the syntax written
+/// by the `Display` of `RangeTable`:
+///
+/// - `AA`, `AKs`, `AKo`, `AK` (suited and offsuit) and exact hands like `AhKd`
+/// - `22+` and `A2s+`, `KTo+`, `KT+` up to the highest kicker
+/// - `99-55` and `KTs-K6s` with both ends included
+/// - `X` for any rank and lower case suits, e.g. `KXs`, `AsX` or `XhXh`
+/// - `top 15%` of the hands by the ordering of the parser, equity against a
+///   random hand by default, and `full`
+/// - `[50]AKo` or `[25.5](AK,QQ)`: weights in percent
+/// - `a,b` union, `a!b` difference and `a&b` intersection (binding strongest),
+///   grouped with parentheses
+///
+/// Hands in both operands keep the higher weight in a union and the lower one
+/// in an intersection, a difference subtracts the weights.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RangeParser {
+    ordering: RangeOrdering,
+}
+
+impl RangeParser {
+    /// The ordering of `top N%`.
+    pub fn with_ordering(ordering: RangeOrdering) -> Self {
+        Self { ordering }
+    }
+
+    pub fn parse(&self, range_str: &str) -> Result<RangeTableWith<u16>> {
+        let mut cursor = Cursor {
+            input: range_str.as_bytes(),
+            position: 0,
+            ordering: self.ordering,
+        };
+
+        cursor.parse_all().map_err(|err| {
+            format!(
+                "invalid range '{}': {} at position {}",
+                range_str, err.message, err.position
+            )
+            .into()
+        })
+    }
+}
+
+struct SyntaxError {
+    position: usize,
+    message: String,
+}
+
+type SyntaxResult<T> = std::result::Result<T, SyntaxError>;
+
+#[derive(Clone, Copy)]
+struct CardPattern {
+    rank: Option<Rank>,
+    suite: Option<Suite>,
+}
+
+impl CardPattern {
+    fn matches(self, card: Card) -> bool {
+        self.rank.is_none_or(|rank| card.rank() == rank)
+            && self.suite.is_none_or(|suite| card.suite() == suite)
+    }
+}
+
+struct Cursor<'a> {
+    input: &'a [u8],
+    position: usize,
+    ordering: RangeOrdering,
+}
+
+impl Cursor<'_> {
+    fn parse_all(&mut self) -> SyntaxResult<RangeTableWith<u16>> {
+        self.skip_whitespace();
+        if self.peek().is_none() {
+            return Ok(RangeTableWith::default());
+        }
+
+        let range = self.parse_union()?;
+        self.skip_whitespace();
+        match self.peek() {
+            None => Ok(range),
+            Some(ch) => Err(self.error(format!("unexpected '{}'", char::from(ch)))),
+        }
+    }
+
+    fn parse_union(&mut self) -> SyntaxResult<RangeTableWith<u16>> {
+        let mut range = self.parse_difference()?;
+        while self.eat(b',') {
+            let other = self.parse_difference()?;
+            combine(&mut range, &other, u16::max);
+        }
+        Ok(range)
+    }
+
+    fn parse_difference(&mut self) -> SyntaxResult<RangeTableWith<u16>> {
+        let mut range = self.parse_intersection()?;
+        while self.eat(b'!') {
+            let other = self.parse_intersection()?;
+            combine(&mut range, &other, u16::saturating_sub);
+        }
+        Ok(range)
+    }
+
+    fn parse_intersection(&mut self) -> SyntaxResult<RangeTableWith<u16>> {
+        let mut range = self.parse_primary()?;
+        while self.eat(b'&') {
+            let other = self.parse_primary()?;
+            combine(&mut range, &other, u16::min);
+        }
+        Ok(range)
+    }
+
+    fn parse_primary(&mut self) -> SyntaxResult<RangeTableWith<u16>> {
+        if self.eat(b'(') {
+            let range = self.parse_union()?;
+            self.expect(b')')?;
+            return Ok(range);
+        }
+
+        if self.eat(b'[') {
+            self.skip_whitespace();
+            let weight = self.parse_percent()?;
+            self.expect(b']')?;
+            let mut range = self.parse_primary()?;
+            let weight = (weight / 100.0 * f64::from(MAX_FREQUENCY)).round() as u32;
+            for (_, frequency) in range.iter_mut() {
+                *frequency = (u32::from(*frequency) * weight / u32::from(MAX_FREQUENCY)) as u16;
+            }
+            return Ok(range);
+        }
+
+        self.skip_whitespace();
+        if self.input[self.position..].starts_with(b"top") {
+            self.position += 3;
+            self.skip_whitespace();
+            let start = self.position;
+            let percent = self.parse_percent()?;
+            self.expect(b'%')?;
+            let top = self.ordering.top(percent).map_err(|err| SyntaxError {
+                position: start,
+                message: err.to_string(),
+            })?;
+            let mut range = RangeTableWith::default();
+            top.for_each_hand(|hand| range[hand] = MAX_FREQUENCY);
+            return Ok(range);
+        }
+
+        self.parse_hands()
+    }
+
+    /// A number from 0 to 100.
+    fn parse_percent(&mut self) -> SyntaxResult<f64> {
+        let start = self.position;
+        let len = self.input[start..]
+            .iter()
+            .take_while(|ch| ch.is_ascii_digit() || **ch == b'.')
+            .count();
+        self.position += len;
+
+        let raw = std::str::from_utf8(&self.input[start..self.position]).unwrap();
+        match raw.parse::<f64>() {
+            Ok(percent) if (0.0..=100.0).contains(&percent) => Ok(percent),
+            _ => Err(SyntaxError {
+                position: start,
+                message: "expected a percentage from 0 to 100".to_string(),
+            }),
+        }
+    }
+
+    fn parse_hands(&mut self) -> SyntaxResult<RangeTableWith<u16>> {
+        let start = self.position;
+        let len = self.input[start..]
+            .iter()
+            .take_while(|ch| ch.is_ascii_alphanumeric() || **ch == b'+' || **ch == b'-')
+            .count();
+        self.position += len;
+        let word = &self.input[start..self.position];
+
+        match word {
+            [] => {
+                self.position = start;
+                Err(match self.peek() {
+                    None => self.error("expected hands, found the end".to_string()),
+                    Some(ch) => self.error(format!("expected hands, found '{}'", char::from(ch))),
+                })
+            }
+            b"full" => Ok(full()),
+            _ => {
+                if let Some(dash) = word.iter().position(|ch| *ch == b'-') {
+                    let (from, to) = (&word[..dash], &word[dash + 1..]);
+                    parse_dash(from, start, to, start + dash + 1)
+                } else if let Some(simple) = word.strip_suffix(b"+") {
+                    parse_plus(simple, start)
+                } else {
+                    parse_pattern(word, start)
+                }
+            }
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|ch| ch.is_ascii_whitespace()) {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.position).copied()
+    }
+
+    fn eat(&mut self, expected: u8) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(expected) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: u8) -> SyntaxResult<()> {
+        if self.eat(expected) {
+            return Ok(());
+        }
+        let found = match self.peek() {
+            Some(ch) => format!("'{}'", char::from(ch)),
+            None => "the end".to_string(),
+        };
+        Err(self.error(format!(
+            "expected '{}', found {found}",
+            char::from(expected)
+        )))
+    }
+
+    fn error(&self, message: String) -> SyntaxError {
+        SyntaxError {
+            position: self.position,
+            message,
+        }
+    }
+}
+
+fn combine(range: &mut RangeTableWith<u16>, other: &RangeTableWith<u16>, op: fn(u16, u16) -> u16) {
+    for (hand, frequency) in range.iter_mut() {
+        *frequency = op(*frequency, other[hand]);
+    }
+}
+
+fn full() -> RangeTableWith<u16> {
+    let mut range = RangeTableWith::default();
+    for (_, frequency) in range.iter_mut() {
+        *frequency = MAX_FREQUENCY;
+    }
+    range
+}
+
+fn from_entries(entries: impl IntoIterator<Item = RangeEntry>) -> RangeTableWith<u16> {
+    let mut range = RangeTableWith::default();
+    for hand in entries.into_iter().flat_map(RangeEntry::iter_hands) {
+        range[hand] = MAX_FREQUENCY;
+    }
+    range
+}
+
+fn parse_rank(ch: Option<u8>, position: usize) -> SyntaxResult<Option<Rank>> {
+    match ch {
+        Some(b'X') => Ok(None),
+        Some(ch) => Rank::from_ascii(ch).map(Some).map_err(|err| SyntaxError {
+            position,
+            message: err.to_string(),
+        }),
+        None => Err(SyntaxError {
+            position,
+            message: "expected a rank, found the end".to_string(),
+        }),
+    }
+}
+
+/// `AK`, `AKs` or `AKo` without wildcards, returns the ranks in descending
+/// order and whether the hands are suited if given.
+fn parse_simple(word: &[u8], start: usize) -> SyntaxResult<(Rank, Rank, Option<bool>)> {
+    let error = |offset: usize, message: &str| SyntaxError {
+        position: start + offset,
+        message: message.to_string(),
+    };
+
+    let suited = match word {
+        [_, _] => None,
+        [_, _, b's'] => Some(true),
+        [_, _, b'o'] => Some(false),
+        _ => return Err(error(0, "expected a pair or two ranks with 's' or 'o'")),
+    };
+    let (Some(high), Some(low)) = (
+        parse_rank(Some(word[0]), start)?,
+        parse_rank(Some(word[1]), start + 1)?,
+    ) else {
+        return Err(error(0, "wildcards cannot be used in '+' and '-' ranges"));
+    };
+
+    if low > high {
+        return Err(error(1, "ranks must be in descending order"));
+    }
+    if low == high && suited.is_some() {
+        return Err(error(2, "pairs cannot be suited or offsuit"));
+    }
+    Ok((high, low, suited))
+}
+
+fn unpaired(high: Rank, low: Rank, suited: Option<bool>) -> impl Iterator<Item = RangeEntry> {
+    let suited = match suited {
+        Some(suited) => vec![suited],
+        None => vec![true, false],
+    };
+    suited
+        .into_iter()
+        .filter_map(move |suited| RangeEntry::new(high, low, suited))
+}
+
+fn parse_plus(word: &[u8], start: usize) -> SyntaxResult<RangeTableWith<u16>> {
+    let (high, low, suited) = parse_simple(word, start)?;
+    if high == low {
+        return Ok(from_entries(
+            Rank::range(low, Rank::Ace).map(RangeEntry::paired),
+        ));
+    }
+
+    let kickers = Rank::range(low, high.predecessor().unwrap());
+    Ok(from_entries(
+        kickers.flat_map(|kicker| unpaired(high, kicker, suited)),
+    ))
+}
+
+fn parse_dash(
+    from: &[u8],
+    from_start: usize,
+    to: &[u8],
+    to_start: usize,
+) -> SyntaxResult<RangeTableWith<u16>> {
+    let (from_high, from_low, from_suited) = parse_simple(from, from_start)?;
+    let (to_high, to_low, to_suited) = parse_simple(to, to_start)?;
+    let error = |message: &str| SyntaxError {
+        position: to_start,
+        message: message.to_string(),
+    };
+
+    if from_high == from_low {
+        if to_high != to_low {
+            return Err(error("a range of pairs must end with a pair"));
+        }
+        let (low, high) = (from_low.min(to_low), from_low.max(to_low));
+        return Ok(from_entries(Rank::range(low, high).map(RangeEntry::paired)));
+    }
+
+    if to_high != from_high || to_suited != from_suited || to_high == to_low {
+        return Err(error(
+            "both ends of a range must have the same high rank and suitedness",
+        ));
+    }
+    let (low, high) = (from_low.min(to_low), from_low.max(to_low));
+    Ok(from_entries(Rank::range(low, high).flat_map(|kicker| {
+        unpaired(from_high, kicker, from_suited)
+    })))
+}
+
+/// Two cards of a rank or `X` with an optional suit each, e.g. `AsX`, or two
+/// ranks followed by an optional 's' or 'o', e.g. `KXs`.
+fn parse_pattern(word: &[u8], start: usize) -> SyntaxResult<RangeTableWith<u16>> {
+    let is_suite = |ch: Option<&u8>| matches!(ch, Some(b'c' | b'd' | b'h' | b's'));
+    let is_rank =
+        |ch: Option<&u8>| ch.is_some_and(|ch| *ch == b'X' || Rank::from_ascii(*ch).is_ok());
+
+    let mut offset = 0;
+    let first_rank = parse_rank(word.first().copied(), start)?;
+    offset += 1;
+
+    let mut first_suite = None;
+    if is_suite(word.get(offset)) && is_rank(word.get(offset + 1)) {
+        first_suite = Some(Suite::from_ascii(word[offset]).unwrap());
+        offset += 1;
+    }
+
+    let second_rank = parse_rank(word.get(offset).copied(), start + offset)?;
+    offset += 1;
+
+    let mut second_suite = None;
+    let mut suited = None;
+    if first_suite.is_some() {
+        if is_suite(word.get(offset)) {
+            second_suite = Some(Suite::from_ascii(word[offset]).unwrap());
+            offset += 1;
+        }
+    } else {
+        match word.get(offset) {
+            Some(b's') => suited = Some(true),
+            Some(b'o') => suited = Some(false),
+            _ => {}
+        }
+        if suited.is_some() {
+            offset += 1;
+        }
+    }
+
+    if let Some(ch) = word.get(offset) {
+        return Err(SyntaxError {
+            position: start + offset,
+            message: format!("unexpected '{}'", char::from(*ch)),
+        });
+    }
+
+    if first_suite.is_none() {
+        if let (Some(high), Some(low)) = (first_rank, second_rank) {
+            if low > high {
+                return Err(SyntaxError {
+                    position: start + 1,
+                    message: "ranks must be in descending order".to_string(),
+                });
+            }
+        }
+    }
+
+    let first = CardPattern {
+        rank: first_rank,
+        suite: first_suite,
+    };
+    let second = CardPattern {
+        rank: second_rank,
+        suite: second_suite,
+    };
+
+    let mut range = RangeTableWith::default();
+    let mut matched = false;
+    for hand in Hand::all() {
+        let [a, b] = hand.to_card_array();
+        let cards_match =
+            (first.matches(a) && second.matches(b)) || (first.matches(b) && second.matches(a));
+        if cards_match && suited.is_none_or(|suited| hand.suited() == suited) {
+            range[hand] = MAX_FREQUENCY;
+            matched = true;
+        }
+    }
+
+    if !matched {
+        return Err(SyntaxError {
+            position: start,
+            message: format!("'{}' matches no hand", String::from_utf8_lossy(word)),
+        });
+    }
+    Ok(range)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::range::RangeTable;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_range_syntax() {
+        unsafe {
+            crate::init::init();
+        }
+
+        let count = |range_str: &str| RangeTable::parse(range_str).unwrap().count();
+        let same = |a: &str, b: &str| {
+            assert_eq!(
+                RangeTable::parse(a).unwrap(),
+                RangeTable::parse(b).unwrap(),
+                "{a} != {b}"
+            );
+        };
+
+        same("99-55", "55,66,77,88,99");
+        same("KTs-K6s", "K6s,K7s,K8s,K9s,KTs");
+        same("AK", "AKs,AKo");
+        same("KXs", "K2s+,AKs");
+        same("QQ+,AK!AhKh", "QQ+,AKo,AKs ! AhKh");
+        same("(22+,AK)&(AXs)", "AKs");
+        assert_eq!(count("AsX"), 51);
+        assert_eq!(count("XhXh"), 78);
+        assert_eq!(count("XX"), 1326);
+
+        // The default equity ordering takes too long to simulate in debug builds.
+        let chen = RangeParser::with_ordering(RangeOrdering::Chen);
+        let top = |range_str: &str| {
+            RangeTable::from_frequencies_not_zero(&chen.parse(range_str).unwrap())
+        };
+        assert_eq!(top("top 100%"), RangeTable::FULL);
+        assert_eq!(top("top 0%"), RangeTable::EMPTY);
+        assert!((top("top 15%").count() as f64 / 1326.0 - 0.15).abs() < 0.01);
+
+        let weighted = RangeTable::parse_weighted("[50]AKo,AKs,[25](QQ,JJ)").unwrap();
+        let hand = |hand: &str| weighted[hand.parse().unwrap()];
+        assert_eq!(hand("AhKd"), MAX_FREQUENCY / 2);
+        assert_eq!(hand("AhKh"), MAX_FREQUENCY);
+        assert_eq!(hand("QhQd"), MAX_FREQUENCY / 4);
+        assert_eq!(hand("2h2d"), 0);
+
+        for (range_str, position) in [
+            ("QQ+,AKx", 6),
+            ("QQ+, ", 5),
+            ("KAs", 1),
+            ("(AK", 3),
+            ("KTs-QTs", 4),
+            ("[150]AK", 1),
+            ("top 5", 5),
+        ] {
+            let err = RangeTable::parse(range_str).unwrap_err().to_string();
+            assert!(err.ends_with(&format!("at position {position}")), "{err}");
+        }
+    }
+}
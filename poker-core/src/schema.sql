@@ -61,4 +61,20 @@ CREATE TABLE IF NOT EXISTS hands_players(
     FOREIGN KEY(hand_id) REFERENCES hands(id)
 ) WITHOUT ROWID, STRICT;
 
+CREATE TABLE IF NOT EXISTS players_notes(
+    player_name TEXT NOT NULL PRIMARY KEY,
+    note TEXT NOT NULL,
+    color_label TEXT -- 'red', 'orange', 'yellow', 'green', 'blue', 'purple'
+) STRICT;
+
+-- Bookmarks are stored as the tag 'bookmark'.
+CREATE TABLE IF NOT EXISTS hands_tags(
+    hand_id INTEGER NOT NULL,
+    tag TEXT NOT NULL,
+
+    PRIMARY KEY (hand_id, tag),
+    -- Deferred, so derived hand rows can be rebuilt inside a transaction.
+    FOREIGN KEY(hand_id) REFERENCES hands(id) DEFERRABLE INITIALLY DEFERRED
+) WITHOUT ROWID, STRICT;
+
 COMMIT;
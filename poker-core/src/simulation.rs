@@ -0,0 +1,341 @@
+use std::thread;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    ai::PlayerActionGenerator,
+    card::Card,
+    cards::Cards,
+    deck::Deck,
+    game::{Game, Player, State, Street},
+    hand::Hand,
+    result::Result,
+};
+
+/// Creates a fresh generator for every hand.
+pub type GeneratorFactory = Box<dyn Fn() -> Box<dyn PlayerActionGenerator> + Send + Sync>;
+
+pub struct Bot {
+    pub name: String,
+    pub factory: GeneratorFactory,
+}
+
+impl Bot {
+    pub fn new(
+        name: impl Into<String>,
+        factory: impl Fn() -> Box<dyn PlayerActionGenerator> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            factory: Box::new(factory),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SimulationConfig {
+    /// Rounded up to full rotations with duplicate dealing.
+    pub hands: u64,
+    pub small_blind: u32,
+    pub big_blind: u32,
+    /// Stacks are reset before every hand.
+    pub starting_stack: u32,
+    /// Deals every hand once per seat with the bots rotated, so every bot
+    /// plays the same cards from the same position.
+    pub duplicate: bool,
+    /// Number of tables played in parallel.
+    pub tables: usize,
+    pub seed: Option<u64>,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            hands: 100_000,
+            small_blind: 50,
+            big_blind: 100,
+            starting_stack: 10_000,
+            duplicate: false,
+            tables: 1,
+            seed: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BotResult {
+    pub name: String,
+    pub hands: u64,
+    /// Actions which could not be applied, the bot checks or folds instead.
+    pub invalid_actions: u64,
+    /// In big blinds per 100 hands.
+    pub win_rate: f64,
+    /// Half width of the 95% confidence interval of the win rate.
+    pub confidence: f64,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct BotStats {
+    hands: u64,
+    invalid_actions: u64,
+    samples: u64,
+    sum: f64,
+    sum_squares: f64,
+}
+
+impl BotStats {
+    fn add_sample(&mut self, big_blinds_per_hand: f64) {
+        self.samples += 1;
+        self.sum += big_blinds_per_hand;
+        self.sum_squares += big_blinds_per_hand * big_blinds_per_hand;
+    }
+
+    fn merge(&mut self, other: &BotStats) {
+        self.hands += other.hands;
+        self.invalid_actions += other.invalid_actions;
+        self.samples += other.samples;
+        self.sum += other.sum;
+        self.sum_squares += other.sum_squares;
+    }
+
+    fn to_result(self, name: &str) -> BotResult {
+        let (win_rate, confidence) = if self.samples == 0 {
+            (0.0, f64::INFINITY)
+        } else {
+            let samples = self.samples as f64;
+            let mean = self.sum / samples;
+            let confidence = if self.samples < 2 {
+                f64::INFINITY
+            } else {
+                let variance = (self.sum_squares - self.sum * mean) / (samples - 1.0);
+                1.96 * (variance.max(0.0) / samples).sqrt() * 100.0
+            };
+            (mean * 100.0, confidence)
+        };
+
+        BotResult {
+            name: name.to_owned(),
+            hands: self.hands,
+            invalid_actions: self.invalid_actions,
+            win_rate,
+            confidence,
+        }
+    }
+}
+
+struct Deal {
+    hands: Vec<Hand>,
+    board: [Card; 5],
+}
+
+impl Deal {
+    fn new(rng: &mut impl Rng, player_count: usize) -> Self {
+        let mut deck = Deck::from_cards(rng, Cards::EMPTY);
+        let hands = (0..player_count).map(|_| deck.hand(rng).unwrap()).collect();
+        let board = [(); 5].map(|_| deck.draw(rng).unwrap());
+        Self { hands, board }
+    }
+}
+
+/// Seats the bots at a table and plays hands with a rotating button.
+pub fn simulate(bots: &[Bot], config: &SimulationConfig) -> Result<Vec<BotResult>> {
+    if bots.len() < Game::MIN_PLAYERS || bots.len() > Game::MAX_PLAYERS {
+        return Err(format!(
+            "simulation: need {} to {} bots",
+            Game::MIN_PLAYERS,
+            Game::MAX_PLAYERS
+        )
+        .into());
+    }
+    if config.tables == 0 {
+        return Err("simulation: need at least one table".into());
+    }
+    if config.big_blind == 0 || config.small_blind > config.big_blind {
+        return Err("simulation: invalid blinds".into());
+    }
+
+    let seed = config.seed.unwrap_or_else(|| rand::thread_rng().gen());
+    let tables = u64::try_from(config.tables).unwrap();
+
+    let table_results = thread::scope(|scope| {
+        let handles: Vec<_> = (0..tables)
+            .map(|table| {
+                let hands = config.hands / tables + u64::from(table < config.hands % tables);
+                let seed = seed.wrapping_add(table);
+                scope.spawn(move || simulate_table(bots, config, hands, seed))
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect::<Result<Vec<_>>>()
+    })?;
+
+    let mut stats = vec![BotStats::default(); bots.len()];
+    for table_stats in table_results {
+        for (stats, table_stats) in stats.iter_mut().zip(&table_stats) {
+            stats.merge(table_stats);
+        }
+    }
+
+    Ok(stats
+        .into_iter()
+        .zip(bots)
+        .map(|(stats, bot)| stats.to_result(&bot.name))
+        .collect())
+}
+
+fn simulate_table(
+    bots: &[Bot],
+    config: &SimulationConfig,
+    hands: u64,
+    seed: u64,
+) -> Result<Vec<BotStats>> {
+    let player_count = bots.len();
+    let rotations = if config.duplicate { player_count } else { 1 };
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut stats = vec![BotStats::default(); player_count];
+    let mut hands_played = 0;
+    let mut deal_index = 0;
+
+    while hands_played < hands {
+        let deal = Deal::new(&mut rng, player_count);
+        let button_index = deal_index % player_count;
+        let mut big_blinds = vec![0.0; player_count];
+
+        for rotation in 0..rotations {
+            // The bot sitting in each seat.
+            let seat_bots: Vec<_> = (0..player_count)
+                .map(|seat| (seat + rotation) % player_count)
+                .collect();
+            let results = play_hand(bots, &seat_bots, &deal, button_index, config, &mut stats)?;
+
+            for (seat, result) in results.into_iter().enumerate() {
+                let bot = seat_bots[seat];
+                stats[bot].hands += 1;
+                big_blinds[bot] += result as f64 / f64::from(config.big_blind);
+            }
+        }
+
+        // Mirrored hands are one sample, which is where the variance reduction comes from.
+        for (stats, big_blinds) in stats.iter_mut().zip(big_blinds) {
+            stats.add_sample(big_blinds / rotations as f64);
+        }
+
+        hands_played += u64::try_from(rotations).unwrap();
+        deal_index += 1;
+    }
+
+    Ok(stats)
+}
+
+/// Returns the won or lost amount per seat.
+fn play_hand(
+    bots: &[Bot],
+    seat_bots: &[usize],
+    deal: &Deal,
+    button_index: usize,
+    config: &SimulationConfig,
+    stats: &mut [BotStats],
+) -> Result<Vec<i64>> {
+    let players: Vec<_> = deal
+        .hands
+        .iter()
+        .map(|hand| Player {
+            hand: Some(*hand),
+            ..Player::with_starting_stack(config.starting_stack)
+        })
+        .collect();
+    let mut game = Game::new(&players, button_index, config.small_blind, config.big_blind)?;
+    game.post_small_and_big_blind()?;
+
+    let mut generators: Vec<_> = seat_bots.iter().map(|bot| (bots[*bot].factory)()).collect();
+    let mut log = String::new();
+
+    loop {
+        match game.state() {
+            State::Post => game.post_small_and_big_blind()?,
+            State::Player(player) => {
+                let bot = &bots[seat_bots[player]];
+                log.clear();
+                let (action, _, _) = generators[player]
+                    .update_hero(&game, &mut log)
+                    .map_err(|err| format!("simulation: {}: {err}", bot.name))?;
+
+                if action.apply_to_game(&mut game).is_err() {
+                    stats[seat_bots[player]].invalid_actions += 1;
+                    if game.can_check() {
+                        game.check()?;
+                    } else {
+                        game.fold()?;
+                    }
+                }
+
+                for villain in 0..seat_bots.len() {
+                    if villain == player || game.folded(villain) {
+                        continue;
+                    }
+
+                    log.clear();
+                    generators[villain]
+                        .update_villain(&game, &mut log)
+                        .map_err(|err| {
+                            format!("simulation: {}: {err}", bots[seat_bots[villain]].name)
+                        })?;
+                }
+            }
+            State::Street(Street::PreFlop) => unreachable!(),
+            State::Street(Street::Flop) => {
+                game.flop([deal.board[0], deal.board[1], deal.board[2]])?
+            }
+            State::Street(Street::Turn) => game.turn(deal.board[3])?,
+            State::Street(Street::River) => game.river(deal.board[4])?,
+            State::UncalledBet { .. } => game.uncalled_bet()?,
+            State::ShowOrMuck(_) => game.show_hand()?,
+            State::ShowdownOrNextRunout => game.showdown_simple()?,
+            State::End => break,
+        }
+    }
+
+    Ok(game
+        .current_stacks()
+        .iter()
+        .zip(game.starting_stacks())
+        .map(|(stack, starting_stack)| i64::from(*stack) - i64::from(*starting_stack))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ai::{AlwaysCheckCall, AlwaysFold};
+
+    use super::*;
+
+    #[test]
+    fn test_simulate_duplicate() {
+        unsafe {
+            crate::init::init();
+        }
+
+        let bots = [
+            Bot::new("check/call", || Box::new(AlwaysCheckCall)),
+            Bot::new("fold", || Box::new(AlwaysFold)),
+        ];
+        let config = SimulationConfig {
+            hands: 1_000,
+            duplicate: true,
+            tables: 2,
+            seed: Some(42),
+            ..Default::default()
+        };
+
+        let results = simulate(&bots, &config).unwrap();
+        assert_eq!(results[0].hands, 1_000);
+        assert_eq!(results[1].hands, 1_000);
+        assert!(results[0].win_rate > 0.0);
+        assert!((results[0].win_rate + results[1].win_rate).abs() < 0.0001);
+        // Folding every hand loses the same amount in every mirrored pair.
+        assert!(results[1].confidence < 0.0001);
+    }
+}
@@ -0,0 +1,240 @@
+use std::fmt::Write;
+
+use crate::{
+    game::{Action, Game},
+    hand::Hand,
+    range::{RangeActionKind, RangeConfigEntry, MAX_FREQUENCY},
+};
+
+/// Decisions picking an action below this frequency count as mistakes.
+pub const MISTAKE_FREQUENCY: f64 = 0.1;
+
+#[derive(Debug, Clone)]
+pub struct Decision {
+    pub spot: String,
+    pub hand: Hand,
+    pub action: RangeActionKind,
+    /// The recommended frequencies for the exact hand, from 0 to 1.
+    pub frequencies: Vec<(RangeActionKind, f64)>,
+    /// The frequency of the action relative to the most frequent action.
+    pub score: f64,
+}
+
+impl Decision {
+    /// The game has to be at the state before the action was applied.
+    /// Bet and raise sizes are matched to the closest recommended size.
+    pub fn grade(game: &Game, config: &RangeConfigEntry, action: Action) -> Option<Self> {
+        let player = game.current_player()?;
+        if action.player() != Some(player) {
+            return None;
+        }
+
+        let hand = game.current_hand()?;
+        let action = RangeActionKind::from_game_action(game, action).ok()?;
+
+        let frequencies: Vec<_> = config
+            .action_kinds()
+            .map(|kind| {
+                let frequency = f64::from(config.hand_frequency(kind, hand));
+                (kind, frequency / f64::from(MAX_FREQUENCY))
+            })
+            .collect();
+
+        let action = config.closest_action(action).unwrap_or(action);
+
+        let frequency = frequencies
+            .iter()
+            .find(|(kind, _)| *kind == action)
+            .map_or(0.0, |(_, frequency)| *frequency);
+        let best_frequency = frequencies
+            .iter()
+            .map(|(_, frequency)| *frequency)
+            .fold(0.0, f64::max);
+        let score = if best_frequency == 0.0 {
+            0.0
+        } else {
+            frequency / best_frequency
+        };
+
+        Some(Self {
+            spot: Self::spot(game, player),
+            hand,
+            action,
+            frequencies,
+            score,
+        })
+    }
+
+    /// Describes the spot by street, position and the previous actions in the street.
+    pub fn spot(game: &Game, player: usize) -> String {
+        let mut spot = game.board().street().to_string();
+
+        if let Some((position, _)) =
+            Game::position_name(game.player_count(), game.button_index(), player)
+        {
+            write!(spot, " {position}").unwrap();
+        }
+
+        let actions: String = game
+            .actions_in_street()
+            .iter()
+            .filter(|action| !matches!(action, Action::Post { .. } | Action::Straddle { .. }))
+            .filter_map(|action| action.player_char())
+            .collect();
+        if !actions.is_empty() {
+            write!(spot, " {actions}").unwrap();
+        }
+
+        spot
+    }
+
+    pub fn frequency(&self) -> f64 {
+        self.frequencies
+            .iter()
+            .find(|(kind, _)| *kind == self.action)
+            .map_or(0.0, |(_, frequency)| *frequency)
+    }
+
+    pub fn is_mistake(&self) -> bool {
+        self.frequency() < MISTAKE_FREQUENCY
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SpotStats {
+    pub spot: String,
+    pub decisions: u32,
+    pub mistakes: u32,
+    pub total_score: f64,
+}
+
+impl SpotStats {
+    pub fn average_score(&self) -> f64 {
+        if self.decisions == 0 {
+            0.0
+        } else {
+            self.total_score / f64::from(self.decisions)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TrainerSession {
+    decisions: Vec<Decision>,
+}
+
+impl TrainerSession {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, decision: Decision) {
+        self.decisions.push(decision);
+    }
+
+    pub fn clear(&mut self) {
+        self.decisions.clear();
+    }
+
+    pub fn decisions(&self) -> &[Decision] {
+        &self.decisions
+    }
+
+    pub fn last(&self) -> Option<&Decision> {
+        self.decisions.last()
+    }
+
+    pub fn total(&self) -> SpotStats {
+        let mut total = SpotStats::default();
+        for decision in &self.decisions {
+            total.decisions += 1;
+            total.mistakes += u32::from(decision.is_mistake());
+            total.total_score += decision.score;
+        }
+        total
+    }
+
+    /// Sorted by mistake count, most mistakes first.
+    pub fn spot_stats(&self) -> Vec<SpotStats> {
+        let mut stats: Vec<SpotStats> = Vec::new();
+
+        for decision in &self.decisions {
+            let index = if let Some(index) = stats.iter().position(|s| s.spot == decision.spot) {
+                index
+            } else {
+                stats.push(SpotStats {
+                    spot: decision.spot.clone(),
+                    ..Default::default()
+                });
+                stats.len() - 1
+            };
+
+            let spot_stats = &mut stats[index];
+            spot_stats.decisions += 1;
+            spot_stats.mistakes += u32::from(decision.is_mistake());
+            spot_stats.total_score += decision.score;
+        }
+
+        stats.sort_by(|a, b| {
+            b.mistakes
+                .cmp(&a.mistakes)
+                .then_with(|| a.spot.cmp(&b.spot))
+        });
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        game::GameData,
+        range::{RangeEntry, RangeTable},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_grade() {
+        unsafe {
+            crate::init::init();
+        }
+
+        let mut game = Game::from_game_data(&GameData::default()).unwrap();
+        game.post_small_and_big_blind().unwrap();
+        let player = game.current_player().unwrap();
+        let entry: RangeEntry = "AKs".parse().unwrap();
+        let hand = entry.iter_hands().next().unwrap();
+        game.set_hand(player, hand).unwrap();
+
+        let raise = RangeActionKind::Raise(2500);
+        let mut config = RangeConfigEntry::distribute_action(
+            RangeTable::FULL.to_frequencies(MAX_FREQUENCY),
+            RangeActionKind::Fold,
+        )
+        .unwrap();
+        config
+            .update_hand_action_frequency(hand, raise, 9_500)
+            .unwrap();
+
+        let mut raised = game.clone();
+        raised.raise(game.big_blind() * 3).unwrap();
+        let decision = Decision::grade(&game, &config, *raised.actions().last().unwrap()).unwrap();
+        assert_eq!(decision.action, raise);
+        assert_eq!(decision.score, 1.0);
+        assert!(!decision.is_mistake());
+
+        let mut folded = game.clone();
+        folded.fold().unwrap();
+        let decision = Decision::grade(&game, &config, *folded.actions().last().unwrap()).unwrap();
+        assert_eq!(decision.action, RangeActionKind::Fold);
+        assert!((decision.score - 0.05 / 0.95).abs() < 0.0001);
+        assert!(decision.is_mistake());
+
+        let mut session = TrainerSession::new();
+        session.add(decision);
+        let stats = session.spot_stats();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].decisions, 1);
+        assert_eq!(stats[0].mistakes, 1);
+    }
+}
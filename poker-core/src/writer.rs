@@ -0,0 +1,590 @@
+use std::{fmt::Write, mem};
+
+use crate::{
+    card::Card,
+    game::{Action, Game, State, Street},
+    result::Result,
+};
+
+// Writes PokerStars style hand histories in the GG flavor,
+// which can be read again by the GGHandHistoryParser.
+// Amounts are always formatted as cents (e.g. 150 -> $1.5).
+//
+// TODO:
+// - Other currencies / play money
+// - Summary details, e.g. "(didn't bet)" and hand rankings
+
+const DEFAULT_HAND_NAME: &str = "#0";
+const DEFAULT_TABLE_NAME: &str = "Table";
+const RUNOUT_NAMES: [&str; 4] = ["FIRST", "SECOND", "THIRD", "FOURTH"];
+
+pub fn write_games<'a>(games: impl Iterator<Item = &'a Game>) -> Result<String> {
+    let mut out = String::new();
+    for game in games {
+        write_game(game, &mut out)?;
+        out.push_str("\n\n");
+    }
+    Ok(out)
+}
+
+pub fn write_game(game: &Game, out: &mut String) -> Result<()> {
+    let mut game = game.clone();
+    game.forward();
+    if game.state() != State::End {
+        return Err("writer: game not in end state".into());
+    }
+
+    write_header(&game, out)?;
+    write_posts(&game, out)?;
+
+    writeln!(out, "*** HOLE CARDS ***")?;
+    for player in 0..game.player_count() {
+        match game.get_hand(player) {
+            Some(hand) => writeln!(
+                out,
+                "Dealt to {} [{} {}]",
+                game.player_name(player),
+                hand.high(),
+                hand.low()
+            )?,
+            None => writeln!(out, "Dealt to {} ", game.player_name(player))?,
+        }
+    }
+
+    let all_in = all_in_actions(&game);
+    let actions = game.actions();
+    let mut runout_writer = RunoutWriter::new(game.runouts().len() > 1);
+    let mut folded_in = vec![None; game.player_count()];
+    let mut street = Street::PreFlop;
+
+    let mut index = actions
+        .iter()
+        .take_while(|action| matches!(action, Action::Post { .. } | Action::Straddle { .. }))
+        .count();
+    while index < actions.len() {
+        let action = actions[index];
+        index += 1;
+
+        match action {
+            Action::Post { .. } | Action::Straddle { .. } => {
+                return Err("writer: unexpected post or straddle".into());
+            }
+            Action::Flop(_) | Action::Turn(_) | Action::River(_) => {
+                street = action.street().unwrap();
+                runout_writer.write(action, out)?;
+            }
+            Action::Shows { .. } | Action::MucksOrUnknown(_) => {
+                // The shows are written after the runout of the current board.
+                let shows_start = index - 1;
+                while actions.get(index).is_some_and(|action| {
+                    matches!(action, Action::Shows { .. } | Action::MucksOrUnknown(_))
+                }) {
+                    index += 1;
+                }
+                let shows_end = index;
+
+                while let Some(next_street) = actions.get(index).and_then(|action| action.street())
+                {
+                    if street.next() != Some(next_street) {
+                        break;
+                    }
+                    street = next_street;
+                    runout_writer.write(actions[index], out)?;
+                    index += 1;
+                }
+
+                for action in &actions[shows_start..shows_end] {
+                    write_show_or_muck(&game, *action, out)?;
+                }
+            }
+            _ => {
+                if let Action::Fold(player) = action {
+                    folded_in[usize::from(player)] = Some(street);
+                }
+                write_player_action(&game, action, all_in[index - 1], out)?;
+            }
+        }
+    }
+
+    writeln!(out, "*** SHOWDOWN ***")?;
+    let winnings = winnings(&game)?;
+    for (player, amount) in winnings.iter().copied().enumerate() {
+        if amount > 0 {
+            writeln!(
+                out,
+                "{} collected {} from pot",
+                game.player_name(player),
+                format_amount(amount)
+            )?;
+        }
+    }
+
+    write_summary(&game, &winnings, &folded_in, out)
+}
+
+fn write_header(game: &Game, out: &mut String) -> Result<()> {
+    let hand_name = game.hand_name();
+    let hand_name = hand_name
+        .as_ref()
+        .map_or(DEFAULT_HAND_NAME, |name| name.as_str());
+    let hand_name_prefix = if hand_name.starts_with('#') { "" } else { "#" };
+    let date = game.date().unwrap_or_default();
+    writeln!(
+        out,
+        "Poker Hand {hand_name_prefix}{hand_name}: Hold'em No Limit ({}/{}) - {}",
+        format_amount(game.small_blind()),
+        format_amount(game.big_blind()),
+        date.format("%Y/%m/%d %H:%M:%S"),
+    )?;
+
+    let table_name = game.table_name();
+    let table_name = table_name
+        .as_ref()
+        .map_or(DEFAULT_TABLE_NAME, |name| name.as_str());
+    writeln!(
+        out,
+        "Table '{table_name}' {}-max Seat #{} is the button",
+        game.max_players().unwrap_or(game.player_count()),
+        game.seat(game.button_index()) + 1,
+    )?;
+
+    for player in 0..game.player_count() {
+        writeln!(
+            out,
+            "Seat {}: {} ({} in chips)",
+            game.seat(player) + 1,
+            game.player_name(player),
+            format_amount(game.starting_stacks()[player]),
+        )?;
+    }
+
+    Ok(())
+}
+
+fn write_posts(game: &Game, out: &mut String) -> Result<()> {
+    let posts = game
+        .actions()
+        .iter()
+        .copied()
+        .take_while(|action| matches!(action, Action::Post { .. } | Action::Straddle { .. }));
+
+    let mut posted = [false; Game::MAX_PLAYERS];
+    for action in posts {
+        match action {
+            Action::Post {
+                player,
+                amount,
+                dead,
+            } => {
+                // The first post of the small blind player is the small blind unless it is
+                // larger, every other live post is a big blind.
+                let first_post = !mem::replace(&mut posted[usize::from(player)], true);
+                let kind = if dead {
+                    "missed"
+                } else if first_post
+                    && usize::from(player) == game.small_blind_index()
+                    && amount <= game.small_blind()
+                {
+                    "small"
+                } else {
+                    "big"
+                };
+                writeln!(
+                    out,
+                    "{}: posts {kind} blind {}",
+                    game.player_name(usize::from(player)),
+                    format_amount(amount),
+                )?;
+            }
+            Action::Straddle { player, amount } => writeln!(
+                out,
+                "{}: straddle {}",
+                game.player_name(usize::from(player)),
+                format_amount(amount),
+            )?,
+            _ => unreachable!(),
+        }
+    }
+
+    Ok(())
+}
+
+fn write_player_action(game: &Game, action: Action, all_in: bool, out: &mut String) -> Result<()> {
+    let all_in = if all_in { " and is all-in" } else { "" };
+    match action {
+        Action::Fold(player) => writeln!(out, "{}: folds", game.player_name(usize::from(player)))?,
+        Action::Check(player) => {
+            writeln!(out, "{}: checks", game.player_name(usize::from(player)))?
+        }
+        Action::Call { player, amount } => writeln!(
+            out,
+            "{}: calls {}{all_in}",
+            game.player_name(usize::from(player)),
+            format_amount(amount),
+        )?,
+        Action::Bet { player, amount } => writeln!(
+            out,
+            "{}: bets {}{all_in}",
+            game.player_name(usize::from(player)),
+            format_amount(amount),
+        )?,
+        Action::Raise {
+            player, amount, to, ..
+        } => writeln!(
+            out,
+            "{}: raises {} to {}{all_in}",
+            game.player_name(usize::from(player)),
+            format_amount(amount),
+            format_amount(to),
+        )?,
+        Action::UncalledBet { player, amount } => writeln!(
+            out,
+            "Uncalled bet ({}) returned to {}",
+            format_amount(amount),
+            game.player_name(usize::from(player)),
+        )?,
+        _ => unreachable!(),
+    }
+    Ok(())
+}
+
+fn write_show_or_muck(game: &Game, action: Action, out: &mut String) -> Result<()> {
+    match action {
+        Action::Shows { player, hand } => writeln!(
+            out,
+            "{}: shows [{} {}]",
+            game.player_name(usize::from(player)),
+            hand.high(),
+            hand.low(),
+        )?,
+        Action::MucksOrUnknown(player) => {
+            writeln!(out, "{}: mucks hand", game.player_name(usize::from(player)))?
+        }
+        _ => unreachable!(),
+    }
+    Ok(())
+}
+
+fn write_summary(
+    game: &Game,
+    winnings: &[u32],
+    folded_in: &[Option<Street>],
+    out: &mut String,
+) -> Result<()> {
+    let total_pot = game.total_pot();
+    let Some(rake) = total_pot.checked_sub(winnings.iter().sum()) else {
+        return Err("writer: winnings are larger than the total pot".into());
+    };
+
+    writeln!(out, "*** SUMMARY ***")?;
+    writeln!(
+        out,
+        "Total pot {} | Rake {}",
+        format_amount(total_pot),
+        format_amount(rake),
+    )?;
+
+    let runouts = game.runouts();
+    for (index, board) in runouts.iter().enumerate() {
+        if board.cards().is_empty() {
+            continue;
+        }
+        if runouts.len() > 1 {
+            write!(out, "{} ", RUNOUT_NAMES[index])?;
+        }
+        writeln!(out, "Board [{}]", format_cards(board.cards()))?;
+    }
+
+    for player in 0..game.player_count() {
+        write!(
+            out,
+            "Seat {}: {}",
+            game.seat(player) + 1,
+            game.player_name(player)
+        )?;
+
+        if player == game.button_index() {
+            write!(out, " (button)")?;
+        }
+        if player == game.small_blind_index() {
+            write!(out, " (small blind)")?;
+        } else if player == game.big_blind_index() {
+            write!(out, " (big blind)")?;
+        }
+
+        let amount_won = winnings[player];
+        if let Some(street) = folded_in[player] {
+            match street {
+                Street::PreFlop => write!(out, " folded before Flop")?,
+                _ => write!(out, " folded on the {street}")?,
+            }
+        } else if game.hand_shown(player) {
+            let hand = game.get_hand(player).unwrap();
+            write!(out, " showed [{} {}]", hand.high(), hand.low())?;
+            if amount_won > 0 {
+                write!(out, " and won ({})", format_amount(amount_won))?;
+            } else {
+                write!(out, " and lost")?;
+            }
+        } else if game.hand_mucked(player) {
+            write!(out, " mucked")?;
+        } else if amount_won > 0 {
+            write!(out, " collected ({})", format_amount(amount_won))?;
+        }
+
+        writeln!(out)?;
+    }
+
+    Ok(())
+}
+
+/// Marks the bets, calls and raises which put a player all-in.
+fn all_in_actions(game: &Game) -> Vec<bool> {
+    let mut replay = game.clone();
+    replay.rewind();
+
+    let mut all_in = vec![false; game.actions().len()];
+    let mut action_count = 0;
+    while replay.next() {
+        let actions = replay.actions();
+        if actions.len() != action_count + 1 {
+            action_count = actions.len();
+            continue;
+        }
+        action_count = actions.len();
+
+        let player = match actions.last().copied().unwrap() {
+            Action::Call { player, .. }
+            | Action::Bet { player, .. }
+            | Action::Raise { player, .. } => usize::from(player),
+            _ => continue,
+        };
+        all_in[action_count - 1] = replay.current_stacks()[player] == 0;
+    }
+
+    all_in
+}
+
+fn winnings(game: &Game) -> Result<Vec<u32>> {
+    assert_eq!(game.state(), State::End);
+
+    game.current_stacks()
+        .iter()
+        .zip(game.current_street_stacks())
+        .map(|(showdown_stack, stack)| {
+            showdown_stack
+                .checked_sub(*stack)
+                .ok_or_else(|| "writer: player lost chips at showdown".into())
+        })
+        .collect()
+}
+
+struct RunoutWriter {
+    multiple_runouts: bool,
+    runout: usize,
+    board: Vec<Card>,
+}
+
+impl RunoutWriter {
+    fn new(multiple_runouts: bool) -> Self {
+        Self {
+            multiple_runouts,
+            runout: 0,
+            board: Vec::with_capacity(Game::TOTAL_CARDS),
+        }
+    }
+
+    fn write(&mut self, action: Action, out: &mut String) -> Result<()> {
+        let street = action.street().unwrap();
+        let previous_street = street.previous().unwrap();
+        let known_card_count = previous_street.community_card_count();
+
+        if self.board.len() != known_card_count {
+            // Start of a new runout.
+            if self.board.len() < known_card_count {
+                return Err("writer: street without previous streets".into());
+            }
+            self.runout += 1;
+            self.board.truncate(known_card_count);
+        }
+
+        write!(out, "*** ")?;
+        if self.multiple_runouts {
+            write!(out, "{} ", RUNOUT_NAMES[self.runout])?;
+        }
+
+        match action {
+            Action::Flop(flop) => {
+                writeln!(out, "FLOP *** [{}]", format_cards(&flop))?;
+                self.board.extend(flop);
+            }
+            Action::Turn(card) | Action::River(card) => {
+                let name = if street == Street::Turn {
+                    "TURN"
+                } else {
+                    "RIVER"
+                };
+                writeln!(out, "{name} *** [{}] [{card}]", format_cards(&self.board))?;
+                self.board.push(card);
+            }
+            _ => unreachable!(),
+        }
+
+        Ok(())
+    }
+}
+
+fn format_cards(cards: &[Card]) -> String {
+    cards
+        .iter()
+        .map(|card| card.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn format_amount(cents: u32) -> String {
+    let dollars = cents / 100;
+    let cents = cents % 100;
+    if cents == 0 {
+        format!("${dollars}")
+    } else if cents.is_multiple_of(10) {
+        format!("${dollars}.{}", cents / 10)
+    } else {
+        format!("${dollars}.{cents:02}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::{
+        game::{GameData, Player},
+        parser::GGHandHistoryParser,
+    };
+
+    use super::*;
+
+    #[test]
+    fn round_trip_example_gg_hand_history() {
+        unsafe {
+            crate::init::init();
+        }
+
+        let path = Path::new("src")
+            .join("test_data")
+            .join("gg_hands_example.txt");
+        let history = fs::read_to_string(path).unwrap();
+        let parser = GGHandHistoryParser::new(false);
+        let games = parser.parse_str(&history).unwrap();
+
+        let written = write_games(games.iter()).unwrap();
+        let parsed_games = parser.parse_str(&written).unwrap();
+
+        assert_eq!(games.len(), parsed_games.len());
+        for (game, parsed_game) in games.iter().zip(&parsed_games) {
+            assert_eq!(game.to_game_data(), parsed_game.to_game_data());
+        }
+    }
+
+    #[test]
+    fn write_posts_by_kind() {
+        unsafe {
+            crate::init::init();
+        }
+
+        let posts = |game: &Game| {
+            let mut out = String::new();
+            write_posts(game, &mut out).unwrap();
+            out
+        };
+
+        // The button posts the small blind heads-up.
+        let mut game = Game::from_game_data(&GameData {
+            players: vec![Player::with_starting_stack(1_000); 2],
+            button_index: 1,
+            ..GameData::default()
+        })
+        .unwrap();
+        game.post_small_and_big_blind().unwrap();
+        assert_eq!(
+            posts(&game),
+            format!(
+                "{}: posts small blind $0.05\n{}: posts big blind $0.1\n",
+                game.player_name(1),
+                game.player_name(0)
+            )
+        );
+
+        // A player coming back posts a dead small blind and a live big blind.
+        let mut game = Game::from_game_data(&GameData::default()).unwrap();
+        game.post_small_and_big_blind().unwrap();
+        game.additional_post(4, 5, true).unwrap();
+        game.additional_post(4, 10, false).unwrap();
+        assert_eq!(
+            posts(&game),
+            format!(
+                "{}: posts small blind $0.05\n{}: posts big blind $0.1\n\
+                 {}: posts missed blind $0.05\n{}: posts big blind $0.1\n",
+                game.player_name(1),
+                game.player_name(2),
+                game.player_name(4),
+                game.player_name(4)
+            )
+        );
+    }
+
+    #[test]
+    fn round_trip_simulated_games() {
+        unsafe {
+            crate::init::init();
+        }
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let parser = GGHandHistoryParser::new(false);
+
+        for round in 0..100 {
+            let mut game = Game::from_game_data(&GameData::default()).unwrap();
+            game.draw_unset_hands(&mut rng);
+            game.post_small_and_big_blind().unwrap();
+
+            while game.state() != State::End {
+                match game.state() {
+                    State::Player(_) if round % 3 == 0 && game.can_all_in().is_some() => {
+                        game.all_in().unwrap()
+                    }
+                    State::Player(_) if game.can_check() => game.check().unwrap(),
+                    State::Player(_) if round % 2 == 0 => game.fold().unwrap(),
+                    State::Player(_) => game.call().unwrap(),
+                    State::Street(_) => game.draw_next_street(&mut rng).unwrap(),
+                    State::UncalledBet { .. } => game.uncalled_bet().unwrap(),
+                    State::ShowOrMuck(_)
+                        if round % 5 == 0
+                            && matches!(game.actions().last(), Some(Action::Shows { .. })) =>
+                    {
+                        game.muck_hand().unwrap()
+                    }
+                    State::ShowOrMuck(_) => game.show_hand().unwrap(),
+                    State::ShowdownOrNextRunout => game.showdown_simple().unwrap(),
+                    State::Post | State::End => unreachable!(),
+                }
+            }
+
+            let mut written = String::new();
+            write_game(&game, &mut written).unwrap();
+            let parsed_game = parser.parse_str(&written).unwrap().pop().unwrap();
+
+            let data = game.to_game_data();
+            let parsed_data = parsed_game.to_game_data();
+            assert_eq!(data.players.len(), parsed_data.players.len());
+            for (player, parsed_player) in data.players.iter().zip(&parsed_data.players) {
+                assert_eq!(player.hand, parsed_player.hand);
+                assert_eq!(player.starting_stack, parsed_player.starting_stack);
+            }
+            assert_eq!(data.actions, parsed_data.actions);
+            assert_eq!(data.showdown_stacks, parsed_data.showdown_stacks);
+        }
+    }
+}
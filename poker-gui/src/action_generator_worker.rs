@@ -0,0 +1,109 @@
+use std::{
+    sync::mpsc::{self, Receiver, Sender, TryRecvError},
+    thread,
+};
+
+use eframe::egui::Context;
+use poker_core::{
+    ai::{AiAction, PlayerActionGenerator},
+    game::Game,
+    range::{RangeConfigEntry, RangeTableWith},
+    result::Result,
+};
+
+pub enum WorkerRequest {
+    UpdateVillain {
+        game: Game,
+        log_header: String,
+    },
+    /// The id is sent back with the response.
+    UpdateHero {
+        game: Game,
+        id: usize,
+    },
+}
+
+pub enum WorkerResponse {
+    Villain {
+        log_header: String,
+        log: String,
+        result: Result<()>,
+    },
+    Hero {
+        id: usize,
+        log: String,
+        result: Result<Box<HeroUpdate>>,
+    },
+}
+
+pub struct HeroUpdate {
+    pub action: AiAction,
+    pub config: RangeConfigEntry,
+    pub ranges: Option<Vec<RangeTableWith<u16>>>,
+}
+
+/// Runs a generator on its own thread, requests are handled in order.
+pub struct ActionGeneratorWorker {
+    sender: Sender<WorkerRequest>,
+    receiver: Receiver<WorkerResponse>,
+}
+
+impl ActionGeneratorWorker {
+    pub fn spawn(mut generator: Box<dyn PlayerActionGenerator>, ctx: Context) -> Self {
+        let (request_sender, request_receiver) = mpsc::channel();
+        let (response_sender, response_receiver) = mpsc::channel();
+
+        // Stops once the worker is dropped and the current request is done.
+        thread::spawn(move || {
+            for request in request_receiver {
+                let mut log = String::new();
+                let response = match request {
+                    WorkerRequest::UpdateVillain { game, log_header } => {
+                        let result = generator.update_villain(&game, &mut log);
+                        WorkerResponse::Villain {
+                            log_header,
+                            log,
+                            result,
+                        }
+                    }
+                    WorkerRequest::UpdateHero { game, id } => {
+                        let result = generator.update_hero(&game, &mut log).map(
+                            |(action, config, ranges)| {
+                                Box::new(HeroUpdate {
+                                    action,
+                                    config,
+                                    ranges: ranges.map(|ranges| ranges.to_vec()),
+                                })
+                            },
+                        );
+                        WorkerResponse::Hero { id, log, result }
+                    }
+                };
+
+                if response_sender.send(response).is_err() {
+                    break;
+                }
+                ctx.request_repaint();
+            }
+        });
+
+        Self {
+            sender: request_sender,
+            receiver: response_receiver,
+        }
+    }
+
+    pub fn send(&self, request: WorkerRequest) -> Result<()> {
+        self.sender
+            .send(request)
+            .map_err(|_| "action generator worker: stopped".into())
+    }
+
+    pub fn try_recv(&self) -> Result<Option<WorkerResponse>> {
+        match self.receiver.try_recv() {
+            Ok(response) => Ok(Some(response)),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err("action generator worker: stopped".into()),
+        }
+    }
+}
@@ -0,0 +1,455 @@
+use std::{
+    mem,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        mpsc::{self, Receiver, TryRecvError},
+        Arc,
+    },
+    thread,
+};
+
+use eframe::egui::{
+    Align2, Button, Color32, Context, DragValue, FontFamily, FontId, Grid, Id, Pos2, ProgressBar,
+    Rect, Sense, TextEdit, Ui, Vec2, Window,
+};
+
+use poker_core::{
+    card::Card,
+    cards::Cards,
+    equity::EquityTable,
+    game::Game,
+    range::{RangeEntry, RangeTable, MAX_FREQUENCY},
+    rank::Rank,
+    result::Result,
+};
+
+use crate::{
+    card::draw_cards,
+    card_selector::CardSelector,
+    range_viewer::{RangeValue, RangeViewer},
+};
+
+type EquityResult = Option<Vec<EquityTable>>;
+
+struct EquityJob {
+    cancel: Arc<AtomicBool>,
+    /// In permille.
+    progress: Arc<AtomicU32>,
+    receiver: Receiver<EquityResult>,
+    ranges: Vec<RangeTable>,
+    /// The inputs the job was started with, the job is cancelled when they change.
+    inputs: (Vec<String>, Vec<Card>),
+}
+
+impl Drop for EquityJob {
+    fn drop(&mut self) {
+        // Stops the calculation thread with the next progress report.
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+struct EquityResults {
+    ranges: Vec<RangeTable>,
+    equity_tables: Vec<EquityTable>,
+}
+
+pub struct EquityCalculator {
+    ranges: Vec<String>,
+    range_grid: Option<(usize, RangeViewer)>,
+    board: Vec<Card>,
+    board_selector: CardSelector,
+    show_board_selector: bool,
+    enumerate: bool,
+    rounds: u64,
+    job: Option<EquityJob>,
+    results: Option<EquityResults>,
+    heatmap_player: usize,
+    error: String,
+}
+
+impl Default for EquityCalculator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EquityCalculator {
+    pub fn new() -> Self {
+        let mut board_selector = CardSelector::new();
+        board_selector.set_min_max(0, 5);
+
+        Self {
+            ranges: vec![String::new(); 2],
+            range_grid: None,
+            board: Vec::new(),
+            board_selector,
+            show_board_selector: false,
+            enumerate: false,
+            rounds: 1_000_000,
+            job: None,
+            results: None,
+            heatmap_player: 0,
+            error: String::new(),
+        }
+    }
+
+    pub fn window(&mut self, ctx: &Context, title: String) {
+        Window::new(title)
+            .id(Id::new("equity_calculator"))
+            .resizable([true, true])
+            .default_size([500.0, 700.0])
+            .show(ctx, |ui| self.view(ui));
+    }
+
+    pub fn view(&mut self, ui: &mut Ui) {
+        self.poll_job();
+
+        self.ranges_editor(ui);
+        ui.separator();
+        self.board_editor(ui);
+        ui.separator();
+        self.calculation_bar(ui);
+        self.cancel_outdated_job();
+
+        if !self.error.is_empty() {
+            ui.label(&self.error);
+        }
+
+        if self.results.is_some() {
+            ui.separator();
+            self.results_view(ui);
+        }
+
+        self.view_range_grid(ui.ctx());
+        self.view_board_selector(ui.ctx());
+    }
+
+    fn ranges_editor(&mut self, ui: &mut Ui) {
+        let mut remove_range = None;
+        let mut toggle_grid = None;
+
+        let can_remove = self.ranges.len() > 2;
+        Grid::new("equity_ranges").num_columns(4).show(ui, |ui| {
+            for (index, range) in self.ranges.iter_mut().enumerate() {
+                ui.label(format!("Player {}", index + 1));
+                ui.add(
+                    TextEdit::singleline(range)
+                        .hint_text("QQ+,AKs,AhKd")
+                        .desired_width(250.0),
+                );
+                let grid_open = self
+                    .range_grid
+                    .as_ref()
+                    .is_some_and(|(grid_index, _)| *grid_index == index);
+                if ui.selectable_label(grid_open, "Grid").clicked() {
+                    toggle_grid = Some(index);
+                }
+                if ui.add_enabled(can_remove, Button::new("x")).clicked() {
+                    remove_range = Some(index);
+                }
+                ui.end_row();
+            }
+        });
+
+        if ui
+            .add_enabled(
+                self.ranges.len() < Game::MAX_PLAYERS,
+                Button::new("Add player"),
+            )
+            .clicked()
+        {
+            self.ranges.push(String::new());
+        }
+
+        if let Some(index) = remove_range {
+            self.ranges.remove(index);
+            self.range_grid = None;
+        }
+
+        if let Some(index) = toggle_grid {
+            if self
+                .range_grid
+                .take()
+                .is_some_and(|(grid_index, _)| grid_index == index)
+            {
+                return;
+            }
+
+            match RangeTable::parse(&self.ranges[index]) {
+                Ok(range) => {
+                    let mut range_viewer = RangeViewer::new();
+                    range_viewer.set_editable(true);
+                    range_viewer.replace_ranges(vec![RangeValue::Simple(
+                        range.to_frequencies(MAX_FREQUENCY),
+                    )]);
+                    self.range_grid = Some((index, range_viewer));
+                }
+                Err(err) => self.error = err.to_string(),
+            }
+        }
+    }
+
+    fn view_range_grid(&mut self, ctx: &Context) {
+        let Some((index, range_viewer)) = self.range_grid.as_mut() else {
+            return;
+        };
+
+        let title = format!("Range - Player {}", *index + 1);
+        range_viewer.window(ctx, Id::new("equity_range_grid"), title);
+
+        // The grid is the source of truth while it is open.
+        if let Some(RangeValue::Simple(range)) = range_viewer.ranges().first() {
+            self.ranges[*index] = RangeTable::from_frequencies_not_zero(range).to_string();
+        }
+    }
+
+    fn board_editor(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Board");
+
+            let card_size = Vec2::new(30.0 * self.board.len().max(1) as f32, 39.0);
+            let (rect, _) = ui.allocate_exact_size(card_size, Sense::hover());
+            draw_cards(ui.painter(), rect, &self.board);
+
+            if ui.button("Select").clicked() {
+                self.show_board_selector = !self.show_board_selector;
+            }
+            if ui
+                .add_enabled(!self.board.is_empty(), Button::new("Clear"))
+                .clicked()
+            {
+                self.board.clear();
+                self.board_selector.reset();
+            }
+        });
+    }
+
+    fn view_board_selector(&mut self, ctx: &Context) {
+        if !self.show_board_selector {
+            return;
+        }
+
+        if self.board_selector.window(ctx, "Select board".to_owned()) {
+            self.board = self.board_selector.cards_in_order().to_vec();
+            self.show_board_selector = false;
+        }
+    }
+
+    fn calculation_bar(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            let running = self.job.is_some();
+
+            ui.add_enabled_ui(!running, |ui| {
+                ui.radio_value(&mut self.enumerate, false, "Simulate");
+                ui.add_enabled(
+                    !self.enumerate,
+                    DragValue::new(&mut self.rounds)
+                        .range(1_000..=1_000_000_000)
+                        .speed(10_000)
+                        .suffix(" rounds"),
+                );
+                ui.radio_value(&mut self.enumerate, true, "Enumerate");
+            });
+
+            if let Some(job) = self.job.as_ref() {
+                if ui.button("Cancel").clicked() {
+                    job.cancel.store(true, Ordering::Relaxed);
+                }
+
+                let progress = job.progress.load(Ordering::Relaxed) as f32 / 1000.0;
+                ui.add(ProgressBar::new(progress).show_percentage());
+            } else if ui.button("Calculate").clicked() {
+                self.error = match self.start_job(ui.ctx()) {
+                    Ok(()) => String::new(),
+                    Err(err) => err.to_string(),
+                };
+            }
+        });
+    }
+
+    fn cancel_outdated_job(&mut self) {
+        let outdated = self.job.as_ref().is_some_and(|job| {
+            let (ranges, board) = &job.inputs;
+            *ranges != self.ranges || *board != self.board
+        });
+        if outdated {
+            self.job = None;
+            self.error = "equity: cancelled, the input changed".to_owned();
+        }
+    }
+
+    fn start_job(&mut self, ctx: &Context) -> Result<()> {
+        let ranges = self
+            .ranges
+            .iter()
+            .enumerate()
+            .map(|(index, range)| {
+                let range = RangeTable::parse(range)
+                    .map_err(|err| format!("player {}: {err}", index + 1))?;
+                if range.is_empty() {
+                    return Err(format!("player {}: empty range", index + 1).into());
+                }
+                Ok(range)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let Some(board) = Cards::from_slice(&self.board) else {
+            return Err("equity: invalid board".into());
+        };
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        let progress = Arc::new(AtomicU32::new(0));
+        let (sender, receiver) = mpsc::channel();
+
+        let job_ranges: Vec<_> = ranges.iter().cloned().map(Box::new).collect();
+        let job_cancel = cancel.clone();
+        let job_progress = progress.clone();
+        let enumerate = self.enumerate;
+        let rounds = self.rounds;
+        let ctx = ctx.clone();
+
+        thread::spawn(move || {
+            let report_progress = |fraction: f64| {
+                job_progress.store((fraction * 1000.0) as u32, Ordering::Relaxed);
+                ctx.request_repaint();
+                !job_cancel.load(Ordering::Relaxed)
+            };
+
+            let result = if enumerate {
+                EquityTable::enumerate_with_progress(board, &job_ranges, report_progress)
+            } else {
+                EquityTable::simulate_with_progress(board, &job_ranges, rounds, report_progress)
+            };
+
+            // The receiver is gone if the calculator was dropped.
+            let _ = sender.send(result);
+            ctx.request_repaint();
+        });
+
+        self.job = Some(EquityJob {
+            cancel,
+            progress,
+            receiver,
+            ranges,
+            inputs: (self.ranges.clone(), self.board.clone()),
+        });
+        Ok(())
+    }
+
+    fn poll_job(&mut self) {
+        let Some(job) = self.job.as_ref() else {
+            return;
+        };
+
+        let result = match job.receiver.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => {
+                self.job = None;
+                self.error = "equity: calculation failed".to_owned();
+                return;
+            }
+        };
+
+        let mut job = self.job.take().unwrap();
+        match result {
+            Some(equity_tables) => {
+                self.heatmap_player = 0;
+                self.results = Some(EquityResults {
+                    ranges: mem::take(&mut job.ranges),
+                    equity_tables,
+                });
+            }
+            None if job.cancel.load(Ordering::Relaxed) => {
+                self.error = "equity: cancelled".to_owned();
+            }
+            None => {
+                self.error =
+                    "equity: invalid input or expected sample too large, try simulating".to_owned();
+            }
+        }
+    }
+
+    fn results_view(&mut self, ui: &mut Ui) {
+        let Some(results) = self.results.as_ref() else {
+            return;
+        };
+
+        Grid::new("equity_results")
+            .num_columns(5)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Player");
+                ui.strong("Equity");
+                ui.strong("Win");
+                ui.strong("Tie");
+                ui.strong("Combos");
+                ui.end_row();
+
+                for (index, equity_table) in results.equity_tables.iter().enumerate() {
+                    ui.radio_value(
+                        &mut self.heatmap_player,
+                        index,
+                        format!("Player {}", index + 1),
+                    );
+                    ui.label(format!(
+                        "{:.2}%",
+                        equity_table.total_equity_percent() * 100.0
+                    ));
+                    ui.label(format!("{:.2}%", equity_table.total_win_percent() * 100.0));
+                    ui.label(format!("{:.2}%", equity_table.total_tie_percent() * 100.0));
+                    ui.label(results.ranges[index].count().to_string());
+                    ui.end_row();
+                }
+            });
+
+        let width = ui.available_width().min(ui.available_height()).max(200.0);
+        let (rect, _) = ui.allocate_exact_size(Vec2::splat(width), Sense::hover());
+        self.draw_heatmap(ui, rect);
+    }
+
+    fn draw_heatmap(&self, ui: &mut Ui, bounding_rect: Rect) {
+        let results = self.results.as_ref().unwrap();
+        let equity_table = &results.equity_tables[self.heatmap_player];
+        let field_size = Vec2::splat(bounding_rect.width() / Rank::COUNT as f32);
+
+        for (row_index, row) in Rank::RANKS.into_iter().rev().enumerate() {
+            for (column_index, column) in Rank::RANKS.into_iter().rev().enumerate() {
+                let left_corner = Pos2 {
+                    x: bounding_rect.min.x + field_size.x * column_index as f32,
+                    y: bounding_rect.min.y + field_size.y * row_index as f32,
+                };
+                let field_rect = Rect::from_min_size(left_corner, field_size);
+                let entry = RangeEntry::from_row_column(row, column);
+                let equity = equity_table.entry_equity(entry);
+
+                let painter = ui.painter_at(field_rect);
+                let color = equity
+                    .map(|equity| heatmap_color(equity.equity_percent()))
+                    .unwrap_or(Color32::from_gray(40));
+                painter.rect_filled(field_rect.shrink(0.5), 0.0, color);
+                painter.text(
+                    field_rect.center(),
+                    Align2::CENTER_CENTER,
+                    entry.to_regular_string(),
+                    FontId::new(field_rect.width() / 3.0, FontFamily::Monospace),
+                    Color32::WHITE,
+                );
+
+                let Some(equity) = equity else {
+                    continue;
+                };
+                ui.allocate_rect(field_rect, Sense::hover())
+                    .on_hover_text(format!("{}: {equity}", entry.to_regular_string()));
+            }
+        }
+    }
+}
+
+/// From red at 0% over yellow at 50% to green at 100% equity.
+fn heatmap_color(equity: f64) -> Color32 {
+    let equity = equity.clamp(0.0, 1.0) as f32;
+    let red = (2.0 - 2.0 * equity).min(1.0);
+    let green = (2.0 * equity).min(1.0);
+    Color32::from_rgb((red * 180.0) as u8, (green * 180.0) as u8, 0)
+}
@@ -14,6 +14,8 @@ pub struct GameBuilderConfig {
     pub game: Game,
     pub players: Vec<GameBuilderPlayer>,
     pub pick_community_cards: bool,
+    /// Grades the decisions of players without an action generator.
+    pub trainer_action_generator: Option<usize>,
 }
 
 #[derive(Clone)]
@@ -29,9 +31,10 @@ pub struct GameBuilder {
     big_blind: u32,
     hand_selector: CardSelector,
     hand_selector_for: Option<usize>,
-    player_action_generators: Vec<&'static str>,
+    player_action_generators: Vec<String>,
     default_player_action_generator: Option<usize>,
     pick_community_cards: bool,
+    trainer_action_generator: Option<usize>,
     error: String,
     remove_hand: Option<usize>,
     remove_player: Option<usize>,
@@ -40,9 +43,10 @@ pub struct GameBuilder {
 
 impl GameBuilder {
     const ROW_HEIGHT: f32 = 20.0;
+    const NONE: &str = "None";
 
     pub fn new(
-        player_action_generators: Vec<&'static str>,
+        player_action_generators: Vec<String>,
         default_player_action_generator: Option<usize>,
     ) -> Self {
         let mut hand_selector = CardSelector::new();
@@ -67,6 +71,7 @@ impl GameBuilder {
             player_action_generators,
             default_player_action_generator,
             pick_community_cards: false,
+            trainer_action_generator: None,
             error: String::new(),
             remove_hand: None,
             remove_player: None,
@@ -118,7 +123,40 @@ impl GameBuilder {
             }
         });
 
-        ui.horizontal(|ui| ui.checkbox(&mut self.pick_community_cards, "Pick community cards"));
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.pick_community_cards, "Pick community cards");
+            ui.separator();
+
+            ui.label("Trainer:");
+            ComboBox::from_id_salt("Trainer Combobox")
+                .selected_text(Self::action_generator_name(
+                    &self.player_action_generators,
+                    self.trainer_action_generator,
+                ))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.trainer_action_generator, None, Self::NONE);
+                    for (ai_index, ai_name) in self.player_action_generators.iter().enumerate() {
+                        ui.selectable_value(
+                            &mut self.trainer_action_generator,
+                            Some(ai_index),
+                            ai_name,
+                        );
+                    }
+                });
+        });
+    }
+
+    pub fn add_player_action_generator(&mut self, name: String) {
+        self.player_action_generators.push(name);
+    }
+
+    fn action_generator_name(
+        player_action_generators: &[String],
+        action_generator: Option<usize>,
+    ) -> &str {
+        action_generator
+            .map(|index| player_action_generators[index].as_str())
+            .unwrap_or(Self::NONE)
     }
 
     fn players_table(&mut self, ui: &mut Ui) {
@@ -238,18 +276,14 @@ impl GameBuilder {
 
     fn players_table_ai_column(&mut self, ui: &mut Ui, player_index: usize) {
         let player = &mut self.players[player_index];
-        const NONE: &str = "None";
-        let action_generator_name = player
-            .action_generator
-            .map(|index| self.player_action_generators[index])
-            .unwrap_or(NONE);
+        let action_generator_name =
+            Self::action_generator_name(&self.player_action_generators, player.action_generator);
 
         ComboBox::from_id_salt(format!("AI Player Combobox - {player_index}"))
             .selected_text(action_generator_name)
             .show_ui(ui, |ui| {
-                ui.selectable_value(&mut player.action_generator, None, NONE);
-                for (ai_index, ai_name) in self.player_action_generators.iter().copied().enumerate()
-                {
+                ui.selectable_value(&mut player.action_generator, None, Self::NONE);
+                for (ai_index, ai_name) in self.player_action_generators.iter().enumerate() {
                     ui.selectable_value(&mut player.action_generator, Some(ai_index), ai_name);
                 }
             });
@@ -396,6 +430,7 @@ impl GameBuilder {
             game,
             players: self.players.clone(),
             pick_community_cards: self.pick_community_cards,
+            trainer_action_generator: self.trainer_action_generator,
         })
     }
 }
@@ -1,4 +1,4 @@
-use std::{collections::HashMap, fmt::Write, fs, mem, sync::Arc};
+use std::{collections::HashMap, fmt::Write, path::Path, sync::Arc};
 
 use eframe::egui::{
     Align, Align2, Button, Color32, Context, DragValue, FontFamily, FontId, Id, Layout, Painter,
@@ -9,39 +9,65 @@ use poker_core::{
     ai::{AlwaysAllIn, AlwaysCheckCall, AlwaysFold, PlayerActionGenerator, SimpleStrategy},
     game::{Action, Game, GameData, State, Street},
     hand::Hand,
-    range::{PreFlopRangeConfig, PreFlopRangeConfigData},
+    opponent::OpponentModel,
+    range::{PreFlopRangeConfigSet, RangeConfigEntry},
+    range_binary,
     result::Result,
+    trainer::Decision,
 };
 
 use crate::{
+    action_generator_worker::{ActionGeneratorWorker, HeroUpdate, WorkerRequest, WorkerResponse},
     card::{draw_card, draw_hidden_card},
     card_selector::CardSelector,
+    equity_calculator::EquityCalculator,
     game_builder::GameBuilder,
     range_viewer::{RangeValue, RangeViewer},
+    trainer::Trainer,
 };
 
 // TODO:
 // - Portable text drawing fonts
 // - Max text sizes
 
+struct TrainerPending {
+    player: usize,
+    /// The action count of the graded decision.
+    id: usize,
+    game: Game,
+    config: Option<RangeConfigEntry>,
+}
+
+type PlayerActionGeneratorFactory = Box<dyn FnMut() -> Box<dyn PlayerActionGenerator>>;
+
 pub struct GameView {
     game: Game,
     card_selector: CardSelector,
     enable_game_builder: bool,
     game_builder: GameBuilder,
-    player_action_generators: Vec<(
-        &'static str,
-        Box<dyn FnMut() -> Box<dyn PlayerActionGenerator>>,
-    )>,
-
-    // TODO: Offload to background thread.
-    current_player_action_generators: HashMap<usize, Box<dyn PlayerActionGenerator>>,
+    player_action_generators: Vec<(String, PlayerActionGeneratorFactory)>,
+
+    current_player_action_generators: HashMap<usize, ActionGeneratorWorker>,
+    /// The factory index of every current generator, to respawn it on cancel.
+    current_player_action_generator_indices: HashMap<usize, usize>,
+    /// The player and the action count of the pending hero update.
+    thinking: Option<(usize, usize)>,
+    /// The action count at which the pending hero update got cancelled,
+    /// the user then acts for the generator.
+    cancelled_thinking: Option<usize>,
     /// The keys are the indices of the inserted action values.
     /// The values contain the length of the matching generator logs.
     current_range_histories: HashMap<usize, (Vec<RangeValue>, usize)>,
     current_generator_logs: Vec<String>,
     last_applied_action_index: usize,
     range_viewer: RangeViewer,
+    equity_calculator: EquityCalculator,
+    show_equity_calculator: bool,
+    /// Reference generators of the players without an action generator.
+    trainer_workers: HashMap<usize, ActionGeneratorWorker>,
+    trainer_pending: Vec<TrainerPending>,
+    trainer: Trainer,
+    show_trainer: bool,
 
     current_amount: u32,
     pick_community_cards: bool,
@@ -53,29 +79,51 @@ impl GameView {
         Self::new_inner(None).unwrap()
     }
 
-    pub fn new_with_simple_strategy(pre_flop_ranges_config_path: &str) -> Result<Self> {
-        Self::new_inner(Some(pre_flop_ranges_config_path))
+    /// The opponent model narrows the villain ranges shown in the range viewer.
+    pub fn new_with_simple_strategy(
+        pre_flop_ranges_config_path: &str,
+        opponent_model: OpponentModel,
+    ) -> Result<Self> {
+        Self::new_inner(Some((pre_flop_ranges_config_path, opponent_model)))
+    }
+
+    /// Makes the generator selectable for players and the trainer, e.g. an external bot.
+    pub fn add_player_action_generator(
+        &mut self,
+        name: String,
+        factory: impl FnMut() -> Box<dyn PlayerActionGenerator> + 'static,
+    ) {
+        self.game_builder.add_player_action_generator(name.clone());
+        self.player_action_generators
+            .push((name, Box::new(factory)));
     }
 
-    fn new_inner(pre_flop_ranges_config_path: Option<&str>) -> Result<Self> {
-        let mut player_action_generators: Vec<(
-            &'static str,
-            Box<dyn FnMut() -> Box<dyn PlayerActionGenerator>>,
-        )> = vec![
-            ("Fold", Box::new(|| Box::new(AlwaysFold))),
-            ("Check/Call", Box::new(|| Box::new(AlwaysCheckCall))),
-            ("AllIn", Box::new(|| Box::new(AlwaysAllIn))),
+    fn new_inner(simple_strategy: Option<(&str, OpponentModel)>) -> Result<Self> {
+        let mut player_action_generators: Vec<(String, PlayerActionGeneratorFactory)> = vec![
+            ("Fold".to_owned(), Box::new(|| Box::new(AlwaysFold))),
+            (
+                "Check/Call".to_owned(),
+                Box::new(|| Box::new(AlwaysCheckCall)),
+            ),
+            ("AllIn".to_owned(), Box::new(|| Box::new(AlwaysAllIn))),
         ];
 
-        let default_player_action = if let Some(path) = pre_flop_ranges_config_path {
-            let pre_flop_ranges_raw = fs::read_to_string(path)?;
-            let pre_flop_ranges: PreFlopRangeConfigData =
-                serde_json::from_str(&pre_flop_ranges_raw)?;
-            let pre_flop_ranges = Arc::new(PreFlopRangeConfig::from_data(pre_flop_ranges)?);
+        let default_player_action = if let Some((path, opponent_model)) = simple_strategy {
+            // One config per depth, separated by commas.
+            let mut configs = Vec::new();
+            for path in path.split(',') {
+                configs.push(range_binary::load_pre_flop_range_config(Path::new(path))?);
+            }
+            let pre_flop_ranges = Arc::new(PreFlopRangeConfigSet::new(configs)?);
 
             player_action_generators.push((
-                "Simple",
-                Box::new(move || Box::new(SimpleStrategy::new(pre_flop_ranges.clone()))),
+                "Simple".to_owned(),
+                Box::new(move || {
+                    Box::new(
+                        SimpleStrategy::new(pre_flop_ranges.clone())
+                            .with_opponent_model(opponent_model.clone()),
+                    )
+                }),
             ));
 
             player_action_generators.len() - 1
@@ -85,7 +133,7 @@ impl GameView {
 
         let player_action_generator_names = player_action_generators
             .iter()
-            .map(|(name, _)| *name)
+            .map(|(name, _)| name.clone())
             .collect();
 
         let mut game_view = Self {
@@ -98,10 +146,19 @@ impl GameView {
             ),
             player_action_generators,
             current_player_action_generators: HashMap::new(),
+            current_player_action_generator_indices: HashMap::new(),
+            thinking: None,
+            cancelled_thinking: None,
             current_range_histories: HashMap::new(),
             current_generator_logs: vec![String::new(); Game::MAX_PLAYERS],
             last_applied_action_index: 2, // Skip initial posts.
             range_viewer: RangeViewer::new(),
+            equity_calculator: EquityCalculator::new(),
+            show_equity_calculator: false,
+            trainer_workers: HashMap::new(),
+            trainer_pending: Vec::new(),
+            trainer: Trainer::new(),
+            show_trainer: false,
             current_amount: 0,
             pick_community_cards: false,
             show_all_hands: true,
@@ -121,6 +178,10 @@ impl GameView {
         self.pick_community_cards = pick_community_cards;
     }
 
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
     pub fn with_game_mut(&mut self, f: impl FnOnce(&mut Game)) -> Result<()> {
         f(&mut self.game);
 
@@ -153,10 +214,22 @@ impl GameView {
 
         self.view_ranges(ui.ctx());
 
+        if self.show_equity_calculator {
+            self.equity_calculator
+                .window(ui.ctx(), "Equity calculator".to_owned());
+        }
+
+        if self.show_trainer {
+            self.trainer.window(ui.ctx(), "Trainer".to_owned());
+        }
+
         self.finalize(ui.ctx())
     }
 
     fn finalize(&mut self, ctx: &Context) -> Result<()> {
+        self.receive_generator_responses(ctx)?;
+        self.grade_trainer_decisions();
+
         if self.game.can_next() {
             return Ok(());
         }
@@ -167,38 +240,36 @@ impl GameView {
             State::Player(player)
                 if self.current_player_action_generators.contains_key(&player) =>
             {
-                let mut temp_log = String::new();
-
-                // TODO: Gracefully handle errors and check action is valid.
-                let (action, config, ranges) = self
-                    .current_player_action_generators
-                    .get_mut(&player)
-                    .unwrap()
-                    .update_hero(&self.game, &mut temp_log)?;
-
-                action.apply_to_game(&mut self.game)?;
-
-                let mut ranges_history_entry = vec![RangeValue::Full(config)];
-                if let Some(ranges) = ranges {
-                    for current_player in self.game.players_not_folded() {
-                        if current_player == player {
-                            continue;
-                        }
-
-                        // TODO: Check index is valid.
-                        ranges_history_entry
-                            .push(RangeValue::Simple(ranges[current_player].clone()))
-                    }
+                let id = self.game.actions().len();
+                if self.thinking.is_none() && self.cancelled_thinking != Some(id) {
+                    self.current_player_action_generators[&player].send(
+                        WorkerRequest::UpdateHero {
+                            game: self.game.clone(),
+                            id,
+                        },
+                    )?;
+                    self.thinking = Some((player, id));
                 }
 
-                let current_log_offset = self.write_generator_log(player, "Hero", &temp_log);
-
-                self.current_range_histories.insert(
-                    self.game.actions().len(),
-                    (ranges_history_entry, current_log_offset),
-                );
+                // The worker requests a repaint once it is done.
+                return Ok(());
+            }
+            State::Player(player) if self.trainer_workers.contains_key(&player) => {
+                let id = self.game.actions().len();
+                if !self.trainer_pending.iter().any(|pending| pending.id == id) {
+                    self.trainer_workers[&player].send(WorkerRequest::UpdateHero {
+                        game: self.game.clone(),
+                        id,
+                    })?;
+                    self.trainer_pending.push(TrainerPending {
+                        player,
+                        id,
+                        game: self.game.clone(),
+                        config: None,
+                    });
+                }
 
-                self.apply_action_to_villains()?;
+                return Ok(());
             }
             State::Street(_) if !self.pick_community_cards => {
                 let mut rng = rand::thread_rng();
@@ -218,6 +289,145 @@ impl GameView {
         Ok(())
     }
 
+    fn receive_generator_responses(&mut self, ctx: &Context) -> Result<()> {
+        let mut responses = Vec::new();
+        for (player, worker) in &self.current_player_action_generators {
+            while let Some(response) = worker.try_recv()? {
+                responses.push((*player, response));
+            }
+        }
+
+        let mut trainer_responses = Vec::new();
+        for worker in self.trainer_workers.values() {
+            while let Some(response) = worker.try_recv()? {
+                trainer_responses.push(response);
+            }
+        }
+
+        for response in trainer_responses {
+            match response {
+                WorkerResponse::Villain { result, .. } => result?,
+                WorkerResponse::Hero { id, result, .. } => {
+                    let update = result?;
+                    if let Some(pending) = self
+                        .trainer_pending
+                        .iter_mut()
+                        .find(|pending| pending.id == id)
+                    {
+                        pending.config = Some(update.config);
+                    }
+                }
+            }
+        }
+
+        for (player, response) in responses {
+            match response {
+                WorkerResponse::Villain {
+                    log_header,
+                    log,
+                    result,
+                } => {
+                    // TODO: Handle errors gracefully.
+                    result?;
+                    self.write_generator_log(player, &log_header, &log);
+                }
+                WorkerResponse::Hero { id, log, result } => {
+                    if self.thinking != Some((player, id)) {
+                        // Cancelled, the user already acted instead.
+                        continue;
+                    }
+                    self.thinking = None;
+
+                    // TODO: Gracefully handle errors and check action is valid.
+                    self.apply_hero_update(player, *result?, &log)?;
+                    ctx.request_repaint();
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn apply_hero_update(&mut self, player: usize, update: HeroUpdate, log: &str) -> Result<()> {
+        update.action.apply_to_game(&mut self.game)?;
+
+        let mut ranges_history_entry = vec![RangeValue::Full(update.config)];
+        if let Some(ranges) = update.ranges {
+            for current_player in self.game.players_not_folded() {
+                if current_player == player {
+                    continue;
+                }
+
+                // TODO: Check index is valid.
+                ranges_history_entry.push(RangeValue::Simple(ranges[current_player].clone()))
+            }
+        }
+
+        let log_header = self.generator_log_header("Hero");
+        let current_log_offset = self.write_generator_log(player, &log_header, log);
+
+        self.current_range_histories.insert(
+            self.game.actions().len(),
+            (ranges_history_entry, current_log_offset),
+        );
+
+        self.apply_action_to_villains()
+    }
+
+    fn grade_trainer_decisions(&mut self) {
+        let actions = self.game.actions();
+
+        self.trainer_pending.retain(|pending| {
+            let Some(action) = actions.get(pending.id).copied() else {
+                return true;
+            };
+            let Some(config) = &pending.config else {
+                return true;
+            };
+
+            if action.player() == Some(pending.player) {
+                if let Some(decision) = Decision::grade(&pending.game, config, action) {
+                    self.trainer.add(decision);
+                }
+            }
+            false
+        });
+    }
+
+    /// The generator can't be interrupted, so it is replaced by a new one that gets
+    /// all previous actions as villain updates. The old one finishes on its own
+    /// and its result and state are dropped.
+    fn cancel_thinking(&mut self, ctx: &Context) -> Result<()> {
+        let Some((player, id)) = self.thinking.take() else {
+            return Ok(());
+        };
+        self.cancelled_thinking = Some(id);
+
+        let ai_index = self.current_player_action_generator_indices[&player];
+        let worker = ActionGeneratorWorker::spawn(
+            (self.player_action_generators[ai_index].1)(),
+            ctx.clone(),
+        );
+
+        let mut game = self.game.clone();
+        game.rewind();
+        while game.actions().len() < id && game.next() {
+            let is_player_action = game
+                .actions()
+                .last()
+                .is_some_and(|action| action.player().is_some());
+            if game.actions().len() > 2 && is_player_action {
+                worker.send(WorkerRequest::UpdateVillain {
+                    game: game.clone(),
+                    log_header: self.generator_log_header("Replay after cancel"),
+                })?;
+            }
+        }
+
+        self.current_player_action_generators.insert(player, worker);
+        Ok(())
+    }
+
     fn apply_action_to_villains(&mut self) -> Result<()> {
         if self.game.can_next() {
             return Ok(());
@@ -235,26 +445,39 @@ impl GameView {
             return Ok(());
         };
 
-        let mut generators = mem::take(&mut self.current_player_action_generators);
+        let log_header =
+            self.generator_log_header(&format!("Villain {}", self.game.player_name(action_player)));
 
-        for (player, action_generator) in &mut generators {
-            if self.game.folded(*player) || *player == action_player {
+        // The user acted for a cancelled generator, which has to follow that action too.
+        let substitute = self.cancelled_thinking == Some(self.game.actions().len() - 1);
+        for (player, worker) in &self.current_player_action_generators {
+            let skip = if *player == action_player {
+                !substitute
+            } else {
+                self.game.folded(*player)
+            };
+            if skip {
                 continue;
             }
 
-            let mut temp_log = String::new();
+            worker.send(WorkerRequest::UpdateVillain {
+                game: self.game.clone(),
+                log_header: log_header.clone(),
+            })?;
+        }
 
-            // TODO: Handle errors gracefully and don't forget to restore the generator hashmap.
-            action_generator.update_villain(&self.game, &mut temp_log)?;
+        // The reference also has to follow the actions the user picked.
+        for (player, worker) in &self.trainer_workers {
+            if self.game.folded(*player) && *player != action_player {
+                continue;
+            }
 
-            self.write_generator_log(
-                *player,
-                &format!("Villain {}", self.game.player_name(action_player)),
-                &temp_log,
-            );
+            worker.send(WorkerRequest::UpdateVillain {
+                game: self.game.clone(),
+                log_header: log_header.clone(),
+            })?;
         }
 
-        self.current_player_action_generators = generators;
         self.last_applied_action_index = self.game.actions().len();
 
         Ok(())
@@ -339,15 +562,42 @@ impl GameView {
             UiBuilder::new()
                 .max_rect(bounding_rect.with_min_y(upper_elements_max_y))
                 .layout(Layout::left_to_right(Align::Center)),
-            |ui| self.action_bar_history_buttons(ui),
+            |ui| {
+                // The pending hero update belongs to the current position.
+                ui.add_enabled_ui(self.thinking.is_none(), |ui| {
+                    self.action_bar_history_buttons(ui)
+                })
+                .inner
+            },
         )
         .inner?;
 
+        if let Some((player, _)) = self.thinking {
+            let cancel = ui
+                .allocate_new_ui(
+                    UiBuilder::new()
+                        .max_rect(bounding_rect.with_min_y(upper_elements_max_y))
+                        .layout(Layout::right_to_left(Align::Center)),
+                    |ui| {
+                        let cancel = ui.button("Cancel").clicked();
+                        ui.label(format!("{} is thinking...", self.game.player_name(player)));
+                        ui.spinner();
+                        cancel
+                    },
+                )
+                .inner;
+            if cancel {
+                self.cancel_thinking(ui.ctx())?;
+            }
+            return Ok(());
+        }
+
         if self.game.can_next()
             || self.game.current_player().is_none()
-            || self
+            || (self
                 .current_player_action_generators
                 .contains_key(&self.game.current_player().unwrap())
+                && self.cancelled_thinking != Some(self.game.actions().len()))
         {
             return Ok(());
         }
@@ -489,6 +739,8 @@ impl GameView {
         }
 
         ui.checkbox(&mut self.show_all_hands, "Show all");
+        ui.checkbox(&mut self.show_equity_calculator, "Equity");
+        ui.checkbox(&mut self.show_trainer, "Trainer");
 
         Ok(())
     }
@@ -769,15 +1021,34 @@ impl GameView {
             .for_each(String::clear);
         self.last_applied_action_index = 2; // Skip initial posts.
         self.current_player_action_generators.clear();
+        self.current_player_action_generator_indices.clear();
+        self.thinking = None;
+        self.cancelled_thinking = None;
+        self.trainer_workers.clear();
+        self.trainer_pending.clear();
+        if config.trainer_action_generator.is_some() {
+            self.show_trainer = true;
+        }
         self.range_viewer = RangeViewer::new();
 
         for (player_index, player) in config.players.into_iter().enumerate() {
             let Some(ai_index) = player.action_generator else {
+                if let Some(ai_index) = config.trainer_action_generator {
+                    let action_generator = (self.player_action_generators[ai_index].1)();
+                    self.trainer_workers.insert(
+                        player_index,
+                        ActionGeneratorWorker::spawn(action_generator, ctx.clone()),
+                    );
+                }
                 continue;
             };
             let action_generator = (self.player_action_generators[ai_index].1)();
-            self.current_player_action_generators
-                .insert(player_index, action_generator);
+            self.current_player_action_generators.insert(
+                player_index,
+                ActionGeneratorWorker::spawn(action_generator, ctx.clone()),
+            );
+            self.current_player_action_generator_indices
+                .insert(player_index, ai_index);
         }
 
         ctx.request_repaint();
@@ -816,23 +1087,25 @@ impl GameView {
         self.range_viewer.window(ctx, Id::new("Range"), title);
     }
 
-    fn write_generator_log(&mut self, player: usize, name: &str, log: &str) -> usize {
+    fn generator_log_header(&self, name: &str) -> String {
+        format!(
+            "{name} at {} - {}",
+            self.game.board().street(),
+            self.game
+                .actions_in_street()
+                .iter()
+                .filter_map(|action| action.player_char())
+                .collect::<String>(),
+        )
+    }
+
+    fn write_generator_log(&mut self, player: usize, log_header: &str, log: &str) -> usize {
         let out = &mut self.current_generator_logs[player];
 
         if log.is_empty() {
             out.len()
         } else {
-            write!(
-                out,
-                "{name} at {} - {}:\n\n{log}",
-                self.game.board().street(),
-                self.game
-                    .actions_in_street()
-                    .iter()
-                    .filter_map(|action| action.player_char())
-                    .collect::<String>(),
-            )
-            .unwrap();
+            write!(out, "{log_header}:\n\n{log}").unwrap();
             let offset = out.trim_end().len();
             out.push_str("\n---------\n\n");
             offset
@@ -1,18 +1,27 @@
+use std::collections::HashSet;
+
 use eframe::egui::{
-    Align, CentralPanel, Context, Layout, ScrollArea, Sense, TextStyle, TopBottomPanel, Ui,
-    UiBuilder,
+    Align, CentralPanel, ComboBox, Context, Key, Layout, ScrollArea, Sense, TextEdit, TextStyle,
+    TopBottomPanel, Ui, UiBuilder,
 };
 use egui_extras::{Column, TableBody, TableBuilder, TableRow};
 use poker_core::{db, game::Game, result::Result};
 
-use crate::{card::draw_cards, game_view::GameView};
+use crate::{card::draw_cards, replayer::Replayer};
 
 pub struct HistoryView {
     entries: Vec<(db::Hand, Option<db::HandPlayer>)>,
     current_entry: Option<usize>,
     scroll_to_current_entry: bool,
-    game_view: Option<GameView>,
+    replayer: Option<Replayer>,
     game_getter: Box<dyn FnMut(u64) -> Result<Game>>,
+    tags_db: Option<db::DB>,
+    tags: Vec<(String, u64)>,
+    current_tags: Vec<String>,
+    new_tag: String,
+    tag_filter: Option<String>,
+    visible_entries: Vec<usize>,
+    error: String,
 }
 
 impl HistoryView {
@@ -20,18 +29,40 @@ impl HistoryView {
         entries: Vec<(db::Hand, Option<db::HandPlayer>)>,
         game_getter: Box<dyn FnMut(u64) -> Result<Game>>,
     ) -> Self {
+        let visible_entries = (0..entries.len()).collect();
         Self {
             entries,
             scroll_to_current_entry: false,
             current_entry: None,
-            game_view: None,
+            replayer: None,
             game_getter,
+            tags_db: None,
+            tags: Vec::new(),
+            current_tags: Vec::new(),
+            new_tag: String::new(),
+            tag_filter: None,
+            visible_entries,
+            error: String::new(),
         }
     }
 
+    /// Enables tagging hands, the database has to be writable.
+    pub fn set_tags_db(&mut self, db: db::DB) {
+        self.tags_db = Some(db);
+        self.tag_filter = None;
+        self.refresh_tags();
+    }
+
     pub fn view(&mut self, ctx: &Context) {
         let old_entry = self.current_entry;
-        let has_game = self.game_view.is_some();
+
+        if self.tags_db.is_some() {
+            TopBottomPanel::top("tags_panel").show(ctx, |ui| {
+                ui.horizontal_wrapped(|ui| self.tags_bar(ui));
+            });
+        }
+
+        let has_game = self.replayer.is_some();
 
         if has_game {
             CentralPanel::default().show(ctx, |ui| self.game(ui));
@@ -51,14 +82,168 @@ impl HistoryView {
             });
 
         if old_entry != self.current_entry {
-            self.update_game_view();
+            self.update_replayer(ctx);
+            self.refresh_current_tags();
             ctx.request_repaint();
         }
     }
 
-    fn update_game_view(&mut self) {
+    fn tags_bar(&mut self, ui: &mut Ui) {
+        let mut tag_filter = self.tag_filter.clone();
+        ui.label("Tag:");
+        ComboBox::from_id_salt("tag_filter")
+            .selected_text(tag_filter.as_deref().unwrap_or("All hands"))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut tag_filter, None, "All hands");
+                for (tag, count) in &self.tags {
+                    ui.selectable_value(
+                        &mut tag_filter,
+                        Some(tag.clone()),
+                        format!("{tag} ({count})"),
+                    );
+                }
+            });
+        if tag_filter != self.tag_filter {
+            self.tag_filter = tag_filter;
+            self.refresh_tags();
+        }
+
+        ui.label(format!(
+            "{}/{} hands",
+            self.visible_entries.len(),
+            self.entries.len()
+        ));
+
+        let Some(hand_id) = self.current_hand_id() else {
+            if !self.error.is_empty() {
+                ui.separator();
+                ui.label(&self.error);
+            }
+            return;
+        };
+
+        ui.separator();
+
+        let bookmarked = self
+            .current_tags
+            .iter()
+            .any(|tag| tag == db::DB::BOOKMARK_TAG);
+        let bookmark_text = if bookmarked { "★" } else { "☆" };
+        if ui.button(bookmark_text).on_hover_text("Bookmark").clicked() {
+            self.toggle_tag(hand_id, db::DB::BOOKMARK_TAG, !bookmarked);
+        }
+
+        let mut remove_tag = None;
+        for tag in &self.current_tags {
+            if tag == db::DB::BOOKMARK_TAG {
+                continue;
+            }
+            if ui
+                .button(format!("{tag} ✕"))
+                .on_hover_text("Remove tag")
+                .clicked()
+            {
+                remove_tag = Some(tag.clone());
+            }
+        }
+        if let Some(tag) = remove_tag {
+            self.toggle_tag(hand_id, &tag, false);
+        }
+
+        let response = ui.add(
+            TextEdit::singleline(&mut self.new_tag)
+                .hint_text("New tag")
+                .desired_width(120.0),
+        );
+        let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+        if (ui.button("Add tag").clicked() || submitted) && !self.new_tag.trim().is_empty() {
+            let tag = std::mem::take(&mut self.new_tag);
+            self.toggle_tag(hand_id, &tag, true);
+        }
+
+        if !self.error.is_empty() {
+            ui.separator();
+            ui.label(&self.error);
+        }
+    }
+
+    fn toggle_tag(&mut self, hand_id: u64, tag: &str, add: bool) {
+        let Some(db) = self.tags_db.as_ref() else {
+            return;
+        };
+
+        let result = if add {
+            db.add_hand_tag(hand_id, tag)
+        } else {
+            db.remove_hand_tag(hand_id, tag)
+        };
+        if let Err(err) = result {
+            self.error = err.to_string();
+            return;
+        }
+
+        self.refresh_tags();
+    }
+
+    fn refresh_tags(&mut self) {
+        self.error = String::new();
+        if let Err(err) = self.try_refresh_tags() {
+            self.error = err.to_string();
+        }
+        self.refresh_current_tags();
+    }
+
+    fn try_refresh_tags(&mut self) -> Result<()> {
+        let Some(db) = self.tags_db.as_ref() else {
+            return Ok(());
+        };
+
+        self.tags = db.get_tags()?;
+
+        let Some(tag_filter) = self.tag_filter.as_ref() else {
+            self.visible_entries = (0..self.entries.len()).collect();
+            return Ok(());
+        };
+
+        let hand_ids: HashSet<u64> = db.get_hand_ids_with_tag(tag_filter)?.into_iter().collect();
+        self.visible_entries = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, (hand, _))| hand.id.is_some_and(|id| hand_ids.contains(&id)))
+            .map(|(index, _)| index)
+            .collect();
+
+        // Keep showing the current hand after removing the filtered tag from it.
+        if let Some(current_entry) = self.current_entry {
+            if let Err(index) = self.visible_entries.binary_search(&current_entry) {
+                self.visible_entries.insert(index, current_entry);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn refresh_current_tags(&mut self) {
+        self.current_tags.clear();
+        let (Some(db), Some(hand_id)) = (self.tags_db.as_ref(), self.current_hand_id()) else {
+            return;
+        };
+
+        match db.get_hand_tags(hand_id) {
+            Ok(tags) => self.current_tags = tags,
+            Err(err) => self.error = err.to_string(),
+        }
+    }
+
+    fn current_hand_id(&self) -> Option<u64> {
+        self.current_entry
+            .and_then(|current_entry| self.entries[current_entry].0.id)
+    }
+
+    fn update_replayer(&mut self, ctx: &Context) {
         if let Some(current_entry) = self.current_entry {
-            if self.game_view.is_none() {
+            if self.replayer.is_none() {
                 self.scroll_to_current_entry = true;
             }
 
@@ -66,27 +251,33 @@ impl HistoryView {
             // - Handle errors
             // - Run in background
             let hand_id = self.entries[current_entry].0.id.unwrap();
-            let mut game = (self.game_getter)(hand_id).unwrap();
-            game.rewind();
-
-            let mut game_view = GameView::new();
-            game_view.set_enable_game_builder(false);
-            game_view
-                .with_game_mut(|current_game| *current_game = game)
-                .unwrap();
-            self.game_view = Some(game_view);
+            let game = (self.game_getter)(hand_id).unwrap();
+            self.replayer = Some(Replayer::new(game, ctx).unwrap());
         } else {
-            self.game_view = None;
+            self.replayer = None;
         }
     }
 
     fn game(&mut self, ui: &mut Ui) {
-        let mut max_rect = ui.max_rect().shrink(ui.max_rect().height() * 0.02);
-        max_rect.set_height(max_rect.height() * 0.7);
-        max_rect.set_width(max_rect.height() * 4.0 / 3.0);
+        let bounding_rect = ui.max_rect().shrink(ui.max_rect().height() * 0.02);
+        let mut game_rect = bounding_rect;
+        game_rect.set_height(game_rect.height() * 0.7);
+        game_rect.set_width(game_rect.height() * 4.0 / 3.0);
+        let controls_rect = bounding_rect.with_min_y(game_rect.bottom() + 5.0);
+        let stats_rect = bounding_rect
+            .with_min_x(game_rect.right() + 10.0)
+            .with_max_y(game_rect.bottom());
+
+        let replayer = self.replayer.as_mut().unwrap();
 
-        ui.allocate_new_ui(UiBuilder::new().max_rect(max_rect), |ui| {
-            self.game_view.as_mut().unwrap().view(ui).unwrap();
+        ui.allocate_new_ui(UiBuilder::new().max_rect(game_rect), |ui| {
+            replayer.game_view().view(ui).unwrap();
+        });
+        ui.allocate_new_ui(UiBuilder::new().max_rect(controls_rect), |ui| {
+            replayer.controls(ui).unwrap();
+        });
+        ui.allocate_new_ui(UiBuilder::new().max_rect(stats_rect), |ui| {
+            replayer.stats(ui);
         });
     }
 
@@ -119,7 +310,10 @@ impl HistoryView {
             .sense(Sense::click());
 
         if self.scroll_to_current_entry {
-            builder = builder.scroll_to_row(self.current_entry.unwrap(), Some(Align::TOP));
+            let current_entry = self.current_entry.unwrap();
+            if let Ok(row) = self.visible_entries.binary_search(&current_entry) {
+                builder = builder.scroll_to_row(row, Some(Align::TOP));
+            }
             self.scroll_to_current_entry = false;
         }
 
@@ -181,8 +375,8 @@ impl HistoryView {
 
     fn table_body(&mut self, mut body: TableBody<'_>) {
         let text_height = Self::text_height(body.ui_mut());
-        body.rows(1.5 * text_height, self.entries.len(), |row| {
-            self.table_row(row.index(), row);
+        body.rows(1.5 * text_height, self.visible_entries.len(), |row| {
+            self.table_row(self.visible_entries[row.index()], row);
         });
     }
 
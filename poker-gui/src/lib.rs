@@ -1,6 +1,10 @@
+mod action_generator_worker;
 mod card;
 pub mod card_selector;
+pub mod equity_calculator;
 pub mod game_builder;
 pub mod game_view;
 pub mod history_viewer;
 pub mod range_viewer;
+pub mod replayer;
+pub mod trainer;
@@ -1,26 +1,69 @@
-use std::{cmp, mem};
+use std::{cmp, collections::HashMap, mem, thread};
 
 use eframe::egui::{
-    text::LayoutJob, Align2, Button, Color32, Context, FontFamily, FontId, Id, Label, Painter,
-    Pos2, Rect, ScrollArea, Sense, TextFormat, Ui, Vec2, WidgetText, Window,
+    text::LayoutJob, Align2, Button, Color32, ComboBox, Context, DragValue, FontFamily, FontId, Id,
+    Label, Painter, Pos2, Rect, ScrollArea, Sense, Slider, TextEdit, TextFormat, Ui, Vec2,
+    WidgetText, Window,
 };
 
 use poker_core::{
-    range::{range_entry_frequency, RangeActionKind, RangeConfigEntry, RangeEntry, RangeTableWith},
+    cards::Cards,
+    game::{milli_big_blind_from_f64, milli_big_blind_to_f64_approximate},
+    hand::Hand,
+    range::{
+        frequency_to_f64, range_entry_frequency, RangeActionKind, RangeConfigEntry, RangeEntry,
+        RangeTable, RangeTableWith, MAX_FREQUENCY,
+    },
+    range_blockers::BlockerAnalysis,
+    range_ordering::RangeOrdering,
     rank::Rank,
+    result::Result,
 };
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum RangeValue {
     Simple(RangeTableWith<u16>),
     Full(RangeConfigEntry),
+    /// How much every hand changed, e.g. between two configs, read only.
+    Heatmap(RangeTableWith<u16>),
 }
 
+const NEW_ACTIONS: [&str; 4] = ["Check", "Call", "Bet", "Raise"];
+
+const TOP_ORDERINGS: [RangeOrdering; 6] = [
+    RangeOrdering::Equity { opponents: 1 },
+    RangeOrdering::Equity { opponents: 2 },
+    RangeOrdering::Equity { opponents: 5 },
+    RangeOrdering::Equity { opponents: 8 },
+    RangeOrdering::Sklansky,
+    RangeOrdering::Chen,
+];
+
 #[derive(Debug, Clone)]
 pub struct RangeViewer {
     ranges: Vec<RangeValue>,
     selected: usize,
     details: String,
+    editable: bool,
+    /// Paints the total range if not set.
+    paint_action: Option<RangeActionKind>,
+    /// In percent.
+    paint_frequency: f64,
+    painting: Option<RangeEntry>,
+    combo_entry: Option<RangeEntry>,
+    action_colors: HashMap<RangeActionKind, Color32>,
+    new_action: usize,
+    new_action_amount: f64,
+    range_text: String,
+    top_ordering: RangeOrdering,
+    /// In percent.
+    top_percent: f64,
+    /// The ordering computed in the background, the top hands are imported when it is done.
+    top_pending: Option<RangeOrdering>,
+    /// The hero hand and board of the blocker analysis, as text.
+    blocker_hand: String,
+    blocker_board: String,
+    error: String,
 }
 
 impl RangeViewer {
@@ -29,9 +72,32 @@ impl RangeViewer {
             ranges: vec![RangeValue::Full(RangeConfigEntry::default())],
             selected: 0,
             details: String::new(),
+            editable: false,
+            paint_action: None,
+            paint_frequency: 100.0,
+            painting: None,
+            combo_entry: None,
+            action_colors: HashMap::new(),
+            new_action: 3,
+            new_action_amount: 2.5,
+            range_text: String::new(),
+            top_ordering: RangeOrdering::default(),
+            top_percent: 20.0,
+            top_pending: None,
+            blocker_hand: String::new(),
+            blocker_board: String::new(),
+            error: String::new(),
         }
     }
 
+    /// Click or drag on the grid to paint the range with the selected
+    /// frequency, right click an entry to edit its combos.
+    pub fn set_editable(&mut self, editable: bool) {
+        self.editable = editable;
+        self.painting = None;
+        self.combo_entry = None;
+    }
+
     pub fn selected(&self) -> usize {
         self.selected
     }
@@ -82,6 +148,11 @@ impl RangeViewer {
         self.details = details;
     }
 
+    /// The board of the blocker analysis, the hero hand is entered below the grid.
+    pub fn set_blocker_board(&mut self, board: Cards) {
+        self.blocker_board = board.to_string();
+    }
+
     pub fn window(&mut self, ctx: &Context, id: Id, title: String) {
         Window::new(title)
             .id(id)
@@ -103,10 +174,18 @@ impl RangeViewer {
 
         self.navigation_bar(ui);
 
+        if let RangeValue::Full(_) = &self.ranges[self.selected] {
+            self.blockers_bar(ui);
+        }
+
+        if self.editable {
+            self.editor(ui);
+        }
+
         self.details_text(ui);
     }
 
-    fn draw_range(&self, ui: &mut Ui, bounding_rect: Rect) {
+    fn draw_range(&mut self, ui: &mut Ui, bounding_rect: Rect) {
         assert!((bounding_rect.width() - bounding_rect.height()).abs() <= 0.1);
 
         let field_size = bounding_rect.width() / Rank::COUNT as f32;
@@ -114,6 +193,17 @@ impl RangeViewer {
 
         let action_kinds = self.action_kinds_colors();
 
+        let sense = if self.editable {
+            Sense::click_and_drag()
+        } else {
+            Sense::hover()
+        };
+        let primary_down = ui.input(|i| i.pointer.primary_down());
+        if !primary_down {
+            self.painting = None;
+        }
+        let mut paint_entry = None;
+
         for (row_index, row) in Rank::RANKS.into_iter().rev().enumerate() {
             for (column_index, column) in Rank::RANKS.into_iter().rev().enumerate() {
                 let left_corner = Pos2 {
@@ -125,10 +215,33 @@ impl RangeViewer {
                 let entry = RangeEntry::from_row_column(row, column);
                 self.draw_entry(&ui.painter_at(field_rect), entry, &action_kinds);
 
-                ui.allocate_rect(field_rect, Sense::hover())
+                let response = ui
+                    .allocate_rect(field_rect, sense)
                     .on_hover_ui(|ui| self.entry_hover_label(ui, entry, &action_kinds));
+
+                if !self.editable {
+                    continue;
+                }
+
+                // Dragging starts on one field, so the other fields only
+                // notice the pointer being above them.
+                let started = primary_down && response.is_pointer_button_down_on();
+                let dragged_over = self.painting.is_some() && response.contains_pointer();
+                if (started || dragged_over) && self.painting != Some(entry) {
+                    paint_entry = Some(entry);
+                }
+                if response.secondary_clicked() {
+                    self.combo_entry = Some(entry);
+                }
             }
         }
+
+        if let Some(entry) = paint_entry {
+            self.painting = Some(entry);
+            let hands: Vec<_> = entry.iter_hands().collect();
+            self.paint(entry, &hands);
+            ui.ctx().request_repaint();
+        }
     }
 
     fn draw_entry(
@@ -137,51 +250,105 @@ impl RangeViewer {
         entry: RangeEntry,
         action_kinds: &[(RangeActionKind, Color32)],
     ) {
-        let field_rect = painter.clip_rect();
+        let (height_percent, frequencies) = match &self.ranges[self.selected] {
+            RangeValue::Simple(range) => (
+                range_entry_frequency(range, entry),
+                vec![(1.0, Color32::WHITE)],
+            ),
+            RangeValue::Heatmap(range) => (
+                1.0,
+                vec![(1.0, Self::heat_color(range_entry_frequency(range, entry)))],
+            ),
+            RangeValue::Full(range) => (
+                range.total_entry_frequency(entry),
+                action_kinds
+                    .iter()
+                    .map(|(action, color)| (range.entry_frequency(*action, entry), *color))
+                    .collect(),
+            ),
+        };
 
-        let range = &self.ranges[self.selected];
+        Self::draw_field(
+            painter,
+            &entry.to_regular_string(),
+            height_percent,
+            &frequencies,
+        );
+    }
 
-        let height_percent = match range {
-            RangeValue::Simple(range) => range_entry_frequency(range, entry),
-            RangeValue::Full(range) => range.total_entry_frequency(entry),
+    fn draw_hand(
+        &self,
+        painter: &Painter,
+        hand: Hand,
+        action_kinds: &[(RangeActionKind, Color32)],
+    ) {
+        let (height_percent, frequencies) = match &self.ranges[self.selected] {
+            RangeValue::Simple(range) => {
+                (frequency_to_f64(range[hand]), vec![(1.0, Color32::WHITE)])
+            }
+            RangeValue::Heatmap(range) => (
+                1.0,
+                vec![(1.0, Self::heat_color(frequency_to_f64(range[hand])))],
+            ),
+            RangeValue::Full(range) => (
+                frequency_to_f64(range.total_range()[hand]),
+                action_kinds
+                    .iter()
+                    .map(|(action, color)| {
+                        (
+                            frequency_to_f64(range.hand_frequency(*action, hand)),
+                            *color,
+                        )
+                    })
+                    .collect(),
+            ),
         };
 
-        let top = field_rect.bottom() - height_percent as f32 * field_rect.height();
+        Self::draw_field(painter, &hand.to_string(), height_percent, &frequencies);
+    }
 
-        match range {
-            RangeValue::Simple(_) => {
-                let frequency_rect = Rect::from_two_pos(
-                    Pos2::new(field_rect.left(), top),
-                    Pos2::new(field_rect.right(), field_rect.bottom()),
-                );
+    /// Dark gray for unchanged hands up to red for hands changed completely.
+    fn heat_color(change: f64) -> Color32 {
+        let change = change.clamp(0.0, 1.0);
+        Color32::from_rgb(
+            (40.0 + change * 215.0) as u8,
+            (40.0 - change * 40.0) as u8,
+            (40.0 - change * 40.0) as u8,
+        )
+    }
 
-                painter.rect_filled(frequency_rect, 0.0, Color32::WHITE);
-            }
-            RangeValue::Full(range) => {
-                let mut left = field_rect.left();
+    /// The height shows the frequency in the range,
+    /// the width is split by the action frequencies.
+    fn draw_field(
+        painter: &Painter,
+        text: &str,
+        height_percent: f64,
+        frequencies: &[(f64, Color32)],
+    ) {
+        let field_rect = painter.clip_rect();
 
-                for (action, color) in action_kinds.iter().copied() {
-                    let frequency = range.entry_frequency(action, entry);
+        let top = field_rect.bottom() - height_percent as f32 * field_rect.height();
+        let mut left = field_rect.left();
 
-                    let right = left + frequency as f32 * field_rect.width();
-                    let frequency_rect = Rect::from_two_pos(
-                        Pos2::new(left, top),
-                        Pos2::new(right, field_rect.bottom()),
-                    );
+        for (frequency, color) in frequencies.iter().copied() {
+            let right = left + frequency as f32 * field_rect.width();
+            let frequency_rect =
+                Rect::from_two_pos(Pos2::new(left, top), Pos2::new(right, field_rect.bottom()));
 
-                    painter.rect_filled(frequency_rect, 0.0, color);
+            painter.rect_filled(frequency_rect, 0.0, color);
 
-                    left = right;
-                }
-            }
+            left = right;
         }
 
         painter.rect_filled(field_rect, 0, Color32::from_black_alpha(80));
         painter.text(
             field_rect.center(),
             Align2::CENTER_CENTER,
-            entry.to_regular_string(),
-            FontId::new(field_rect.width() / 3.0, FontFamily::Monospace),
+            text,
+            FontId::new(
+                field_rect.width() / text.len().max(3) as f32,
+                FontFamily::Monospace,
+            ),
             Color32::WHITE,
         );
     }
@@ -198,6 +365,15 @@ impl RangeViewer {
                 let text = format!("{}: {:.1}", entry.to_regular_string(), frequency * 100.0);
                 WidgetText::from(text)
             }
+            RangeValue::Heatmap(range) => {
+                let change = range_entry_frequency(range, entry);
+                let text = format!(
+                    "{}: {:.1}% changed",
+                    entry.to_regular_string(),
+                    change * 100.0
+                );
+                WidgetText::from(text)
+            }
             RangeValue::Full(range) => {
                 let mut job = LayoutJob::default();
 
@@ -216,6 +392,10 @@ impl RangeViewer {
 
                     let frequency = range.entry_frequency(action, entry);
                     let mut frequency_text = format!(": {:.1}%", frequency * 100.0);
+                    if let Some(ev) = range.entry_ev(action, entry) {
+                        frequency_text +=
+                            &format!(" ({:.2} bb)", milli_big_blind_to_f64_approximate(ev));
+                    }
 
                     if index < action_kinds.len() - 1 {
                         frequency_text += "; ";
@@ -268,6 +448,10 @@ impl RangeViewer {
                     Color32::from_rgb(bet_raise_red_value, 0, 0)
                 }
             };
+
+            if let Some(custom_color) = self.action_colors.get(action) {
+                *color = *custom_color;
+            }
         }
 
         actions
@@ -297,15 +481,410 @@ impl RangeViewer {
         }
     }
 
+    fn editor(&mut self, ui: &mut Ui) {
+        ui.separator();
+
+        let action_kinds = self.action_kinds_colors();
+
+        ui.horizontal(|ui| {
+            if let RangeValue::Full(_) = &self.ranges[self.selected] {
+                let paint_text = self
+                    .paint_action
+                    .map(|action| action.to_string())
+                    .unwrap_or_else(|| "Range".to_owned());
+                ComboBox::from_id_salt("paint_action")
+                    .selected_text(paint_text)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.paint_action, None, "Range");
+                        for (action, _) in &action_kinds {
+                            ui.selectable_value(
+                                &mut self.paint_action,
+                                Some(*action),
+                                action.to_string(),
+                            );
+                        }
+                    });
+            }
+
+            ui.add(Slider::new(&mut self.paint_frequency, 0.0..=100.0).suffix("%"));
+
+            if ui.button("+").on_hover_text("New range").clicked() {
+                self.ranges
+                    .push(RangeValue::Full(RangeConfigEntry::default()));
+                self.selected = self.ranges.len() - 1;
+            }
+            if ui
+                .add_enabled(self.ranges.len() > 1, Button::new("-"))
+                .on_hover_text("Remove range")
+                .clicked()
+            {
+                self.remove_range(self.selected);
+            }
+        });
+
+        if let RangeValue::Full(_) = &self.ranges[self.selected] {
+            self.actions_editor(ui, &action_kinds);
+        }
+
+        if let Some(entry) = self.combo_entry {
+            self.combo_editor(ui, entry, &action_kinds);
+        }
+
+        ui.horizontal(|ui| {
+            if ui
+                .button("Export")
+                .on_hover_text("Hands of the painted range or action, without frequencies")
+                .clicked()
+            {
+                self.range_text = self.export_text();
+            }
+            if ui
+                .button("Import")
+                .on_hover_text("Replace the painted range or action with the selected frequency")
+                .clicked()
+            {
+                self.error = match self.import_text() {
+                    Ok(()) => String::new(),
+                    Err(err) => err.to_string(),
+                };
+            }
+        });
+        ui.horizontal(|ui| {
+            ComboBox::from_id_salt("top_ordering")
+                .selected_text(self.top_ordering.to_string())
+                .show_ui(ui, |ui| {
+                    for ordering in TOP_ORDERINGS {
+                        ui.selectable_value(&mut self.top_ordering, ordering, ordering.to_string());
+                    }
+                });
+
+            let slider = ui
+                .add(Slider::new(&mut self.top_percent, 0.0..=100.0).suffix("%"))
+                .on_hover_text(
+                    "Replace the painted range or action with the strongest hands, \
+                     the equity orderings are computed in the background the first time",
+                );
+            if slider.changed() {
+                self.request_top(ui.ctx());
+            }
+            self.poll_top();
+
+            if self.top_pending.is_some() {
+                ui.spinner();
+            }
+            ui.label(format!("{:.1}% of hands", self.painted_percent()));
+        });
+
+        ui.add(
+            TextEdit::multiline(&mut self.range_text)
+                .hint_text("QQ+,AKs,AhKd")
+                .desired_rows(2)
+                .desired_width(f32::INFINITY),
+        );
+
+        if !self.error.is_empty() {
+            ui.label(&self.error);
+        }
+    }
+
+    fn actions_editor(&mut self, ui: &mut Ui, action_kinds: &[(RangeActionKind, Color32)]) {
+        let mut remove_action = None;
+
+        ui.horizontal_wrapped(|ui| {
+            for (action, color) in action_kinds {
+                let mut color = *color;
+                if ui.color_edit_button_srgba(&mut color).changed() {
+                    self.action_colors.insert(*action, color);
+                }
+                ui.label(action.to_string());
+                if *action != RangeActionKind::Fold && ui.small_button("x").clicked() {
+                    remove_action = Some(*action);
+                }
+            }
+        });
+
+        let mut add_action = None;
+
+        ui.horizontal(|ui| {
+            ComboBox::from_id_salt("new_action")
+                .selected_text(NEW_ACTIONS[self.new_action])
+                .show_ui(ui, |ui| {
+                    for (index, name) in NEW_ACTIONS.into_iter().enumerate() {
+                        ui.selectable_value(&mut self.new_action, index, name);
+                    }
+                });
+
+            let has_amount = self.new_action >= 2;
+            if has_amount {
+                ui.add(
+                    DragValue::new(&mut self.new_action_amount)
+                        .speed(0.1)
+                        .range(0.0..=1_000.0)
+                        .suffix(" bb"),
+                );
+            }
+
+            if ui.button("Add action").clicked() {
+                add_action = Some(match self.new_action {
+                    0 => Ok(RangeActionKind::Check),
+                    1 => Ok(RangeActionKind::Call),
+                    2 => milli_big_blind_from_f64(self.new_action_amount).map(RangeActionKind::Bet),
+                    _ => {
+                        milli_big_blind_from_f64(self.new_action_amount).map(RangeActionKind::Raise)
+                    }
+                });
+            }
+        });
+
+        let RangeValue::Full(range) = &mut self.ranges[self.selected] else {
+            return;
+        };
+
+        let result = if let Some(action) = remove_action {
+            if self.paint_action == Some(action) {
+                self.paint_action = None;
+            }
+            range.remove_action(action)
+        } else if let Some(action) = add_action {
+            action.and_then(|action| {
+                range.add_action(action)?;
+                self.paint_action = Some(action);
+                Ok(())
+            })
+        } else {
+            return;
+        };
+
+        self.error = match result {
+            Ok(()) => String::new(),
+            Err(err) => err.to_string(),
+        };
+    }
+
+    fn combo_editor(
+        &mut self,
+        ui: &mut Ui,
+        entry: RangeEntry,
+        action_kinds: &[(RangeActionKind, Color32)],
+    ) {
+        ui.horizontal(|ui| {
+            ui.label(format!("{} combos", entry.to_regular_string()));
+            if ui.small_button("x").clicked() {
+                self.combo_entry = None;
+            }
+        });
+
+        let hands: Vec<_> = entry.iter_hands().collect();
+        // Space for the 12 offsuit combos.
+        let field_size = Vec2::splat(ui.available_width() / 12.0 - ui.spacing().item_spacing.x);
+        let mut paint_hand = None;
+
+        ui.horizontal(|ui| {
+            for hand in hands {
+                let (rect, response) = ui.allocate_exact_size(field_size, Sense::click());
+                self.draw_hand(&ui.painter_at(rect), hand, action_kinds);
+                if response.clicked() {
+                    paint_hand = Some(hand);
+                }
+            }
+        });
+
+        if let Some(hand) = paint_hand {
+            self.paint(entry, &[hand]);
+        }
+    }
+
+    fn paint_frequency(&self) -> u16 {
+        (self.paint_frequency / 100.0 * f64::from(MAX_FREQUENCY)).round() as u16
+    }
+
+    fn paint(&mut self, entry: RangeEntry, hands: &[Hand]) {
+        let frequency = self.paint_frequency();
+        let whole_entry = hands.len() == usize::from(entry.combo_count());
+
+        let result = match &mut self.ranges[self.selected] {
+            RangeValue::Simple(range) => {
+                for hand in hands {
+                    range[*hand] = frequency;
+                }
+                Ok(())
+            }
+            RangeValue::Heatmap(_) => Err("range viewer: heatmaps are read only".into()),
+            RangeValue::Full(range) => match self.paint_action {
+                None if whole_entry => range.update_entry_total_frequency(entry, frequency),
+                Some(action) if whole_entry => {
+                    range.update_entry_action_frequency(entry, action, frequency)
+                }
+                None => hands
+                    .iter()
+                    .try_for_each(|hand| range.update_hand_total_frequency(*hand, frequency)),
+                Some(action) => hands.iter().try_for_each(|hand| {
+                    range.update_hand_action_frequency(*hand, action, frequency)
+                }),
+            },
+        };
+
+        self.error = match result {
+            Ok(()) => String::new(),
+            Err(err) => err.to_string(),
+        };
+    }
+
+    /// The painted range or action.
+    fn painted_range(&self) -> RangeTableWith<u16> {
+        match (&self.ranges[self.selected], self.paint_action) {
+            (RangeValue::Simple(range) | RangeValue::Heatmap(range), _) => range.clone(),
+            (RangeValue::Full(range), None) => range.total_range().clone(),
+            (RangeValue::Full(range), Some(action)) => {
+                range.action_range(action).unwrap_or_default()
+            }
+        }
+    }
+
+    /// The weighted share of all hands in the painted range or action.
+    fn painted_percent(&self) -> f64 {
+        let range = self.painted_range();
+        let combos: f64 = range
+            .iter()
+            .map(|(_, frequency)| frequency_to_f64(*frequency))
+            .sum();
+        combos / Hand::COUNT as f64 * 100.0
+    }
+
+    fn export_text(&self) -> String {
+        RangeTable::from_frequencies_not_zero(&self.painted_range()).to_string()
+    }
+
+    fn import_text(&mut self) -> Result<()> {
+        let table = RangeTable::parse_weighted(&self.range_text)?;
+        self.replace_painted(table)
+    }
+
+    /// Imports the top hands right away if the ordering is cached,
+    /// otherwise once it was computed on another thread.
+    fn request_top(&mut self, ctx: &Context) {
+        let ordering = self.top_ordering;
+        if ordering.cached_entries().is_some() {
+            self.top_pending = None;
+            self.error = match self.import_top() {
+                Ok(()) => String::new(),
+                Err(err) => err.to_string(),
+            };
+            return;
+        }
+
+        if self.top_pending != Some(ordering) {
+            let ctx = ctx.clone();
+            thread::spawn(move || {
+                // The result is cached, errors are reported by `poll_top`.
+                let _ = ordering.entries();
+                ctx.request_repaint();
+            });
+            self.top_pending = Some(ordering);
+        }
+    }
+
+    fn poll_top(&mut self) {
+        let Some(ordering) = self.top_pending else {
+            return;
+        };
+        if ordering.cached_entries().is_none() {
+            return;
+        }
+
+        self.top_pending = None;
+        // The ordering might have been changed in the meantime.
+        if ordering == self.top_ordering {
+            self.error = match self.import_top() {
+                Ok(()) => String::new(),
+                Err(err) => err.to_string(),
+            };
+        }
+    }
+
+    fn import_top(&mut self) -> Result<()> {
+        let top = self.top_ordering.top(self.top_percent)?;
+        let mut table = RangeTableWith::default();
+        top.for_each_hand(|hand| table[hand] = MAX_FREQUENCY);
+        self.replace_painted(table)
+    }
+
+    /// Weights in the table scale the selected frequency.
+    fn replace_painted(&mut self, mut table: RangeTableWith<u16>) -> Result<()> {
+        let frequency = u32::from(self.paint_frequency());
+        for (_, weight) in table.iter_mut() {
+            *weight = (u32::from(*weight) * frequency / u32::from(MAX_FREQUENCY)) as u16;
+        }
+
+        match &mut self.ranges[self.selected] {
+            RangeValue::Simple(range) => *range = table,
+            RangeValue::Heatmap(_) => return Err("range viewer: heatmaps are read only".into()),
+            RangeValue::Full(range) => {
+                for hand in &RangeTable::FULL {
+                    let frequency = table[hand];
+                    match self.paint_action {
+                        None => range.update_hand_total_frequency(hand, frequency)?,
+                        Some(action) => {
+                            range.update_hand_action_frequency(hand, action, frequency)?
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn blockers_bar(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Blockers");
+            ui.add(
+                TextEdit::singleline(&mut self.blocker_hand)
+                    .hint_text("AhKd")
+                    .desired_width(48.0),
+            );
+            ui.add(
+                TextEdit::singleline(&mut self.blocker_board)
+                    .hint_text("Board")
+                    .desired_width(90.0),
+            );
+        });
+    }
+
+    fn blockers_text(&self) -> Option<String> {
+        let RangeValue::Full(range) = &self.ranges[self.selected] else {
+            return None;
+        };
+        if self.blocker_hand.trim().is_empty() {
+            return None;
+        }
+
+        let analysis = self.blocker_hand.trim().parse::<Hand>().and_then(|hero| {
+            let board = Cards::from_str(self.blocker_board.trim())?;
+            BlockerAnalysis::new(hero, board, range)
+        });
+        Some(match analysis {
+            Ok(analysis) => analysis.to_string(),
+            Err(err) => err.to_string(),
+        })
+    }
+
     fn details_text(&self, ui: &mut Ui) {
-        if self.details.is_empty() {
+        let blockers = self.blockers_text();
+        if self.details.is_empty() && blockers.is_none() {
             return;
         }
 
         ui.separator();
 
-        ScrollArea::vertical().stick_to_bottom(true).show(ui, |ui| {
-            ui.add_sized(ui.available_size(), Label::new(&self.details));
-        });
+        if let Some(blockers) = blockers {
+            ui.label(blockers);
+        }
+
+        if !self.details.is_empty() {
+            ScrollArea::vertical().stick_to_bottom(true).show(ui, |ui| {
+                ui.add_sized(ui.available_size(), Label::new(&self.details));
+            });
+        }
     }
 }
@@ -0,0 +1,384 @@
+use std::{
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+use eframe::egui::{Button, Context, Grid, RichText, ScrollArea, Slider, Ui};
+
+use poker_core::{
+    cards::Cards,
+    equity::Equity,
+    game::{Action, Game, Street},
+    hand::Hand,
+    range::RangeTable,
+    result::Result,
+};
+
+use crate::game_view::GameView;
+
+struct StreetEquities {
+    street: Street,
+    /// The players with known hands still in the hand at the start of the street.
+    equities: Vec<(usize, Equity)>,
+}
+
+struct TimelineEntry {
+    step: usize,
+    label: String,
+}
+
+/// Plays back a finished game step by step.
+pub struct Replayer {
+    game_view: GameView,
+    hands: Vec<(usize, Hand)>,
+    step_count: usize,
+    timeline: Vec<TimelineEntry>,
+    playing: bool,
+    /// In steps per second.
+    speed: f64,
+    last_step_time: f64,
+    equities: Vec<StreetEquities>,
+    equity_receiver: Option<Receiver<Vec<StreetEquities>>>,
+}
+
+impl Replayer {
+    pub fn new(mut game: Game, ctx: &Context) -> Result<Self> {
+        game.rewind();
+
+        let hands = (0..game.player_count())
+            .filter_map(|player| game.get_hand(player).map(|hand| (player, hand)))
+            .collect();
+
+        let mut timeline = Vec::new();
+        let mut step_game = game.clone();
+        let mut step_count = 0;
+        while step_game.next() {
+            step_count += 1;
+            if let Some(action) = step_game.actions().last().copied() {
+                if step_game.actions().len() > timeline.len() {
+                    timeline.push(TimelineEntry {
+                        step: step_count,
+                        label: Self::action_label(&step_game, action),
+                    });
+                }
+            }
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        let equity_game = game.clone();
+        let equity_ctx = ctx.clone();
+        thread::spawn(move || {
+            let _ = sender.send(Self::street_equities(equity_game));
+            equity_ctx.request_repaint();
+        });
+
+        let mut game_view = GameView::new();
+        game_view.set_enable_game_builder(false);
+        game_view.with_game_mut(|current_game| *current_game = game)?;
+
+        Ok(Self {
+            game_view,
+            hands,
+            step_count,
+            timeline,
+            playing: false,
+            speed: 1.0,
+            last_step_time: 0.0,
+            equities: Vec::new(),
+            equity_receiver: Some(receiver),
+        })
+    }
+
+    pub fn game_view(&mut self) -> &mut GameView {
+        &mut self.game_view
+    }
+
+    pub fn controls(&mut self, ui: &mut Ui) -> Result<()> {
+        let mut step = self.current_step();
+
+        ui.horizontal(|ui| {
+            let play_text = if self.playing { "⏸" } else { "▶" };
+            if ui
+                .add_enabled(step < self.step_count, Button::new(play_text))
+                .clicked()
+            {
+                self.playing = !self.playing;
+                self.last_step_time = ui.input(|i| i.time);
+            }
+
+            ui.label("Speed:");
+            ui.add(
+                Slider::new(&mut self.speed, 0.25..=4.0)
+                    .logarithmic(true)
+                    .suffix("/s"),
+            );
+            ui.separator();
+
+            ui.spacing_mut().slider_width = ui.available_width() - 50.0;
+            ui.add(Slider::new(&mut step, 0..=self.step_count));
+        });
+
+        ScrollArea::vertical()
+            .id_salt("replayer_timeline")
+            .show(ui, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    let current = self.timeline.iter().rposition(|entry| entry.step <= step);
+                    for (index, entry) in self.timeline.iter().enumerate() {
+                        if ui
+                            .selectable_label(current == Some(index), &entry.label)
+                            .clicked()
+                        {
+                            step = entry.step;
+                        }
+                    }
+                });
+            });
+
+        if self.playing {
+            let time = ui.input(|i| i.time);
+            if step >= self.step_count {
+                self.playing = false;
+            } else if time - self.last_step_time >= 1.0 / self.speed {
+                step += 1;
+                self.last_step_time = time;
+            }
+            ui.ctx().request_repaint();
+        }
+
+        if step != self.current_step() {
+            self.seek(step)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn stats(&mut self, ui: &mut Ui) {
+        self.poll_equities();
+
+        let game = self.game_view.game();
+        let pot = game.total_pot();
+
+        Grid::new("replayer_stats").num_columns(2).show(ui, |ui| {
+            ui.label("Pot:");
+            ui.label(pot.to_string());
+            ui.end_row();
+
+            if let Some((action, percent)) = Self::last_bet_size(game) {
+                ui.label(format!("{action}:"));
+                ui.label(format!("{:.0}% pot", percent * 100.0));
+                ui.end_row();
+            }
+
+            if let Some(call) = game.can_call() {
+                let pot_odds = f64::from(call) / f64::from(pot + call);
+                ui.label("Pot odds:");
+                ui.label(format!("{:.1}% ({call} to call)", pot_odds * 100.0));
+                ui.end_row();
+            }
+
+            if let Some(spr) = Self::stack_to_pot_ratio(game) {
+                ui.label("SPR:");
+                ui.label(format!("{spr:.2}"));
+                ui.end_row();
+            }
+        });
+
+        ui.separator();
+        self.equity_table(ui);
+    }
+
+    fn equity_table(&self, ui: &mut Ui) {
+        if self.hands.len() < 2 {
+            ui.label("Equity needs at least two known hands.");
+            return;
+        }
+        if self.equity_receiver.is_some() {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Calculating equity...");
+            });
+            return;
+        }
+
+        let game = self.game_view.game();
+        let current_street = game.board().street();
+
+        Grid::new("replayer_equity")
+            .num_columns(self.equities.len() + 2)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Player");
+                ui.strong("Hand");
+                for street_equities in &self.equities {
+                    let text = RichText::new(street_equities.street.to_string());
+                    if street_equities.street == current_street {
+                        ui.label(text.strong().underline());
+                    } else {
+                        ui.label(text.strong());
+                    }
+                }
+                ui.end_row();
+
+                for (player, hand) in &self.hands {
+                    ui.label(game.player_name(*player));
+                    ui.label(hand.to_string());
+                    for street_equities in &self.equities {
+                        let equity = street_equities
+                            .equities
+                            .iter()
+                            .find(|(current_player, _)| current_player == player)
+                            .map(|(_, equity)| equity.equity_percent());
+                        let text = match equity {
+                            Some(equity) => format!("{:.1}%", equity * 100.0),
+                            None => "-".to_owned(),
+                        };
+                        if street_equities.street == current_street {
+                            ui.label(RichText::new(text).strong());
+                        } else {
+                            ui.label(text);
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+    }
+
+    fn poll_equities(&mut self) {
+        let Some(receiver) = self.equity_receiver.as_ref() else {
+            return;
+        };
+
+        match receiver.try_recv() {
+            Ok(equities) => {
+                self.equities = equities;
+                self.equity_receiver = None;
+            }
+            Err(TryRecvError::Empty) => {}
+            Err(TryRecvError::Disconnected) => self.equity_receiver = None,
+        }
+    }
+
+    fn current_step(&self) -> usize {
+        let mut game = self.game_view.game().clone();
+        let mut step = 0;
+        while game.previous() {
+            step += 1;
+        }
+        step
+    }
+
+    fn seek(&mut self, step: usize) -> Result<()> {
+        self.game_view.with_game_mut(|game| {
+            game.rewind();
+            for _ in 0..step {
+                game.next();
+            }
+        })
+    }
+
+    fn action_label(game: &Game, action: Action) -> String {
+        if let Some(street) = action.street() {
+            return street.to_string();
+        }
+
+        let Some(player) = action.player() else {
+            return action.kind_str().to_owned();
+        };
+
+        let name = game.player_name(player);
+        match action {
+            Action::Post { amount, .. }
+            | Action::Straddle { amount, .. }
+            | Action::Call { amount, .. }
+            | Action::Bet { amount, .. } => format!("{name} {} {amount}", action.kind_str()),
+            Action::Raise { to, .. } => format!("{name} {} {to}", action.kind_str()),
+            _ => format!("{name} {}", action.kind_str()),
+        }
+    }
+
+    /// The size of the last bet or raise relative to the pot before it.
+    fn last_bet_size(game: &Game) -> Option<(&'static str, f64)> {
+        let action = game.actions().last().copied()?;
+        let (Action::Bet { .. } | Action::Raise { .. }) = action else {
+            return None;
+        };
+
+        let mut previous_game = game.clone();
+        assert!(previous_game.previous());
+        let pot_before = previous_game.total_pot();
+        if pot_before == 0 {
+            return None;
+        }
+
+        let added = game.total_pot() - pot_before;
+        Some((action.kind_str(), f64::from(added) / f64::from(pot_before)))
+    }
+
+    /// The effective stack of the current player divided by the pot.
+    fn stack_to_pot_ratio(game: &Game) -> Option<f64> {
+        let player = game.current_player()?;
+        let pot = game.total_pot();
+        if pot == 0 {
+            return None;
+        }
+
+        let stacks = game.current_street_stacks();
+        let villain_stack = game
+            .players_not_folded()
+            .filter(|villain| *villain != player)
+            .map(|villain| stacks[villain])
+            .max()?;
+        let effective_stack = stacks[player].min(villain_stack);
+
+        Some(f64::from(effective_stack) / f64::from(pot))
+    }
+
+    fn street_equities(mut game: Game) -> Vec<StreetEquities> {
+        game.rewind();
+
+        let mut out = Vec::new();
+        let mut street = None;
+        loop {
+            let current_street = game.board().street();
+            // Later runouts restart at earlier streets.
+            if street.is_none_or(|street: Street| current_street.to_usize() > street.to_usize()) {
+                street = Some(current_street);
+                if let Some(equities) = Self::equities(&game) {
+                    out.push(StreetEquities {
+                        street: current_street,
+                        equities,
+                    });
+                }
+            }
+
+            if !game.next() {
+                break;
+            }
+        }
+
+        out
+    }
+
+    fn equities(game: &Game) -> Option<Vec<(usize, Equity)>> {
+        let mut players = Vec::new();
+        let mut ranges = Vec::new();
+        for player in game.players_not_folded() {
+            let Some(hand) = game.get_hand(player) else {
+                continue;
+            };
+
+            let mut range = Box::new(RangeTable::EMPTY);
+            range.add_hand(hand);
+            players.push(player);
+            ranges.push(range);
+        }
+
+        if ranges.len() < 2 {
+            return None;
+        }
+
+        let board = Cards::from_slice(game.board().cards())?;
+        let equities = Equity::enumerate(board, &ranges)?;
+        Some(players.into_iter().zip(equities).collect())
+    }
+}
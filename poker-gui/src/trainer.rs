@@ -0,0 +1,119 @@
+use eframe::egui::{Color32, Context, Grid, Id, RichText, ScrollArea, Ui, Window};
+
+use poker_core::trainer::{Decision, SpotStats, TrainerSession};
+
+pub struct Trainer {
+    session: TrainerSession,
+}
+
+impl Default for Trainer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Trainer {
+    pub fn new() -> Self {
+        Self {
+            session: TrainerSession::new(),
+        }
+    }
+
+    pub fn add(&mut self, decision: Decision) {
+        self.session.add(decision);
+    }
+
+    pub fn window(&mut self, ctx: &Context, title: String) {
+        Window::new(title)
+            .id(Id::new("trainer"))
+            .resizable([true, true])
+            .default_size([400.0, 500.0])
+            .show(ctx, |ui| self.view(ui));
+    }
+
+    pub fn view(&mut self, ui: &mut Ui) {
+        if let Some(decision) = self.session.last() {
+            Self::last_decision(ui, decision);
+        } else {
+            ui.label("Play a hand, your decisions are graded against the trainer strategy.");
+        }
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            Self::stats_label(ui, &self.session.total());
+            if ui.button("Reset").clicked() {
+                self.session.clear();
+            }
+        });
+        ui.separator();
+
+        self.spots(ui);
+    }
+
+    fn last_decision(ui: &mut Ui, decision: &Decision) {
+        ui.horizontal(|ui| {
+            ui.strong(format!("{} with {}", decision.spot, decision.hand));
+            if decision.is_mistake() {
+                ui.label(RichText::new("Mistake").color(Color32::RED));
+            }
+        });
+        ui.label(format!(
+            "{} - score {:.0}%",
+            decision.action,
+            decision.score * 100.0
+        ));
+
+        Grid::new("trainer_frequencies")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                for (action, frequency) in &decision.frequencies {
+                    let text = RichText::new(action.to_string());
+                    if *action == decision.action {
+                        ui.label(text.strong());
+                    } else {
+                        ui.label(text);
+                    }
+                    ui.label(format!("{:.1}%", frequency * 100.0));
+                    ui.end_row();
+                }
+            });
+    }
+
+    fn stats_label(ui: &mut Ui, stats: &SpotStats) {
+        ui.label(format!(
+            "{} decisions, {} mistakes, average score {:.0}%",
+            stats.decisions,
+            stats.mistakes,
+            stats.average_score() * 100.0
+        ));
+    }
+
+    fn spots(&self, ui: &mut Ui) {
+        let stats = self.session.spot_stats();
+        if stats.is_empty() {
+            return;
+        }
+
+        ScrollArea::vertical().show(ui, |ui| {
+            Grid::new("trainer_spots")
+                .num_columns(4)
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong("Spot");
+                    ui.strong("Decisions");
+                    ui.strong("Mistakes");
+                    ui.strong("Score");
+                    ui.end_row();
+
+                    for spot in &stats {
+                        ui.label(&spot.spot);
+                        ui.label(spot.decisions.to_string());
+                        ui.label(spot.mistakes.to_string());
+                        ui.label(format!("{:.0}%", spot.average_score() * 100.0));
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+}